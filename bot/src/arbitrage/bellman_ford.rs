@@ -2,15 +2,17 @@
 
 use anyhow::Result;
 use solana_sdk::pubkey::Pubkey;
-// ИСПРАВЛЕНИЕ: Удаляем неиспользуемый HashMap
-use std::collections::HashSet;
-use tracing::{info, debug};
+use std::time::Instant;
+use tracing::{info, debug, warn};
 
+use super::cycle_index::CandidateCycle;
 use super::graph::PriceGraph;
 
 #[derive(Debug, Clone)]
 pub struct ArbitrageCycle {
     pub tokens: Vec<Pubkey>,
+    /// Пулы для каждого перехода (tokens[i] -> tokens[i + 1])
+    pub pool_ids: Vec<Pubkey>,
     pub total_weight: f64,
 }
 
@@ -21,137 +23,89 @@ impl BellmanFordSolver {
         Self
     }
 
-    /// ИСПРАВЛЕННЫЙ поиск арбитражных циклов
+    /// Оценка кандидатных циклов по текущим весам графа:
+    /// отрицательный суммарный вес + сквозная котировка с эталонного объёма
     pub fn find_negative_cycles(
         &self,
         graph: &PriceGraph,
-        _max_legs: usize, // ИСПРАВЛЕНИЕ: Добавили префикс _
+        candidates: &[&CandidateCycle],
     ) -> Result<Vec<ArbitrageCycle>> {
-        let n = graph.token_count();
-
-        info!("🔍 === ДИАГНОСТИКА ГРАФА ===");
-        info!("Токенов в графе: {}", n);
-
-        // Диагностика: показать все рёбра графа
-        let mut total_edges = 0;
-        for from in 0..n {
-            for to in 0..n {
-                let edges = graph.get_edges(from, to);
-                if !edges.is_empty() {
-                    total_edges += edges.len();
-                    if let (Some(from_token), Some(to_token)) = (graph.get_token(from), graph.get_token(to)) {
-                        info!("   Ребро: {}... -> {}... ({} вариантов)",
-                             &from_token.to_string()[..8],
-                             &to_token.to_string()[..8],
-                             edges.len());
-                    }
-                }
-            }
-        }
-
-        info!("Всего рёбер: {}", total_edges);
+        self.find_negative_cycles_until(graph, candidates, None)
+            .map(|(cycles, _)| cycles)
+    }
 
-        if total_edges == 0 {
-            info!("❌ ГРАФ ПУСТОЙ! Нет рёбер между токенами!");
-            return Ok(vec![]);
-        }
+    /// То же с дедлайном: кандидаты проверяются от самого отрицательного веса,
+    /// после дедлайна возвращается найденное к этому моменту.
+    /// Второй элемент — true, если перебор прерван дедлайном
+    pub fn find_negative_cycles_until(
+        &self,
+        graph: &PriceGraph,
+        candidates: &[&CandidateCycle],
+        deadline: Option<Instant>,
+    ) -> Result<(Vec<ArbitrageCycle>, bool)> {
+        debug!("🔍 Граф: {} токенов, {} рёбер, {} пулов; кандидатов к переоценке: {}",
+               graph.token_count(), graph.edge_count(), graph.pool_count(), candidates.len());
+
+        // Дешёвый шаг: суммарные веса, только отрицательные
+        let mut weighted: Vec<ArbitrageCycle> = candidates.iter()
+            .filter_map(|candidate| self.weigh_cycle(graph, candidate))
+            .filter(|cycle| cycle.total_weight < -0.001)
+            .collect();
+        weighted.sort_by(|a, b| a.total_weight.total_cmp(&b.total_weight));
 
-        // Ищем циклы методом прямого поиска
+        // Дорогой шаг: сквозная котировка, пока позволяет дедлайн
         let mut cycles = Vec::new();
-        let mut found_cycles = HashSet::new();
-
-        info!("🔄 === ПОИСК ТРЕУГОЛЬНЫХ АРБИТРАЖЕЙ ===");
-
-        // Перебираем все возможные треугольники
-        for start_idx in 0..n {
-            for mid_idx in 0..n {
-                if mid_idx == start_idx { continue; }
-
-                for end_idx in 0..n {
-                    if end_idx == start_idx || end_idx == mid_idx { continue; }
-
-                    // Проверяем путь: start → mid → end → start
-                    if let Some(cycle) = self.check_triangle_arbitrage(
-                        graph, start_idx, mid_idx, end_idx
-                    )? {
-                        let cycle_signature = self.get_cycle_signature(&cycle);
-
-                        if !found_cycles.contains(&cycle_signature) {
-                            found_cycles.insert(cycle_signature);
-
-                            info!("🎯 НАЙДЕН ТРЕУГОЛЬНЫЙ АРБИТРАЖ!");
-                            info!("   Путь: {} → {} → {} → {}",
-                                 &cycle.tokens[0].to_string()[..8],
-                                 &cycle.tokens[1].to_string()[..8],
-                                 &cycle.tokens[2].to_string()[..8],
-                                 &cycle.tokens[3].to_string()[..8]);
-                            info!("   Общий вес: {:.6}", cycle.total_weight);
-
-                            if cycle.total_weight < -0.001 { // Прибыльный
-                                info!("   ✅ ПРИБЫЛЬНЫЙ!");
-                                cycles.push(cycle);
-                            } else {
-                                info!("   ❌ Не прибыльный");
-                            }
-                        }
-                    }
-                }
+        let mut interrupted = false;
+
+        for cycle in weighted {
+            if deadline.is_some_and(|d| Instant::now() >= d) {
+                warn!("⏱️ Дедлайн поиска: остановка, найдено {} циклов", cycles.len());
+                interrupted = true;
+                break;
             }
-        }
 
-        info!("🏁 Найдено арбитражных циклов: {}", cycles.len());
-        Ok(cycles)
-    }
+            debug!("🎯 Кандидат: {} шагов, вес {:.6}", cycle.pool_ids.len(), cycle.total_weight);
 
-    /// Проверяем треугольный арбитраж A→B→C→A
-    fn check_triangle_arbitrage(
-        &self,
-        graph: &PriceGraph,
-        a_idx: usize,
-        b_idx: usize,
-        c_idx: usize,
-    ) -> Result<Option<ArbitrageCycle>> {
-        // Проверяем существование всех трёх рёбер
-        let edges_ab = graph.get_edges(a_idx, b_idx);
-        let edges_bc = graph.get_edges(b_idx, c_idx);
-        let edges_ca = graph.get_edges(c_idx, a_idx);
-
-        if edges_ab.is_empty() || edges_bc.is_empty() || edges_ca.is_empty() {
-            return Ok(None); // Нет полного пути
+            if !self.survives_price_impact(graph, &cycle) {
+                debug!("   ❌ Не переживает price impact на эталонном объёме");
+            } else {
+                info!("🎯 НАЙДЕН АРБИТРАЖНЫЙ ЦИКЛ! {} шагов, вес {:.6}",
+                      cycle.pool_ids.len(), cycle.total_weight);
+                cycles.push(cycle);
+            }
         }
 
-        // Берём первое доступное ребро для каждого перехода
-        let edge_ab = &edges_ab[0];
-        let edge_bc = &edges_bc[0];
-        let edge_ca = &edges_ca[0];
-
-        // Вычисляем общий вес цикла
-        let total_weight = edge_ab.weight + edge_bc.weight + edge_ca.weight;
+        debug!("🏁 Найдено арбитражных циклов: {}", cycles.len());
+        Ok((cycles, interrupted))
+    }
 
-        debug!("   Проверка цикла {}->{}->{} = {:.6}",
-               a_idx, b_idx, c_idx, total_weight);
+    /// Суммарный вес цикла по рёбрам его пулов
+    fn weigh_cycle(&self, graph: &PriceGraph, candidate: &CandidateCycle) -> Option<ArbitrageCycle> {
+        let mut total_weight = 0.0;
 
-        let tokens = vec![
-            edge_ab.from_token,
-            edge_ab.to_token,
-            edge_bc.to_token,
-            edge_ca.to_token, // Возврат к началу
-        ];
+        for (i, pool_id) in candidate.pool_ids.iter().enumerate() {
+            total_weight += graph.pool_edge(pool_id, &candidate.tokens[i])?.weight;
+        }
 
-        Ok(Some(ArbitrageCycle {
-            tokens,
+        Some(ArbitrageCycle {
+            tokens: candidate.tokens.clone(),
+            pool_ids: candidate.pool_ids.clone(),
             total_weight,
-        }))
+        })
     }
 
-    /// Получение подписи цикла для дедупликации
-    fn get_cycle_signature(&self, cycle: &ArbitrageCycle) -> String {
-        let mut tokens_str: Vec<String> = cycle.tokens[..cycle.tokens.len()-1]
-            .iter()
-            .map(|t| t.to_string())
-            .collect();
-
-        tokens_str.sort(); // Сортируем для нормализации
-        tokens_str.join("-")
+    /// Сквозная котировка цикла с эталонного объёма: веса рёбер считаются
+    /// независимо, а здесь объём каждого шага — это выход предыдущего
+    fn survives_price_impact(&self, graph: &PriceGraph, cycle: &ArbitrageCycle) -> bool {
+        match graph.quote_cycle(&cycle.tokens, &cycle.pool_ids) {
+            Ok((initial_amount, final_amount)) => {
+                debug!("   Сквозная котировка: {} -> {}", initial_amount, final_amount);
+                final_amount > initial_amount
+            }
+            Err(e) => {
+                debug!("   Ошибка сквозной котировки: {}", e);
+                false
+            }
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,682 @@
+// bot/src/arbitrage/clmm_quote.rs
+// Котировка Raydium CLMM: пошаговая симуляция свопа через инициализированные тики
+
+use anyhow::{Context, Result};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::dex_structs::{
+    ClmmAmmConfig, ClmmPoolState, TickArrayState, TickState,
+    CLMM_AMM_CONFIG_LEN, CLMM_TICK_ARRAY_LEN, TICK_ARRAY_SIZE,
+};
+use crate::types::PoolState;
+use crate::utils::big_num::{mul_div_ceil, mul_div_floor, U512};
+
+pub const MIN_TICK: i32 = -443636;
+pub const MAX_TICK: i32 = 443636;
+pub const MIN_SQRT_PRICE_X64: u128 = 4295048016;
+pub const MAX_SQRT_PRICE_X64: u128 = 79226673521066979257578248091;
+
+/// Знаменатель trade_fee_rate в AmmConfig (1e-6)
+pub const FEE_RATE_DENOMINATOR: u32 = 1_000_000;
+
+const Q64: u128 = 1 << 64;
+const TICK_ARRAY_SEED: &[u8] = b"tick_array";
+const TICK_ARRAY_BITMAP_EXTENSION_SEED: &[u8] = b"pool_tick_array_bitmap_extension";
+/// Количество бит в tick_array_bitmap пула (16 * u64)
+const TICK_ARRAY_BITMAP_BITS: i32 = 1024;
+
+/// Результат симуляции свопа CLMM
+#[derive(Debug, Clone)]
+pub struct ClmmSwapQuote {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+    pub sqrt_price_after_x64: u128,
+    pub tick_after: i32,
+    /// TickArray аккаунты, через которые прошёл своп (в порядке обхода)
+    pub tick_arrays: Vec<Pubkey>,
+}
+
+/// Один шаг свопа внутри диапазона с постоянной ликвидностью
+#[derive(Debug, Clone, Copy)]
+struct SwapStep {
+    sqrt_price_next: u128,
+    amount_in: u128,
+    amount_out: u128,
+    fee_amount: u128,
+}
+
+/// Котировка свопа по PoolState, загруженному сканером (pool + AmmConfig + TickArray)
+pub fn quote_pool(pool: &PoolState, amount_in: u64, a_to_b: bool) -> Result<ClmmSwapQuote> {
+    let state = ClmmPoolState::try_from_slice(&pool.full_state_data)
+        .with_context(|| format!("Не удалось декодировать CLMM пул {}", pool.id))?;
+
+    // Без AmmConfig используем fee_bps пула (1 bps = 100 единиц 1e-6)
+    let mut fee_rate = pool.fee_bps as u32 * 100;
+    let mut tick_arrays = Vec::new();
+
+    for account in &pool.liquidity_accounts {
+        match account.data.len() {
+            CLMM_TICK_ARRAY_LEN => {
+                tick_arrays.push(TickArrayState::try_from_slice(account.pubkey, &account.data)?)
+            }
+            CLMM_AMM_CONFIG_LEN => {
+                fee_rate = ClmmAmmConfig::try_from_slice(&account.data)?.trade_fee_rate
+            }
+            _ => {}
+        }
+    }
+
+    let zero_for_one = if pool.token_a == state.token_mint_0 { a_to_b } else { !a_to_b };
+    simulate_swap(&state, fee_rate, &tick_arrays, amount_in, zero_for_one)
+}
+
+/// Симуляция exact-input свопа: шаги между инициализированными тиками,
+/// пересчёт ликвидности на каждом пересечении и комиссия AmmConfig
+pub fn simulate_swap(
+    pool: &ClmmPoolState,
+    fee_rate: u32,
+    tick_arrays: &[TickArrayState],
+    amount_in: u64,
+    zero_for_one: bool,
+) -> Result<ClmmSwapQuote> {
+    if amount_in == 0 {
+        anyhow::bail!("Нулевая входная сумма для CLMM свопа");
+    }
+    if fee_rate >= FEE_RATE_DENOMINATOR {
+        anyhow::bail!("Некорректная комиссия CLMM: {}", fee_rate);
+    }
+
+    let ticks_in_array = TICK_ARRAY_SIZE * pool.tick_spacing as i32;
+    let coverage_low = tick_arrays
+        .iter()
+        .map(|a| a.start_tick_index)
+        .min()
+        .context("Нет загруженных TickArray для CLMM пула")?;
+    let coverage_high = tick_arrays
+        .iter()
+        .map(|a| a.start_tick_index + ticks_in_array)
+        .max()
+        .context("Нет загруженных TickArray для CLMM пула")?;
+
+    if pool.tick_current < coverage_low || pool.tick_current >= coverage_high {
+        anyhow::bail!(
+            "Текущий тик {} вне загруженных TickArray [{}, {})",
+            pool.tick_current,
+            coverage_low,
+            coverage_high
+        );
+    }
+
+    // Все инициализированные тики загруженных массивов по возрастанию
+    let mut ticks: Vec<TickState> = tick_arrays
+        .iter()
+        .flat_map(|a| a.ticks.iter().copied())
+        .collect();
+    ticks.sort_by_key(|t| t.tick);
+
+    let sqrt_price_limit = if zero_for_one {
+        MIN_SQRT_PRICE_X64 + 1
+    } else {
+        MAX_SQRT_PRICE_X64 - 1
+    };
+
+    let mut amount_remaining = amount_in as u128;
+    let mut amount_out = 0u128;
+    let mut fee_amount = 0u128;
+    let mut sqrt_price = pool.sqrt_price_x64;
+    let mut tick = pool.tick_current;
+    let mut liquidity = pool.liquidity;
+
+    while amount_remaining > 0 && sqrt_price != sqrt_price_limit {
+        if tick < coverage_low || tick >= coverage_high {
+            anyhow::bail!("Своп вышел за пределы загруженных TickArray (тик {})", tick);
+        }
+
+        // Ближайший инициализированный тик в направлении свопа,
+        // иначе граница загруженного диапазона (без изменения ликвидности)
+        let idx = ticks.partition_point(|t| t.tick <= tick);
+        let next = if zero_for_one {
+            idx.checked_sub(1).map(|i| ticks[i])
+        } else {
+            ticks.get(idx).copied()
+        };
+        let next_tick = match next {
+            Some(t) => t.tick,
+            None if zero_for_one => coverage_low,
+            None => coverage_high,
+        }
+        .clamp(MIN_TICK, MAX_TICK);
+
+        let tick_sqrt_price = sqrt_price_at_tick(next_tick)?;
+        let target = if zero_for_one {
+            tick_sqrt_price.max(sqrt_price_limit)
+        } else {
+            tick_sqrt_price.min(sqrt_price_limit)
+        };
+
+        let step = compute_swap_step(
+            sqrt_price,
+            target,
+            liquidity,
+            amount_remaining,
+            fee_rate,
+            zero_for_one,
+        )?;
+
+        amount_remaining = amount_remaining
+            .checked_sub(step.amount_in + step.fee_amount)
+            .context("Переполнение остатка CLMM свопа")?;
+        amount_out += step.amount_out;
+        fee_amount += step.fee_amount;
+
+        if step.sqrt_price_next == tick_sqrt_price {
+            // Пересечение тика: ликвидность меняется на liquidity_net
+            if let Some(crossed) = next {
+                let liquidity_net = if zero_for_one {
+                    -crossed.liquidity_net
+                } else {
+                    crossed.liquidity_net
+                };
+                liquidity = add_liquidity_delta(liquidity, liquidity_net)?;
+            }
+            tick = if zero_for_one { next_tick - 1 } else { next_tick };
+        } else if step.sqrt_price_next != sqrt_price {
+            tick = tick_at_sqrt_price(step.sqrt_price_next)?;
+        }
+
+        sqrt_price = step.sqrt_price_next;
+    }
+
+    if amount_remaining > 0 {
+        anyhow::bail!("Недостаточно ликвидности в CLMM пуле для {} atoms", amount_in);
+    }
+
+    let touched = touched_tick_arrays(
+        tick_arrays,
+        ticks_in_array,
+        pool.tick_current,
+        tick,
+        zero_for_one,
+    );
+
+    Ok(ClmmSwapQuote {
+        amount_in,
+        amount_out: u64::try_from(amount_out).context("Переполнение выхода CLMM свопа")?,
+        fee_amount: u64::try_from(fee_amount).context("Переполнение комиссии CLMM свопа")?,
+        sqrt_price_after_x64: sqrt_price,
+        tick_after: tick,
+        tick_arrays: touched,
+    })
+}
+
+/// TickArray, покрывающие пройденный диапазон тиков, в порядке обхода.
+/// Если своп не вышел из неинициализированного массива, берём первый
+/// инициализированный массив по направлению (его требует программа).
+fn touched_tick_arrays(
+    tick_arrays: &[TickArrayState],
+    ticks_in_array: i32,
+    tick_start: i32,
+    tick_end: i32,
+    zero_for_one: bool,
+) -> Vec<Pubkey> {
+    let (low, high) = (tick_start.min(tick_end), tick_start.max(tick_end));
+
+    let mut touched: Vec<&TickArrayState> = tick_arrays
+        .iter()
+        .filter(|a| a.start_tick_index + ticks_in_array > low && a.start_tick_index <= high)
+        .collect();
+
+    if touched.is_empty() {
+        let first = if zero_for_one {
+            tick_arrays
+                .iter()
+                .filter(|a| a.start_tick_index <= tick_start)
+                .max_by_key(|a| a.start_tick_index)
+        } else {
+            tick_arrays
+                .iter()
+                .filter(|a| a.start_tick_index + ticks_in_array > tick_start)
+                .min_by_key(|a| a.start_tick_index)
+        };
+        touched.extend(first);
+    }
+
+    if zero_for_one {
+        touched.sort_by_key(|a| std::cmp::Reverse(a.start_tick_index));
+    } else {
+        touched.sort_by_key(|a| a.start_tick_index);
+    }
+
+    touched.into_iter().map(|a| a.address).collect()
+}
+
+/// Шаг свопа до target (или до исчерпания входа), как swap_math программы
+fn compute_swap_step(
+    sqrt_price_current: u128,
+    sqrt_price_target: u128,
+    liquidity: u128,
+    amount_remaining: u128,
+    fee_rate: u32,
+    zero_for_one: bool,
+) -> Result<SwapStep> {
+    let fee_rate = fee_rate as u128;
+    let denominator = FEE_RATE_DENOMINATOR as u128;
+
+    let amount_remaining_less_fee =
+        mul_div_floor(amount_remaining, denominator - fee_rate, denominator)
+            .context("Переполнение при вычете комиссии")?;
+
+    let amount_in_to_target = if zero_for_one {
+        delta_amount_0(sqrt_price_target, sqrt_price_current, liquidity, true)?
+    } else {
+        delta_amount_1(sqrt_price_current, sqrt_price_target, liquidity, true)?
+    };
+
+    let sqrt_price_next = if amount_remaining_less_fee >= amount_in_to_target {
+        sqrt_price_target
+    } else {
+        next_sqrt_price_from_input(
+            sqrt_price_current,
+            liquidity,
+            amount_remaining_less_fee,
+            zero_for_one,
+        )?
+    };
+
+    let reached_target = sqrt_price_next == sqrt_price_target;
+
+    let (amount_in, amount_out) = if zero_for_one {
+        let amount_in = if reached_target {
+            amount_in_to_target
+        } else {
+            delta_amount_0(sqrt_price_next, sqrt_price_current, liquidity, true)?
+        };
+        let amount_out = delta_amount_1(sqrt_price_next, sqrt_price_current, liquidity, false)?;
+        (amount_in, amount_out)
+    } else {
+        let amount_in = if reached_target {
+            amount_in_to_target
+        } else {
+            delta_amount_1(sqrt_price_current, sqrt_price_next, liquidity, true)?
+        };
+        let amount_out = delta_amount_0(sqrt_price_current, sqrt_price_next, liquidity, false)?;
+        (amount_in, amount_out)
+    };
+
+    // Если цель не достигнута, весь остаток входа уходит в комиссию
+    let fee_amount = if reached_target {
+        mul_div_ceil(amount_in, fee_rate, denominator - fee_rate)
+            .context("Переполнение комиссии шага")?
+    } else {
+        amount_remaining - amount_in
+    };
+
+    Ok(SwapStep {
+        sqrt_price_next,
+        amount_in,
+        amount_out,
+        fee_amount,
+    })
+}
+
+/// Δtoken0 = L · (√b − √a) / (√a · √b)
+fn delta_amount_0(sqrt_a: u128, sqrt_b: u128, liquidity: u128, round_up: bool) -> Result<u128> {
+    let (sqrt_a, sqrt_b) = if sqrt_a > sqrt_b { (sqrt_b, sqrt_a) } else { (sqrt_a, sqrt_b) };
+
+    let numerator = (U512::from(liquidity) << 64) * U512::from(sqrt_b - sqrt_a);
+    let result = if round_up {
+        div_ceil(div_ceil(numerator, U512::from(sqrt_b)), U512::from(sqrt_a))
+    } else {
+        numerator / U512::from(sqrt_b) / U512::from(sqrt_a)
+    };
+
+    result.try_to_u128().context("Переполнение Δtoken0")
+}
+
+/// Δtoken1 = L · (√b − √a)
+fn delta_amount_1(sqrt_a: u128, sqrt_b: u128, liquidity: u128, round_up: bool) -> Result<u128> {
+    let (sqrt_a, sqrt_b) = if sqrt_a > sqrt_b { (sqrt_b, sqrt_a) } else { (sqrt_a, sqrt_b) };
+
+    let result = if round_up {
+        mul_div_ceil(liquidity, sqrt_b - sqrt_a, Q64)
+    } else {
+        mul_div_floor(liquidity, sqrt_b - sqrt_a, Q64)
+    };
+
+    result.context("Переполнение Δtoken1")
+}
+
+/// Новая √P после добавления входа (округление в пользу пула)
+fn next_sqrt_price_from_input(
+    sqrt_price: u128,
+    liquidity: u128,
+    amount_in: u128,
+    zero_for_one: bool,
+) -> Result<u128> {
+    if amount_in == 0 {
+        return Ok(sqrt_price);
+    }
+    if liquidity == 0 {
+        anyhow::bail!("Нулевая ликвидность при расчёте новой цены");
+    }
+
+    let next = if zero_for_one {
+        // √P' = L·√P / (L + Δx·√P), округление вверх
+        let numerator = U512::from(liquidity) << 64;
+        let denominator = numerator + U512::from(amount_in) * U512::from(sqrt_price);
+        div_ceil(numerator * U512::from(sqrt_price), denominator)
+    } else {
+        // √P' = √P + Δy / L, округление вниз
+        U512::from(sqrt_price) + (U512::from(amount_in) << 64) / U512::from(liquidity)
+    };
+
+    next.try_to_u128().context("Переполнение √P")
+}
+
+fn div_ceil(numerator: U512, denominator: U512) -> U512 {
+    let (quotient, remainder) = numerator.div_mod(denominator);
+    if remainder.is_zero() {
+        quotient
+    } else {
+        quotient + U512::one()
+    }
+}
+
+fn add_liquidity_delta(liquidity: u128, delta: i128) -> Result<u128> {
+    if delta < 0 {
+        liquidity
+            .checked_sub(delta.unsigned_abs())
+            .context("Ликвидность CLMM ушла в минус при пересечении тика")
+    } else {
+        liquidity
+            .checked_add(delta as u128)
+            .context("Переполнение ликвидности CLMM")
+    }
+}
+
+/// √(1.0001^tick) в формате Q64.64 (tick_math программы Raydium)
+pub fn sqrt_price_at_tick(tick: i32) -> Result<u128> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        anyhow::bail!("Тик {} вне допустимого диапазона", tick);
+    }
+
+    const RATIOS: [(u32, u128); 18] = [
+        (0x2, 0xfff97272373d4000),
+        (0x4, 0xfff2e50f5f657000),
+        (0x8, 0xffe5caca7e10f000),
+        (0x10, 0xffcb9843d60f7000),
+        (0x20, 0xff973b41fa98e800),
+        (0x40, 0xff2ea16466c9b000),
+        (0x80, 0xfe5dee046a9a3800),
+        (0x100, 0xfcbe86c7900bb000),
+        (0x200, 0xf987a7253ac65800),
+        (0x400, 0xf3392b0822bb6000),
+        (0x800, 0xe7159475a2caf000),
+        (0x1000, 0xd097f3bdfd2f2000),
+        (0x2000, 0xa9f746462d9f8000),
+        (0x4000, 0x70d869a156f31c00),
+        (0x8000, 0x31be135f97ed3200),
+        (0x10000, 0x9aa508b5b85a500),
+        (0x20000, 0x5d6af8dedc582c),
+        (0x40000, 0x2216e584f5fa),
+    ];
+
+    let abs_tick = tick.unsigned_abs();
+    let mut ratio: u128 = if abs_tick & 0x1 != 0 { 0xfffcb933bd6fb800 } else { Q64 };
+
+    for (mask, factor) in RATIOS {
+        if abs_tick & mask != 0 {
+            ratio = (ratio * factor) >> 64;
+        }
+    }
+
+    if tick > 0 {
+        ratio = u128::MAX / ratio;
+    }
+
+    Ok(ratio)
+}
+
+/// Наибольший тик, у которого sqrt_price_at_tick(tick) <= sqrt_price_x64
+pub fn tick_at_sqrt_price(sqrt_price_x64: u128) -> Result<i32> {
+    if !(MIN_SQRT_PRICE_X64..MAX_SQRT_PRICE_X64).contains(&sqrt_price_x64) {
+        anyhow::bail!("√P {} вне допустимого диапазона", sqrt_price_x64);
+    }
+
+    // Оценка через f64 и точная доводка по целочисленной tick_math
+    let sqrt_price = sqrt_price_x64 as f64 / Q64 as f64;
+    let estimate = (2.0 * sqrt_price.ln() / 1.0001f64.ln()).floor() as i32;
+    let mut tick = estimate.clamp(MIN_TICK, MAX_TICK);
+
+    while tick > MIN_TICK && sqrt_price_at_tick(tick)? > sqrt_price_x64 {
+        tick -= 1;
+    }
+    while tick < MAX_TICK && sqrt_price_at_tick(tick + 1)? <= sqrt_price_x64 {
+        tick += 1;
+    }
+
+    Ok(tick)
+}
+
+/// Стартовый тик массива, содержащего tick
+pub fn tick_array_start_index(tick: i32, tick_spacing: u16) -> i32 {
+    let ticks_in_array = TICK_ARRAY_SIZE * tick_spacing as i32;
+    tick.div_euclid(ticks_in_array) * ticks_in_array
+}
+
+/// PDA TickArray: ["tick_array", pool, start_index (big-endian)]
+pub fn tick_array_address(program_id: &Pubkey, pool_id: &Pubkey, start_index: i32) -> Pubkey {
+    Pubkey::find_program_address(
+        &[TICK_ARRAY_SEED, pool_id.as_ref(), &start_index.to_be_bytes()],
+        program_id,
+    )
+    .0
+}
+
+/// PDA расширения битмапа TickArray: ["pool_tick_array_bitmap_extension", pool]
+pub fn tick_array_bitmap_extension_address(program_id: &Pubkey, pool_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[TICK_ARRAY_BITMAP_EXTENSION_SEED, pool_id.as_ref()], program_id).0
+}
+
+/// Инициализирован ли TickArray по битмапу пула.
+/// None — старт вне диапазона битмапа пула (нужен bitmap extension)
+fn tick_array_initialized(pool: &ClmmPoolState, start: i32) -> Option<bool> {
+    let ticks_in_array = TICK_ARRAY_SIZE * pool.tick_spacing as i32;
+    let bit = start / ticks_in_array + TICK_ARRAY_BITMAP_BITS / 2;
+    if !(0..TICK_ARRAY_BITMAP_BITS).contains(&bit) {
+        return None;
+    }
+    let bit = bit as usize;
+    Some((pool.tick_array_bitmap[bit / 64] >> (bit % 64)) & 1 == 1)
+}
+
+/// Стартовые индексы инициализированных TickArray вокруг текущего тика
+/// (по tick_array_bitmap пула, до `per_side` массивов в каждую сторону)
+pub fn initialized_tick_array_starts(pool: &ClmmPoolState, per_side: usize) -> Vec<i32> {
+    let ticks_in_array = TICK_ARRAY_SIZE * pool.tick_spacing as i32;
+    let current = tick_array_start_index(pool.tick_current, pool.tick_spacing);
+
+    let mut starts = Vec::new();
+    if tick_array_initialized(pool, current) == Some(true) {
+        starts.push(current);
+    }
+
+    for step in [ticks_in_array, -ticks_in_array] {
+        let mut start = current + step;
+        let mut found = 0;
+        while found < per_side {
+            match tick_array_initialized(pool, start) {
+                Some(true) => {
+                    starts.push(start);
+                    found += 1;
+                }
+                Some(false) => {}
+                None => break,
+            }
+            start += step;
+        }
+    }
+
+    starts.sort_unstable();
+    starts
+}
+
+/// TickArray для swap_v2 в порядке обхода: первый инициализированный массив
+/// по направлению (текущий включительно — программа проверяет именно его)
+/// и следующие за ним, всего до `count`.
+/// Второй элемент — обход упёрся в границу битмапа пула: массивы дальше
+/// отмечены только в bitmap extension, и его нужно передать программе
+pub fn swap_tick_array_starts(pool: &ClmmPoolState, zero_for_one: bool, count: usize) -> (Vec<i32>, bool) {
+    let ticks_in_array = TICK_ARRAY_SIZE * pool.tick_spacing as i32;
+    let step = if zero_for_one { -ticks_in_array } else { ticks_in_array };
+    let lowest = tick_array_start_index(MIN_TICK, pool.tick_spacing);
+    let highest = tick_array_start_index(MAX_TICK, pool.tick_spacing);
+
+    let mut start = tick_array_start_index(pool.tick_current, pool.tick_spacing);
+    let mut starts = Vec::new();
+
+    while starts.len() < count && (lowest..=highest).contains(&start) {
+        match tick_array_initialized(pool, start) {
+            Some(true) => starts.push(start),
+            Some(false) => {}
+            None => return (starts, true),
+        }
+        start += step;
+    }
+
+    (starts, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_pool(liquidity: u128, tick_current: i32) -> ClmmPoolState {
+        ClmmPoolState {
+            amm_config: Pubkey::new_unique(),
+            token_mint_0: Pubkey::new_unique(),
+            token_mint_1: Pubkey::new_unique(),
+            token_vault_0: Pubkey::new_unique(),
+            token_vault_1: Pubkey::new_unique(),
+            observation_key: Pubkey::new_unique(),
+            mint_decimals_0: 9,
+            mint_decimals_1: 9,
+            tick_spacing: 10,
+            liquidity,
+            sqrt_price_x64: sqrt_price_at_tick(tick_current).unwrap(),
+            tick_current,
+            tick_array_bitmap: [0; 16],
+        }
+    }
+
+    fn tick_array(start: i32, ticks: &[(i32, i128)]) -> TickArrayState {
+        TickArrayState {
+            address: Pubkey::new_unique(),
+            start_tick_index: start,
+            ticks: ticks
+                .iter()
+                .map(|&(tick, liquidity_net)| TickState {
+                    tick,
+                    liquidity_net,
+                    liquidity_gross: liquidity_net.unsigned_abs(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_sqrt_price_at_tick_bounds() {
+        assert_eq!(sqrt_price_at_tick(0).unwrap(), Q64);
+        assert_eq!(sqrt_price_at_tick(MIN_TICK).unwrap(), MIN_SQRT_PRICE_X64);
+        assert_eq!(sqrt_price_at_tick(MAX_TICK).unwrap(), MAX_SQRT_PRICE_X64);
+
+        let price = sqrt_price_at_tick(1000).unwrap() as f64 / Q64 as f64;
+        assert!((price - 1.0001f64.powf(500.0)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_tick_at_sqrt_price_roundtrip() {
+        for tick in [-200_000, -601, -1, 0, 1, 599, 123_456] {
+            let sqrt_price = sqrt_price_at_tick(tick).unwrap();
+            assert_eq!(tick_at_sqrt_price(sqrt_price).unwrap(), tick);
+            assert_eq!(tick_at_sqrt_price(sqrt_price + 1).unwrap(), tick);
+        }
+    }
+
+    #[test]
+    fn test_tick_array_start_index() {
+        assert_eq!(tick_array_start_index(0, 10), 0);
+        assert_eq!(tick_array_start_index(599, 10), 0);
+        assert_eq!(tick_array_start_index(600, 10), 600);
+        assert_eq!(tick_array_start_index(-1, 10), -600);
+        assert_eq!(tick_array_start_index(-600, 10), -600);
+    }
+
+    #[test]
+    fn test_swap_within_single_range_matches_constant_product() {
+        let liquidity = 1_000_000_000_000u128;
+        let pool = test_pool(liquidity, 0);
+        let arrays = vec![
+            tick_array(-600, &[(-600, liquidity as i128)]),
+            tick_array(0, &[(590, -(liquidity as i128))]),
+        ];
+
+        let quote = simulate_swap(&pool, 0, &arrays, 1_000_000, false).unwrap();
+
+        // Внутри одного диапазона CLMM = x·y=k с резервами L/√P и L·√P
+        let expected = liquidity as f64 * 1_000_000.0 / (liquidity as f64 + 1_000_000.0);
+        assert!((quote.amount_out as f64 - expected).abs() <= 1.0);
+        assert_eq!(quote.tick_arrays, vec![arrays[1].address]);
+    }
+
+    #[test]
+    fn test_swap_crosses_ticks_and_updates_liquidity() {
+        let liquidity = 1_000_000_000u128;
+        let pool = test_pool(liquidity, 0);
+        // Позиция [-600, -100] удваивает ликвидность ниже тика -100
+        let arrays = vec![
+            tick_array(-600, &[(-600, 2 * liquidity as i128), (-100, -(liquidity as i128))]),
+            tick_array(0, &[(590, -(liquidity as i128))]),
+        ];
+
+        let amount_in = 20_000_000;
+        let quote = simulate_swap(&pool, 2500, &arrays, amount_in, true).unwrap();
+        let single_range = simulate_swap(
+            &pool,
+            2500,
+            &[tick_array(-600, &[(-600, liquidity as i128)]), tick_array(0, &[])],
+            amount_in,
+            true,
+        )
+        .unwrap();
+
+        assert!(quote.tick_after < -100);
+        assert!(quote.amount_out > single_range.amount_out);
+        assert_eq!(quote.tick_arrays, vec![arrays[1].address, arrays[0].address]);
+        assert!(quote.fee_amount >= amount_in * 2500 / 1_000_000);
+    }
+
+    #[test]
+    fn test_swap_beyond_loaded_arrays_fails() {
+        let liquidity = 1_000_000u128;
+        let pool = test_pool(liquidity, 0);
+        let arrays = vec![tick_array(0, &[])];
+
+        assert!(simulate_swap(&pool, 0, &arrays, 1_000_000_000, false).is_err());
+    }
+
+    #[test]
+    fn test_swap_tick_arrays_follow_direction_and_bitmap() {
+        let mut pool = test_pool(1_000_000, 0);
+        // Массив 600 тиков: инициализированы [-600, 0) и [1200, 1800), текущий [0, 600) — нет
+        for start in [-600, 1200] {
+            let bit = (start / 600 + TICK_ARRAY_BITMAP_BITS / 2) as usize;
+            pool.tick_array_bitmap[bit / 64] |= 1 << (bit % 64);
+        }
+
+        assert_eq!(swap_tick_array_starts(&pool, true, 1), (vec![-600], false));
+        // Вверх после [1200, 1800) инициализированных нет до границы битмапа
+        assert_eq!(swap_tick_array_starts(&pool, false, 2), (vec![1200], true));
+    }
+}
@@ -0,0 +1,360 @@
+// bot/src/arbitrage/cycle_index.rs
+// Индекс кандидатных циклов: топология перечисляется один раз при добавлении пулов,
+// а на каждом слоте переоцениваются только циклы через изменившиеся пулы
+
+use rayon::prelude::*;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
+use tracing::debug;
+
+use super::graph::PriceGraph;
+
+/// Каноническая подпись цикла: пары (пул, входной токен), повёрнутые к минимальной паре
+pub type CycleSignature = Vec<(Pubkey, Pubkey)>;
+
+/// Подпись цикла по парам (пул, входной токен) в порядке исполнения.
+/// Входной токен различает направления — обратный цикл это другая сделка
+pub fn cycle_signature(legs: impl IntoIterator<Item = (Pubkey, Pubkey)>) -> CycleSignature {
+    let legs: Vec<(Pubkey, Pubkey)> = legs.into_iter().collect();
+
+    let start = legs.iter()
+        .enumerate()
+        .min_by_key(|(_, leg)| **leg)
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+
+    legs[start..].iter().chain(&legs[..start]).copied().collect()
+}
+
+/// Кандидат в арбитражный цикл: фиксированная последовательность пулов
+#[derive(Debug, Clone)]
+pub struct CandidateCycle {
+    /// Токены цикла, последний совпадает с первым
+    pub tokens: Vec<Pubkey>,
+    /// Пулы для каждого перехода (tokens[i] -> tokens[i + 1])
+    pub pool_ids: Vec<Pubkey>,
+}
+
+/// Состояние обхода при перечислении циклов через один пул
+struct Search<'a> {
+    graph: &'a PriceGraph,
+    /// Токен, в который цикл должен вернуться
+    target: usize,
+    /// Пул, с которого перечисляется цикл
+    start: Pubkey,
+    /// Новые пулы пакета: через те, чей id меньше start, циклы уже перечислены из них
+    added: &'a HashSet<Pubkey>,
+    /// Циклическое ядро графа (None — все пулы)
+    core: Option<&'a HashSet<Pubkey>>,
+    limit: usize,
+    tokens: Vec<usize>,
+    pools: Vec<Pubkey>,
+    found: Vec<CandidateCycle>,
+}
+
+impl Search<'_> {
+    /// Продление пути ровно на `legs_left` переходов с возвратом в target
+    fn extend(&mut self, legs_left: usize) {
+        if self.found.len() >= self.limit {
+            return;
+        }
+
+        let current = *self.tokens.last().expect("путь не пуст");
+        let graph = self.graph;
+
+        for (next, edges) in graph.neighbors(current) {
+            let closes = next == self.target;
+            if closes != (legs_left == 1) || (!closes && self.tokens.contains(&next)) {
+                continue;
+            }
+
+            for edge in edges {
+                if self.pools.contains(&edge.pool_id)
+                    || (edge.pool_id < self.start && self.added.contains(&edge.pool_id))
+                    || self.core.is_some_and(|core| !core.contains(&edge.pool_id))
+                {
+                    continue;
+                }
+
+                self.tokens.push(next);
+                self.pools.push(edge.pool_id);
+
+                if closes {
+                    self.found.push(CandidateCycle {
+                        tokens: self.tokens.iter()
+                            .filter_map(|idx| graph.get_token(*idx).copied())
+                            .collect(),
+                        pool_ids: self.pools.clone(),
+                    });
+                } else {
+                    self.extend(legs_left - 1);
+                }
+
+                self.tokens.pop();
+                self.pools.pop();
+
+                if self.found.len() >= self.limit {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Индекс кандидатных циклов по id пула
+pub struct CycleIndex {
+    max_legs: usize,
+    /// Ограничение числа новых циклов на пул (хабы вроде SOL/USDC дают комбинаторный взрыв)
+    max_cycles_per_pool: usize,
+    /// Слоты циклов (None — удалён, слот переиспользуется)
+    cycles: Vec<Option<CandidateCycle>>,
+    free_slots: Vec<usize>,
+    /// pool_id -> слоты циклов через этот пул
+    by_pool: HashMap<Pubkey, Vec<usize>>,
+    /// Каноническая подпись (ротация с минимального pool_id) -> слот
+    signatures: HashMap<CycleSignature, usize>,
+    /// Циклическое ядро графа: перечисляются только циклы внутри него (None — весь граф)
+    core: Option<HashSet<Pubkey>>,
+}
+
+impl CycleIndex {
+    pub fn new(max_legs: usize, max_cycles_per_pool: usize) -> Self {
+        Self {
+            max_legs,
+            max_cycles_per_pool,
+            cycles: Vec::new(),
+            free_slots: Vec::new(),
+            by_pool: HashMap::new(),
+            signatures: HashMap::new(),
+            core: None,
+        }
+    }
+
+    /// Замена циклического ядра: циклы через выбывшие пулы удаляются,
+    /// через вошедшие — перечисляются. Возвращает вошедшие в ядро пулы
+    pub fn set_core(&mut self, graph: &PriceGraph, core: HashSet<Pubkey>) -> Vec<Pubkey> {
+        let (left, entered): (Vec<Pubkey>, Vec<Pubkey>) = match &self.core {
+            Some(old) => (
+                old.difference(&core).copied().collect(),
+                core.difference(old).copied().collect(),
+            ),
+            None => (
+                self.by_pool.keys().filter(|id| !core.contains(id)).copied().collect(),
+                core.iter().filter(|id| !self.by_pool.contains_key(id)).copied().collect(),
+            ),
+        };
+
+        self.remove_pools(&left);
+        self.core = Some(core);
+        self.add_pools(graph, &entered);
+        entered
+    }
+
+    /// Перечисление циклов через добавленные пулы (граф уже содержит их рёбра).
+    /// Обход из каждого пула идёт параллельно, вставка в индекс — последовательно.
+    /// Возвращает количество новых циклов в индексе
+    pub fn add_pools(&mut self, graph: &PriceGraph, added: &[Pubkey]) -> usize {
+        // Пулы вне ядра не участвуют в циклах — они перечислятся при входе в ядро
+        let added: HashSet<Pubkey> = added.iter()
+            .filter(|id| self.core.as_ref().is_none_or(|core| core.contains(id)))
+            .copied()
+            .collect();
+
+        let found: Vec<CandidateCycle> = added.par_iter()
+            .flat_map_iter(|pool_id| self.enumerate_through(graph, pool_id, &added))
+            .collect();
+
+        let mut inserted = 0;
+        for cycle in found {
+            if self.insert(cycle) {
+                inserted += 1;
+            }
+        }
+
+        if inserted > 0 {
+            debug!("   Индекс циклов: +{} (всего {})", inserted, self.len());
+        }
+        inserted
+    }
+
+    /// Удаление всех циклов через пулы. Возвращает количество удалённых циклов
+    pub fn remove_pools(&mut self, removed: &[Pubkey]) -> usize {
+        let mut count = 0;
+
+        for pool_id in removed {
+            let Some(slots) = self.by_pool.remove(pool_id) else {
+                continue;
+            };
+
+            for slot in slots {
+                let Some(cycle) = self.cycles[slot].take() else {
+                    continue;
+                };
+
+                self.signatures.remove(&Self::signature(&cycle));
+                for other in cycle.pool_ids.iter().filter(|id| *id != pool_id) {
+                    if let Some(other_slots) = self.by_pool.get_mut(other) {
+                        other_slots.retain(|s| *s != slot);
+                    }
+                }
+
+                self.free_slots.push(slot);
+                count += 1;
+            }
+        }
+
+        count
+    }
+
+    /// Циклы, проходящие хотя бы через один из изменившихся пулов (без повторов)
+    pub fn cycles_touching(&self, changed: &[Pubkey]) -> Vec<&CandidateCycle> {
+        let mut seen = HashSet::new();
+
+        changed.iter()
+            .filter_map(|pool_id| self.by_pool.get(pool_id))
+            .flatten()
+            .filter(|slot| seen.insert(**slot))
+            .filter_map(|slot| self.cycles[*slot].as_ref())
+            .collect()
+    }
+
+    /// Количество циклов в индексе
+    pub fn len(&self) -> usize {
+        self.signatures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.signatures.is_empty()
+    }
+
+    /// Перечисление циклов, начинающихся с пула (в обоих направлениях),
+    /// от коротких к длинным — при достижении лимита остаются самые короткие.
+    /// Цикл из нескольких новых пулов перечисляется только из пула с меньшим id
+    fn enumerate_through(&self, graph: &PriceGraph, pool_id: &Pubkey, added: &HashSet<Pubkey>) -> Vec<CandidateCycle> {
+        let Some(pool) = graph.get_pool(pool_id) else {
+            return Vec::new();
+        };
+        let (Some(a), Some(b)) = (graph.get_index(&pool.token_a), graph.get_index(&pool.token_b)) else {
+            return Vec::new();
+        };
+
+        let mut search = Search {
+            graph,
+            target: 0,
+            start: *pool_id,
+            added,
+            core: self.core.as_ref(),
+            limit: self.max_cycles_per_pool,
+            tokens: Vec::new(),
+            pools: Vec::new(),
+            found: Vec::new(),
+        };
+
+        for legs in 2..=self.max_legs {
+            for (from, to) in [(a, b), (b, a)] {
+                search.target = from;
+                search.tokens = vec![from, to];
+                search.pools = vec![*pool_id];
+                search.extend(legs - 1);
+            }
+        }
+
+        search.found
+    }
+
+    /// Вставка цикла, если такого ещё нет
+    fn insert(&mut self, cycle: CandidateCycle) -> bool {
+        let signature = Self::signature(&cycle);
+        if self.signatures.contains_key(&signature) {
+            return false;
+        }
+
+        let slot = match self.free_slots.pop() {
+            Some(slot) => slot,
+            None => {
+                self.cycles.push(None);
+                self.cycles.len() - 1
+            }
+        };
+
+        for pool_id in &cycle.pool_ids {
+            self.by_pool.entry(*pool_id).or_default().push(slot);
+        }
+        self.signatures.insert(signature, slot);
+        self.cycles[slot] = Some(cycle);
+        true
+    }
+
+    fn signature(cycle: &CandidateCycle) -> CycleSignature {
+        cycle_signature(cycle.pool_ids.iter().copied().zip(cycle.tokens.iter().copied()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DexProtocol, PoolState};
+    use std::sync::Arc;
+
+    fn pool(token_a: Pubkey, token_b: Pubkey) -> Arc<PoolState> {
+        Arc::new(PoolState {
+            id: Pubkey::new_unique(),
+            protocol: DexProtocol::RaydiumCpmm,
+            token_a,
+            token_b,
+            reserve_a: 1_000_000_000_000,
+            reserve_b: 1_000_000_000_000,
+            fee_bps: 25,
+            last_updated: 0,
+            full_state_data: vec![],
+            decimals_a: 9,
+            decimals_b: 9,
+            liquidity_accounts: vec![],
+        })
+    }
+
+    #[test]
+    fn test_enumerates_cycles_once_and_indexes_by_pool() {
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let pools = vec![pool(a, b), pool(b, c), pool(c, a), pool(a, b)];
+
+        let mut graph = PriceGraph::new(1.0);
+        let update = graph.sync_pools(&pools).unwrap();
+        let mut index = CycleIndex::new(3, 64);
+        index.add_pools(&graph, &update.added);
+
+        // Два 2-шаговых цикла через параллельные пулы A/B и 4 треугольника (2 пула A/B × 2 направления)
+        assert_eq!(index.len(), 6);
+        assert_eq!(index.cycles_touching(&[pools[1].id]).len(), 4);
+        assert!(index.cycles_touching(&[pools[1].id])
+            .iter()
+            .all(|c| c.tokens.first() == c.tokens.last()));
+
+        // Повторное добавление не дублирует циклы
+        assert_eq!(index.add_pools(&graph, &[pools[2].id]), 0);
+
+        assert_eq!(index.remove_pools(&[pools[3].id]), 4);
+        assert_eq!(index.len(), 2);
+        assert_eq!(index.cycles_touching(&[pools[0].id]).len(), 2);
+    }
+
+    #[test]
+    fn test_cycles_limited_per_pool() {
+        let tokens: Vec<Pubkey> = (0..6).map(|_| Pubkey::new_unique()).collect();
+        let mut pools = Vec::new();
+        for i in 0..tokens.len() {
+            for j in i + 1..tokens.len() {
+                pools.push(pool(tokens[i], tokens[j]));
+            }
+        }
+
+        let mut graph = PriceGraph::new(1.0);
+        graph.sync_pools(&pools).unwrap();
+        let mut index = CycleIndex::new(4, 8);
+        index.add_pools(&graph, &[pools[0].id]);
+
+        assert_eq!(index.len(), 8);
+        // Лимит заполняется самыми короткими циклами
+        assert!(index.cycles_touching(&[pools[0].id]).iter().all(|c| c.pool_ids.len() == 3));
+    }
+}
@@ -0,0 +1,516 @@
+// bot/src/arbitrage/dlmm_quote.rs
+// Котировка Meteora DLMM: расход ликвидности бин за бином с динамической комиссией
+
+use anyhow::{Context, Result};
+use solana_sdk::pubkey::Pubkey;
+
+use crate::dex_structs::{DlmmBin, DlmmBinArray, DlmmLbPair, DLMM_BIN_ARRAY_LEN, MAX_BIN_PER_ARRAY};
+use crate::types::PoolState;
+use crate::utils::big_num::{mul_div_ceil, mul_div_floor};
+
+pub const BASIS_POINT_MAX: u64 = 10_000;
+/// Точность ставок комиссии DLMM (1e-9)
+pub const FEE_PRECISION: u128 = 1_000_000_000;
+/// Максимальная суммарная комиссия (10%)
+pub const MAX_FEE_RATE: u128 = 100_000_000;
+
+const ONE: u128 = 1 << 64;
+const MAX_EXPONENTIAL: u32 = 0x80000;
+const BIN_ARRAY_SEED: &[u8] = b"bin_array";
+const BIN_ARRAY_BITMAP_EXTENSION_SEED: &[u8] = b"bitmap";
+const EVENT_AUTHORITY_SEED: &[u8] = b"__event_authority";
+/// Половина битмапа bin_array_bitmap пары (индексы массивов [-512, 511])
+const BIN_ARRAY_BITMAP_HALF: i32 = 512;
+
+/// Результат симуляции свопа DLMM
+#[derive(Debug, Clone)]
+pub struct DlmmSwapQuote {
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_amount: u64,
+    pub active_id_after: i32,
+    /// BinArray аккаунты, через которые прошёл своп (в порядке обхода)
+    pub bin_arrays: Vec<Pubkey>,
+}
+
+/// Результат свопа внутри одного бина
+#[derive(Debug, Clone, Copy)]
+struct BinSwap {
+    amount_in_with_fees: u64,
+    amount_out: u64,
+    fee: u64,
+}
+
+/// Переменные параметры волатильности, меняющиеся по ходу свопа
+#[derive(Debug, Clone, Copy)]
+struct VolatilityState {
+    accumulator: u32,
+    reference: u32,
+    index_reference: i32,
+}
+
+impl VolatilityState {
+    /// update_references программы: обновление референса по прошедшему времени
+    fn at(pair: &DlmmLbPair, now: i64) -> Self {
+        let mut state = Self {
+            accumulator: pair.volatility_accumulator,
+            reference: pair.volatility_reference,
+            index_reference: pair.index_reference,
+        };
+
+        let elapsed = now - pair.last_update_timestamp;
+        if elapsed >= pair.filter_period as i64 {
+            state.index_reference = pair.active_id;
+            state.reference = if elapsed < pair.decay_period as i64 {
+                (pair.volatility_accumulator as u64 * pair.reduction_factor as u64 / BASIS_POINT_MAX) as u32
+            } else {
+                0
+            };
+        }
+
+        state
+    }
+
+    /// update_volatility_accumulator программы для текущего активного бина
+    fn update_accumulator(&mut self, pair: &DlmmLbPair, active_id: i32) {
+        let delta_id = (self.index_reference as i64 - active_id as i64).unsigned_abs();
+        let accumulator = self.reference as u64 + delta_id * BASIS_POINT_MAX;
+        self.accumulator = accumulator.min(pair.max_volatility_accumulator as u64) as u32;
+    }
+}
+
+/// Котировка свопа по PoolState, загруженному сканером (LbPair + BinArray)
+pub fn quote_pool(pool: &PoolState, amount_in: u64, a_to_b: bool) -> Result<DlmmSwapQuote> {
+    let pair = DlmmLbPair::try_from_slice(&pool.full_state_data)
+        .with_context(|| format!("Не удалось декодировать DLMM пару {}", pool.id))?;
+
+    let bin_arrays = pool
+        .liquidity_accounts
+        .iter()
+        .filter(|account| account.data.len() == DLMM_BIN_ARRAY_LEN)
+        .map(|account| DlmmBinArray::try_from_slice(account.pubkey, &account.data))
+        .collect::<Result<Vec<_>>>()?;
+
+    let swap_for_y = if pool.token_a == pair.token_x_mint { a_to_b } else { !a_to_b };
+    simulate_swap(&pair, &bin_arrays, amount_in, swap_for_y, chrono::Utc::now().timestamp())
+}
+
+/// Симуляция exact-input свопа: расход ликвидности бинов начиная с active_id,
+/// комиссия пересчитывается на каждом бине из аккумулятора волатильности
+pub fn simulate_swap(
+    pair: &DlmmLbPair,
+    bin_arrays: &[DlmmBinArray],
+    amount_in: u64,
+    swap_for_y: bool,
+    now: i64,
+) -> Result<DlmmSwapQuote> {
+    if amount_in == 0 {
+        anyhow::bail!("Нулевая входная сумма для DLMM свопа");
+    }
+
+    let mut arrays: Vec<&DlmmBinArray> = bin_arrays.iter().collect();
+    arrays.sort_by_key(|a| a.index);
+
+    let mut volatility = VolatilityState::at(pair, now);
+    let mut active_id = pair.active_id;
+    let mut amount_left = amount_in;
+    let mut amount_out = 0u64;
+    let mut fee_amount = 0u64;
+    let mut touched: Vec<Pubkey> = Vec::new();
+
+    while amount_left > 0 {
+        let array_index = bin_array_index(active_id) as i64;
+        let Some(array) = arrays.iter().find(|a| a.index == array_index).copied() else {
+            // Массив без ликвидности: программа переходит к следующему инициализированному
+            let next = if swap_for_y {
+                arrays.iter().rev().find(|a| a.index < array_index)
+            } else {
+                arrays.iter().find(|a| a.index > array_index)
+            }
+            .context("Недостаточно ликвидности в загруженных BinArray DLMM")?;
+
+            let (lower, upper) = bin_array_bounds(next.index);
+            active_id = if swap_for_y { upper } else { lower };
+            continue;
+        };
+
+        if touched.last() != Some(&array.address) {
+            touched.push(array.address);
+        }
+
+        let (lower, upper) = bin_array_bounds(array.index);
+        while amount_left > 0 && (lower..=upper).contains(&active_id) {
+            volatility.update_accumulator(pair, active_id);
+
+            let bin = &array.bins[(active_id - lower) as usize];
+            let has_output = if swap_for_y { bin.amount_y > 0 } else { bin.amount_x > 0 };
+
+            if has_output {
+                let price = if bin.price != 0 {
+                    bin.price
+                } else {
+                    price_from_id(active_id, pair.bin_step)?
+                };
+                let fee_rate = total_fee_rate(pair, volatility.accumulator);
+                let step = swap_in_bin(bin, price, amount_left, swap_for_y, fee_rate)?;
+
+                amount_left -= step.amount_in_with_fees;
+                amount_out += step.amount_out;
+                fee_amount += step.fee;
+            }
+
+            if amount_left > 0 {
+                active_id = if swap_for_y { active_id - 1 } else { active_id + 1 };
+                if active_id < pair.min_bin_id || active_id > pair.max_bin_id {
+                    anyhow::bail!("Своп вышел за пределы диапазона бинов DLMM пары");
+                }
+            }
+        }
+    }
+
+    Ok(DlmmSwapQuote {
+        amount_in,
+        amount_out,
+        fee_amount,
+        active_id_after: active_id,
+        bin_arrays: touched,
+    })
+}
+
+/// Bin::swap программы: либо весь бин (с комиссией сверху), либо часть входа
+fn swap_in_bin(
+    bin: &DlmmBin,
+    price: u128,
+    amount_in: u64,
+    swap_for_y: bool,
+    fee_rate: u128,
+) -> Result<BinSwap> {
+    let max_amount_out = if swap_for_y { bin.amount_y } else { bin.amount_x };
+
+    let max_amount_in = if swap_for_y {
+        mul_div_ceil(bin.amount_y as u128, ONE, price)
+    } else {
+        mul_div_ceil(bin.amount_x as u128, price, ONE)
+    }
+    .context("Переполнение max_amount_in бина")?;
+    let max_fee = compute_fee(max_amount_in, fee_rate)?;
+    let max_amount_in = max_amount_in + max_fee;
+
+    if amount_in as u128 >= max_amount_in {
+        return Ok(BinSwap {
+            amount_in_with_fees: u64::try_from(max_amount_in)?,
+            amount_out: max_amount_out,
+            fee: u64::try_from(max_fee)?,
+        });
+    }
+
+    let fee = compute_fee_from_amount(amount_in as u128, fee_rate)?;
+    let amount_in_after_fee = amount_in as u128 - fee;
+    let amount_out = if swap_for_y {
+        mul_div_floor(amount_in_after_fee, price, ONE)
+    } else {
+        mul_div_floor(amount_in_after_fee, ONE, price)
+    }
+    .context("Переполнение выхода бина")?;
+
+    Ok(BinSwap {
+        amount_in_with_fees: amount_in,
+        amount_out: amount_out.min(max_amount_out as u128) as u64,
+        fee: u64::try_from(fee)?,
+    })
+}
+
+/// Базовая комиссия: base_factor · bin_step · 10 · 10^base_fee_power_factor
+pub fn base_fee_rate(pair: &DlmmLbPair) -> u128 {
+    pair.base_factor as u128
+        * pair.bin_step as u128
+        * 10
+        * 10u128.pow(pair.base_fee_power_factor as u32)
+}
+
+/// Переменная комиссия: variable_fee_control · (volatility_accumulator · bin_step)²
+pub fn variable_fee_rate(pair: &DlmmLbPair, volatility_accumulator: u32) -> u128 {
+    if pair.variable_fee_control == 0 {
+        return 0;
+    }
+
+    let square_vfa_bin = (volatility_accumulator as u128 * pair.bin_step as u128).pow(2);
+    let v_fee = pair.variable_fee_control as u128 * square_vfa_bin;
+    v_fee.div_ceil(100_000_000_000)
+}
+
+/// Суммарная ставка комиссии (1e-9), ограниченная MAX_FEE_RATE
+pub fn total_fee_rate(pair: &DlmmLbPair, volatility_accumulator: u32) -> u128 {
+    (base_fee_rate(pair) + variable_fee_rate(pair, volatility_accumulator)).min(MAX_FEE_RATE)
+}
+
+/// Комиссия сверху на сумму без комиссии (округление вверх)
+fn compute_fee(amount: u128, fee_rate: u128) -> Result<u128> {
+    mul_div_ceil(amount, fee_rate, FEE_PRECISION - fee_rate).context("Переполнение комиссии DLMM")
+}
+
+/// Комиссия, входящая в сумму с комиссией (округление вверх)
+fn compute_fee_from_amount(amount_with_fees: u128, fee_rate: u128) -> Result<u128> {
+    mul_div_ceil(amount_with_fees, fee_rate, FEE_PRECISION).context("Переполнение комиссии DLMM")
+}
+
+/// Цена бина Q64.64: (1 + bin_step / 10000)^bin_id
+pub fn price_from_id(bin_id: i32, bin_step: u16) -> Result<u128> {
+    let bps = ((bin_step as u128) << 64) / BASIS_POINT_MAX as u128;
+    pow_q64(ONE + bps, bin_id).context("Переполнение цены бина DLMM")
+}
+
+/// Возведение Q64.64 в целую степень, как pow в программе DLMM
+fn pow_q64(base: u128, exp: i32) -> Option<u128> {
+    if exp == 0 {
+        return Some(ONE);
+    }
+
+    let mut invert = exp.is_negative();
+    let exp = exp.unsigned_abs();
+    if exp >= MAX_EXPONENTIAL {
+        return None;
+    }
+
+    // Работаем с основанием < 1, чтобы произведения помещались в u128
+    let mut squared_base = base;
+    if squared_base >= ONE {
+        squared_base = u128::MAX.checked_div(squared_base)?;
+        invert = !invert;
+    }
+
+    let mut result = ONE;
+    for bit in 0..19 {
+        if exp & (1 << bit) != 0 {
+            result = result.checked_mul(squared_base)? >> 64;
+        }
+        squared_base = squared_base.checked_mul(squared_base)? >> 64;
+    }
+
+    if result == 0 {
+        return None;
+    }
+    if invert {
+        result = u128::MAX.checked_div(result)?;
+    }
+
+    Some(result)
+}
+
+/// Индекс BinArray, содержащего бин
+pub fn bin_array_index(bin_id: i32) -> i32 {
+    bin_id.div_euclid(MAX_BIN_PER_ARRAY)
+}
+
+/// Нижний и верхний bin_id массива
+fn bin_array_bounds(index: i64) -> (i32, i32) {
+    let lower = index as i32 * MAX_BIN_PER_ARRAY;
+    (lower, lower + MAX_BIN_PER_ARRAY - 1)
+}
+
+/// PDA BinArray: ["bin_array", lb_pair, index (i64 little-endian)]
+pub fn bin_array_address(program_id: &Pubkey, lb_pair: &Pubkey, index: i64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[BIN_ARRAY_SEED, lb_pair.as_ref(), &index.to_le_bytes()],
+        program_id,
+    )
+    .0
+}
+
+/// PDA расширения битмапа BinArray: ["bitmap", lb_pair]
+pub fn bin_array_bitmap_extension_address(program_id: &Pubkey, lb_pair: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[BIN_ARRAY_BITMAP_EXTENSION_SEED, lb_pair.as_ref()], program_id).0
+}
+
+/// PDA event authority программы (Anchor event_cpi): ["__event_authority"]
+pub fn event_authority_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[EVENT_AUTHORITY_SEED], program_id).0
+}
+
+/// Инициализирован ли BinArray по битмапу пары.
+/// None — индекс вне битмапа пары (нужен bitmap extension)
+fn bin_array_initialized(pair: &DlmmLbPair, index: i32) -> Option<bool> {
+    let bit = index + BIN_ARRAY_BITMAP_HALF;
+    if !(0..2 * BIN_ARRAY_BITMAP_HALF).contains(&bit) {
+        return None;
+    }
+    let bit = bit as usize;
+    Some((pair.bin_array_bitmap[bit / 64] >> (bit % 64)) & 1 == 1)
+}
+
+/// Индексы инициализированных BinArray вокруг активного бина
+/// (по bin_array_bitmap пары, до `per_side` массивов в каждую сторону)
+pub fn initialized_bin_array_indexes(pair: &DlmmLbPair, per_side: usize) -> Vec<i64> {
+    let current = bin_array_index(pair.active_id);
+
+    let mut indexes = Vec::new();
+    if bin_array_initialized(pair, current) == Some(true) {
+        indexes.push(current as i64);
+    }
+
+    for step in [1, -1] {
+        let mut index = current + step;
+        let mut found = 0;
+        while found < per_side {
+            match bin_array_initialized(pair, index) {
+                Some(true) => {
+                    indexes.push(index as i64);
+                    found += 1;
+                }
+                Some(false) => {}
+                None => break,
+            }
+            index += step;
+        }
+    }
+
+    indexes.sort_unstable();
+    indexes
+}
+
+/// BinArray для swap в порядке обхода: начиная с массива активного бина
+/// (или первого инициализированного по направлению), всего до `count`.
+/// swap_for_y сдвигает активный бин вниз, обратный своп — вверх.
+/// Второй элемент — обход упёрся в границу битмапа пары (нужен bitmap extension)
+pub fn swap_bin_array_indexes(pair: &DlmmLbPair, swap_for_y: bool, count: usize) -> (Vec<i64>, bool) {
+    let step = if swap_for_y { -1 } else { 1 };
+    let lowest = bin_array_index(pair.min_bin_id);
+    let highest = bin_array_index(pair.max_bin_id);
+
+    let mut index = bin_array_index(pair.active_id);
+    let mut indexes = Vec::new();
+
+    while indexes.len() < count && (lowest..=highest).contains(&index) {
+        match bin_array_initialized(pair, index) {
+            Some(true) => indexes.push(index as i64),
+            Some(false) => {}
+            None => return (indexes, true),
+        }
+        index += step;
+    }
+
+    (indexes, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_pair(active_id: i32, variable_fee_control: u32) -> DlmmLbPair {
+        DlmmLbPair {
+            base_factor: 10_000,
+            filter_period: 30,
+            decay_period: 600,
+            reduction_factor: 5_000,
+            variable_fee_control,
+            max_volatility_accumulator: 350_000,
+            min_bin_id: -443_636,
+            max_bin_id: 443_636,
+            base_fee_power_factor: 0,
+            volatility_accumulator: 0,
+            volatility_reference: 0,
+            index_reference: active_id,
+            last_update_timestamp: 0,
+            active_id,
+            bin_step: 25,
+            token_x_mint: Pubkey::new_unique(),
+            token_y_mint: Pubkey::new_unique(),
+            reserve_x: Pubkey::new_unique(),
+            reserve_y: Pubkey::new_unique(),
+            oracle: Pubkey::new_unique(),
+            bin_array_bitmap: [0; 16],
+        }
+    }
+
+    /// BinArray, где у каждого бина одинаковые резервы X и Y
+    fn bin_array(index: i64, amount_x: u64, amount_y: u64) -> DlmmBinArray {
+        DlmmBinArray {
+            address: Pubkey::new_unique(),
+            index,
+            bins: vec![
+                DlmmBin {
+                    amount_x,
+                    amount_y,
+                    price: 0,
+                };
+                MAX_BIN_PER_ARRAY as usize
+            ],
+        }
+    }
+
+    #[test]
+    fn test_price_from_id() {
+        assert_eq!(price_from_id(0, 25).unwrap(), ONE);
+
+        let up = price_from_id(100, 25).unwrap() as f64 / ONE as f64;
+        assert!((up - 1.0025f64.powi(100)).abs() < 1e-9);
+
+        let down = price_from_id(-100, 25).unwrap() as f64 / ONE as f64;
+        assert!((down - 1.0025f64.powi(-100)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fee_rates() {
+        let pair = test_pair(0, 40_000);
+
+        // 10000 · 25 · 10 = 2.5e6 / 1e9 = 0.25%
+        assert_eq!(base_fee_rate(&pair), 2_500_000);
+        assert_eq!(variable_fee_rate(&pair, 0), 0);
+        assert!(total_fee_rate(&pair, 100_000) > total_fee_rate(&pair, 10_000));
+        assert_eq!(total_fee_rate(&pair, u32::MAX), MAX_FEE_RATE);
+    }
+
+    #[test]
+    fn test_swap_within_active_bin() {
+        let pair = test_pair(0, 0);
+        let arrays = vec![bin_array(-1, 0, 1_000_000_000), bin_array(0, 0, 1_000_000_000)];
+
+        let quote = simulate_swap(&pair, &arrays, 1_000_000, true, 0).unwrap();
+
+        // Цена бина 0 = 1, комиссия 0.25%
+        assert_eq!(quote.amount_out, 997_500);
+        assert_eq!(quote.fee_amount, 2_500);
+        assert_eq!(quote.active_id_after, 0);
+        assert_eq!(quote.bin_arrays, vec![arrays[1].address]);
+    }
+
+    #[test]
+    fn test_swap_walks_bins_and_volatility_raises_fee() {
+        let arrays = vec![bin_array(-1, 0, 1_000_000), bin_array(0, 0, 1_000_000)];
+        let amount_in = 5_000_000;
+
+        let static_fee = simulate_swap(&test_pair(0, 0), &arrays, amount_in, true, 0).unwrap();
+        let dynamic_fee = simulate_swap(&test_pair(0, 40_000), &arrays, amount_in, true, 0).unwrap();
+
+        // Своп расходует бины 0, -1, -2, ... и переходит в соседний BinArray
+        assert!(static_fee.active_id_after < -1);
+        assert_eq!(static_fee.bin_arrays, vec![arrays[1].address, arrays[0].address]);
+        assert!(dynamic_fee.fee_amount > static_fee.fee_amount);
+        assert!(dynamic_fee.amount_out < static_fee.amount_out);
+    }
+
+    #[test]
+    fn test_swap_skips_uninitialized_bin_array() {
+        let pair = test_pair(0, 0);
+        let arrays = vec![bin_array(-3, 0, 1_000_000_000)];
+
+        let quote = simulate_swap(&pair, &arrays, 1_000, true, 0).unwrap();
+
+        assert_eq!(quote.active_id_after, -3 * MAX_BIN_PER_ARRAY + MAX_BIN_PER_ARRAY - 1);
+        assert_eq!(quote.bin_arrays, vec![arrays[0].address]);
+        assert!(simulate_swap(&pair, &arrays, 1_000, false, 0).is_err());
+    }
+
+    #[test]
+    fn test_swap_bin_arrays_follow_direction() {
+        let mut pair = test_pair(0, 0);
+        // Массивы -2 и 1 инициализированы, массив активного бина (0) — нет
+        for index in [-2, 1] {
+            let bit = (index + BIN_ARRAY_BITMAP_HALF) as usize;
+            pair.bin_array_bitmap[bit / 64] |= 1 << (bit % 64);
+        }
+
+        assert_eq!(swap_bin_array_indexes(&pair, true, 1), (vec![-2], false));
+        assert_eq!(swap_bin_array_indexes(&pair, false, 2), (vec![1], true));
+    }
+}
@@ -1,116 +1,270 @@
 // bot/src/arbitrage/graph.rs
-// Построение графа цен для поиска арбитража
+// Разреженный граф цен для поиска арбитража с инкрементальным обновлением
 
 use anyhow::Result;
 use solana_sdk::pubkey::Pubkey;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use tracing::debug;
 
+use super::pool_math::quote_exact_in;
 use crate::types::{PoolState, PriceEdge};
 
+/// Изменения графа после синхронизации со снимком пулов
+#[derive(Debug, Default)]
+pub struct GraphUpdate {
+    pub added: Vec<Pubkey>,
+    /// Пулы, у которых изменился вес хотя бы одного ребра
+    pub updated: Vec<Pubkey>,
+    pub removed: Vec<Pubkey>,
+}
+
+impl GraphUpdate {
+    /// Пулы, чьи циклы нужно переоценить
+    pub fn changed(&self) -> Vec<Pubkey> {
+        self.added.iter().chain(&self.updated).copied().collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.removed.is_empty()
+    }
+}
+
 /// Граф цен между токенами
 pub struct PriceGraph {
+    /// Эталонный объём сделки в UI-единицах стартового токена
+    reference_amount_ui: f64,
+    /// Decimals токенов (для перевода эталонного объёма в атомы)
+    token_decimals: HashMap<Pubkey, u8>,
+    /// Пулы графа по id — для котировки циклов и обновления рёбер
+    /// (общие со снимком сканера, без копирования данных)
+    pools: HashMap<Pubkey, Arc<PoolState>>,
     /// Соответствие токен -> индекс в графе
     token_to_index: HashMap<Pubkey, usize>,
     /// Соответствие индекс -> токен
     index_to_token: Vec<Pubkey>,
-    /// Матрица смежности: adjacency[from][to] = vec![PriceEdge]
-    adjacency: Vec<Vec<Vec<PriceEdge>>>,
+    /// Списки смежности: adjacency[from][to] = рёбра параллельных пулов
+    adjacency: Vec<HashMap<usize, Vec<PriceEdge>>>,
+    /// Количество рёбер (поддерживается при вставке/удалении)
+    edge_count: usize,
 }
 
 impl PriceGraph {
-    pub fn new() -> Self {
+    pub fn new(reference_amount_ui: f64) -> Self {
         Self {
+            reference_amount_ui,
+            token_decimals: HashMap::new(),
+            pools: HashMap::new(),
             token_to_index: HashMap::new(),
             index_to_token: Vec::new(),
             adjacency: Vec::new(),
+            edge_count: 0,
         }
     }
 
     /// Построение графа из списка пулов
-    pub fn build_from_pools(&self, pools: &[PoolState]) -> Result<PriceGraph> {
-        let mut graph = PriceGraph::new();
+    pub fn build_from_pools(&self, pools: &[Arc<PoolState>]) -> Result<PriceGraph> {
+        let mut graph = PriceGraph::new(self.reference_amount_ui);
+        graph.sync_pools(pools)?;
+
+        debug!("Граф построен: {} токенов, {} рёбер", graph.token_count(), graph.edge_count());
+        Ok(graph)
+    }
+
+    /// Синхронизация графа со снимком пулов: новые пулы добавляются,
+    /// у известных веса обновляются на месте, отсутствующие удаляются.
+    /// Пулы с неизменным состоянием не перекотируются
+    pub fn sync_pools(&mut self, pools: &[Arc<PoolState>]) -> Result<GraphUpdate> {
+        let mut update = GraphUpdate::default();
 
-        // Шаг 1: Собираем все уникальные токены
         for pool in pools {
-            graph.add_token_if_new(pool.token_a);
-            graph.add_token_if_new(pool.token_b);
+            let is_new = !self.pools.contains_key(&pool.id);
+            if self.upsert_pool(pool)? {
+                if is_new {
+                    update.added.push(pool.id);
+                } else {
+                    update.updated.push(pool.id);
+                }
+            }
         }
-        let max_pools = 2000;
-        let pools = if pools.len() > max_pools {
-            tracing::warn!("⚠️ Ограничение пулов с {} до {}", pools.len(), max_pools);
-            &pools[..max_pools]
-        } else {
-            pools
-        };
 
-        // Собираем токены и считаем, сколько будет узлов
-        // ...
-        let n = graph.token_count();
+        let present: HashSet<Pubkey> = pools.iter().map(|p| p.id).collect();
+        let stale: Vec<Pubkey> = self.pools.keys()
+            .filter(|id| !present.contains(id))
+            .copied()
+            .collect();
 
-        // Если токенов слишком много — останавливаемся заранее
-        let max_tokens = if pools.len() < 100 { 1000 } else { 100 }; // ДИНАМИЧЕСКИЙ ЛИМИТ
-        if n > max_tokens {
-            anyhow::bail!("Слишком большой граф: {} токенов (> {})", n, max_tokens);
+        for pool_id in stale {
+            self.remove_pool(&pool_id);
+            update.removed.push(pool_id);
         }
-        // Инициализация матрицы смежности
-        let n = graph.token_count();
-        graph.adjacency = vec![vec![Vec::new(); n]; n];
 
-        // Шаг 2: Добавляем рёбра для каждого пула
-        for pool in pools {
-            // Направление A -> B
-            let edge_ab = PriceEdge {
-                from_token: pool.token_a,
-                to_token: pool.token_b,
-                pool_id: pool.id,
-                protocol: pool.protocol,
-                weight: Self::calculate_edge_weight(pool, true)?,
-                fee_bps: pool.fee_bps,
+        Ok(update)
+    }
+
+    /// Добавление пула или обновление весов его рёбер на месте.
+    /// Возвращает true, если пул новый или вес хотя бы одного ребра изменился
+    pub fn upsert_pool(&mut self, pool: &Arc<PoolState>) -> Result<bool> {
+        if let Some(known) = self.pools.get(&pool.id) {
+            if known.token_a != pool.token_a || known.token_b != pool.token_b {
+                // Смена пары токенов — пересоздаём рёбра
+                self.remove_pool(&pool.id);
+            } else if Self::same_state(known, pool) {
+                // Котировка не изменится: обновляется только время наблюдения
+                self.pools.insert(pool.id, pool.clone());
+                return Ok(false);
+            }
+        }
+
+        if !self.pools.contains_key(&pool.id) {
+            self.insert_pool(pool)?;
+            return Ok(true);
+        }
+
+        let mut changed = false;
+        for a_to_b in [true, false] {
+            let (from_token, to_token) = Self::direction(pool, a_to_b);
+            let reference_amount = self.reference_amount(&from_token);
+            let weight = Self::edge_weight_or_inf(pool, a_to_b, reference_amount);
+
+            let edge = self.edge_mut(&from_token, &to_token, &pool.id)
+                .ok_or_else(|| anyhow::anyhow!("Ребро пула {} не найдено в графе", pool.id))?;
+            if edge.weight != weight || edge.fee_bps != pool.fee_bps {
+                changed = true;
+            }
+            edge.weight = weight;
+            edge.fee_bps = pool.fee_bps;
+            edge.reference_amount = reference_amount;
+        }
+
+        self.pools.insert(pool.id, pool.clone());
+        Ok(changed)
+    }
+
+    /// Состояние пула не изменилось с прошлой синхронизации (тот же снимок
+    /// или те же данные аккаунтов) — веса рёбер пересчитывать не нужно.
+    /// last_updated — время загрузки, оно меняется на каждом сканировании
+    fn same_state(known: &Arc<PoolState>, pool: &Arc<PoolState>) -> bool {
+        Arc::ptr_eq(known, pool)
+            || (known.full_state_data == pool.full_state_data
+                && known.reserve_a == pool.reserve_a
+                && known.reserve_b == pool.reserve_b
+                && known.fee_bps == pool.fee_bps
+                && known.liquidity_accounts == pool.liquidity_accounts)
+    }
+
+    /// Удаление пула и его рёбер из графа
+    pub fn remove_pool(&mut self, pool_id: &Pubkey) -> bool {
+        let Some(pool) = self.pools.remove(pool_id) else {
+            return false;
+        };
+
+        for a_to_b in [true, false] {
+            let (from_token, to_token) = Self::direction(&pool, a_to_b);
+            let (Some(from), Some(to)) = (self.get_index(&from_token), self.get_index(&to_token)) else {
+                continue;
             };
 
-            // Направление B -> A
-            let edge_ba = PriceEdge {
-                from_token: pool.token_b,
-                to_token: pool.token_a,
+            if let Some(edges) = self.adjacency[from].get_mut(&to) {
+                let before = edges.len();
+                edges.retain(|e| e.pool_id != *pool_id);
+                self.edge_count -= before - edges.len();
+                if edges.is_empty() {
+                    self.adjacency[from].remove(&to);
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Вставка нового пула: токены и оба ребра
+    fn insert_pool(&mut self, pool: &Arc<PoolState>) -> Result<()> {
+        self.add_token_if_new(pool.token_a);
+        self.add_token_if_new(pool.token_b);
+        self.token_decimals.entry(pool.token_a).or_insert(pool.decimals_a);
+        self.token_decimals.entry(pool.token_b).or_insert(pool.decimals_b);
+
+        for a_to_b in [true, false] {
+            let (from_token, to_token) = Self::direction(pool, a_to_b);
+            let reference_amount = self.reference_amount(&from_token);
+
+            self.add_edge(PriceEdge {
+                from_token,
+                to_token,
                 pool_id: pool.id,
                 protocol: pool.protocol,
-                weight: Self::calculate_edge_weight(pool, false)?,
+                weight: Self::edge_weight_or_inf(pool, a_to_b, reference_amount),
                 fee_bps: pool.fee_bps,
-            };
-
-            graph.add_edge(edge_ab)?;
-            graph.add_edge(edge_ba)?;
+                reference_amount,
+            })?;
         }
 
-        debug!("Граф построен: {} токенов, {} рёбер", n, graph.edge_count());
-        Ok(graph)
+        self.pools.insert(pool.id, pool.clone());
+        Ok(())
     }
 
-    /// Вычисление веса ребра (отрицательный логарифм обменного курса)
-    /// Bellman-Ford находит минимальные пути, отрицательный цикл = прибыль
-    fn calculate_edge_weight(pool: &PoolState, a_to_b: bool) -> Result<f64> {
-        let (reserve_in, reserve_out) = if a_to_b {
-            (pool.reserve_a as f64, pool.reserve_b as f64)
+    /// Пара (from, to) для направления свопа
+    fn direction(pool: &PoolState, a_to_b: bool) -> (Pubkey, Pubkey) {
+        if a_to_b {
+            (pool.token_a, pool.token_b)
         } else {
-            (pool.reserve_b as f64, pool.reserve_a as f64)
-        };
+            (pool.token_b, pool.token_a)
+        }
+    }
 
-        if reserve_in <= 0.0 || reserve_out <= 0.0 {
-            anyhow::bail!("Нулевые резервы в пуле");
+    /// Вес ребра; пул, не способный исполнить эталонный объём, получает +inf
+    /// (ребро остаётся в графе, но не участвует в циклах до следующего обновления)
+    fn edge_weight_or_inf(pool: &PoolState, a_to_b: bool, reference_amount: u64) -> f64 {
+        match Self::calculate_edge_weight(pool, a_to_b, reference_amount) {
+            Ok(weight) => weight,
+            Err(e) => {
+                debug!("   Ребро пула {} неактивно: {}", pool.id, e);
+                f64::INFINITY
+            }
         }
+    }
 
-        // Учёт комиссии (fee_bps / 10000)
-        let fee_multiplier = 1.0 - (pool.fee_bps as f64 / 10000.0);
+    /// Вычисление веса ребра (отрицательный логарифм эффективного курса)
+    /// на эталонном объёме через котировку протокола пула — с учётом price impact.
+    /// Bellman-Ford находит минимальные пути, отрицательный цикл = прибыль
+    fn calculate_edge_weight(pool: &PoolState, a_to_b: bool, reference_amount: u64) -> Result<f64> {
+        if reference_amount == 0 {
+            anyhow::bail!("Нулевой эталонный объём");
+        }
 
-        // Обменный курс с учётом комиссии
-        let exchange_rate = (reserve_out / reserve_in) * fee_multiplier;
+        let amount_out = quote_exact_in(pool, reference_amount, a_to_b)?;
+        if amount_out == 0 {
+            anyhow::bail!("Нулевой выход на эталонном объёме {}", reference_amount);
+        }
+
+        // Эффективный курс на эталонном объёме (комиссия и проскальзывание включены)
+        let effective_rate = amount_out as f64 / reference_amount as f64;
 
-        // Вес = -log(exchange_rate)
+        // Вес = -log(effective_rate)
         // Отрицательный цикл означает произведение курсов > 1 (прибыль)
-        let weight = -(exchange_rate.ln());
+        Ok(-(effective_rate.ln()))
+    }
+
+    /// Эталонный объём сделки в атомах токена (как стартовая сумма в OpportunityEvaluator)
+    pub fn reference_amount(&self, token: &Pubkey) -> u64 {
+        let decimals = self.token_decimals.get(token).copied().unwrap_or(9);
+        (self.reference_amount_ui * 10f64.powi(decimals as i32)) as u64
+    }
+
+    /// Сквозная котировка цикла с эталонного объёма стартового токена.
+    /// Возвращает (вход, выход) в атомах стартового токена
+    pub fn quote_cycle(&self, tokens: &[Pubkey], pool_ids: &[Pubkey]) -> Result<(u64, u64)> {
+        let initial_amount = self.reference_amount(&tokens[0]);
+        let mut amount = initial_amount;
 
-        Ok(weight)
+        for (i, pool_id) in pool_ids.iter().enumerate() {
+            let pool = self.pools.get(pool_id)
+                .ok_or_else(|| anyhow::anyhow!("Пул {} не найден в графе", pool_id))?;
+            amount = quote_exact_in(pool, amount, tokens[i] == pool.token_a)?;
+        }
+
+        Ok((initial_amount, amount))
     }
 
     /// Добавление токена если ещё не существует
@@ -119,6 +273,7 @@ impl PriceGraph {
             let index = self.index_to_token.len();
             self.token_to_index.insert(token, index);
             self.index_to_token.push(token);
+            self.adjacency.push(HashMap::new());
         }
     }
 
@@ -129,10 +284,21 @@ impl PriceGraph {
         let to_idx = self.token_to_index.get(&edge.to_token)
             .ok_or_else(|| anyhow::anyhow!("Токен не найден в графе"))?;
 
-        self.adjacency[*from_idx][*to_idx].push(edge);
+        self.adjacency[*from_idx].entry(*to_idx).or_default().push(edge);
+        self.edge_count += 1;
         Ok(())
     }
 
+    /// Ребро конкретного пула между двумя токенами
+    fn edge_mut(&mut self, from: &Pubkey, to: &Pubkey, pool_id: &Pubkey) -> Option<&mut PriceEdge> {
+        let from_idx = self.get_index(from)?;
+        let to_idx = self.get_index(to)?;
+        self.adjacency[from_idx]
+            .get_mut(&to_idx)?
+            .iter_mut()
+            .find(|e| e.pool_id == *pool_id)
+    }
+
     /// Получение токена по индексу
     pub fn get_token(&self, index: usize) -> Option<&Pubkey> {
         self.index_to_token.get(index)
@@ -145,7 +311,85 @@ impl PriceGraph {
 
     /// Получение всех рёбер между двумя токенами
     pub fn get_edges(&self, from: usize, to: usize) -> &[PriceEdge] {
-        &self.adjacency[from][to]
+        self.adjacency
+            .get(from)
+            .and_then(|row| row.get(&to))
+            .map(|edges| edges.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Соседи токена: (индекс соседа, рёбра к нему)
+    pub fn neighbors(&self, from: usize) -> impl Iterator<Item = (usize, &[PriceEdge])> + '_ {
+        self.adjacency[from].iter().map(|(to, edges)| (*to, edges.as_slice()))
+    }
+
+    /// Ребро пула в направлении от from_token
+    pub fn pool_edge(&self, pool_id: &Pubkey, from_token: &Pubkey) -> Option<&PriceEdge> {
+        let pool = self.pools.get(pool_id)?;
+        let to_token = if *from_token == pool.token_a { pool.token_b } else { pool.token_a };
+
+        self.get_edges(self.get_index(from_token)?, self.get_index(&to_token)?)
+            .iter()
+            .find(|e| e.pool_id == *pool_id)
+    }
+
+    /// Котируемые пулы между двумя токенами (параллельные рёбра from -> to)
+    pub fn parallel_pools(&self, from: &Pubkey, to: &Pubkey) -> Vec<&PoolState> {
+        let (Some(from_idx), Some(to_idx)) = (self.get_index(from), self.get_index(to)) else {
+            return vec![];
+        };
+
+        self.get_edges(from_idx, to_idx)
+            .iter()
+            .filter(|edge| edge.weight.is_finite())
+            .filter_map(|edge| self.get_pool(&edge.pool_id))
+            .collect()
+    }
+
+    /// Курс from -> to (атомов to за атом from) по лучшему прямому ребру
+    /// или через одного посредника; веса — на эталонном объёме from
+    pub fn conversion_rate(&self, from: &Pubkey, to: &Pubkey) -> Option<f64> {
+        if from == to {
+            return Some(1.0);
+        }
+
+        let from_idx = self.get_index(from)?;
+        let to_idx = self.get_index(to)?;
+
+        let best_rate = |a: usize, b: usize| -> Option<f64> {
+            self.get_edges(a, b)
+                .iter()
+                .map(|e| e.weight)
+                .filter(|w| w.is_finite())
+                .min_by(|x, y| x.total_cmp(y))
+                .map(|w| (-w).exp())
+        };
+
+        best_rate(from_idx, to_idx).or_else(|| {
+            self.neighbors(from_idx)
+                .filter_map(|(mid, _)| Some(best_rate(from_idx, mid)? * best_rate(mid, to_idx)?))
+                .max_by(|x, y| x.total_cmp(y))
+        })
+    }
+
+    /// Decimals токена
+    pub fn token_decimals(&self, token: &Pubkey) -> Option<u8> {
+        self.token_decimals.get(token).copied()
+    }
+
+    /// Пул графа по id
+    pub fn get_pool(&self, pool_id: &Pubkey) -> Option<&PoolState> {
+        self.pools.get(pool_id).map(Arc::as_ref)
+    }
+
+    /// Все пулы графа
+    pub fn pools(&self) -> impl Iterator<Item = &PoolState> {
+        self.pools.values().map(Arc::as_ref)
+    }
+
+    /// Количество пулов в графе
+    pub fn pool_count(&self) -> usize {
+        self.pools.len()
     }
 
     /// Количество токенов в графе
@@ -155,9 +399,120 @@ impl PriceGraph {
 
     /// Количество рёбер в графе
     pub fn edge_count(&self) -> usize {
-        self.adjacency.iter()
-            .flat_map(|row| row.iter())
-            .map(|edges| edges.len())
-            .sum()
+        self.edge_count
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arbitrage::bellman_ford::BellmanFordSolver;
+    use crate::arbitrage::cycle_index::CycleIndex;
+    use crate::types::DexProtocol;
+
+    fn cpmm_pool(token_a: Pubkey, token_b: Pubkey, reserve_a: u64, reserve_b: u64) -> Arc<PoolState> {
+        Arc::new(PoolState {
+            id: Pubkey::new_unique(),
+            protocol: DexProtocol::RaydiumCpmm,
+            token_a,
+            token_b,
+            reserve_a,
+            reserve_b,
+            fee_bps: 25,
+            last_updated: 0,
+            full_state_data: vec![],
+            decimals_a: 9,
+            decimals_b: 9,
+            liquidity_accounts: vec![],
+        })
+    }
+
+    #[test]
+    fn test_edge_weight_includes_price_impact() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let deep = cpmm_pool(a, b, 1_000_000_000_000, 1_000_000_000_000);
+        let shallow = cpmm_pool(a, b, 2_000_000_000, 2_000_000_000);
+
+        // Одинаковая mid-цена, но на 1 токене мелкий пул теряет ~33% на проскальзывании
+        let graph = PriceGraph::new(1.0).build_from_pools(&[deep.clone(), shallow.clone()]).unwrap();
+        let edges = graph.get_edges(graph.get_index(&a).unwrap(), graph.get_index(&b).unwrap());
+
+        let weight = |pool: &PoolState| edges.iter().find(|e| e.pool_id == pool.id).unwrap().weight;
+        assert!(weight(&deep) < 0.005);
+        assert!(weight(&shallow) > 0.4);
+        assert_eq!(edges[0].reference_amount, 1_000_000_000);
+    }
+
+    #[test]
+    fn test_phantom_cycle_filtered_during_search() {
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let unit = 1_000_000_000u64;
+
+        // Mid-цена A→B в мелком пуле на 10% выгоднее, но объём 1 токен её съедает
+        let pools = vec![
+            cpmm_pool(a, b, 2 * unit, 22 * unit / 10),
+            cpmm_pool(b, c, 1_000_000 * unit, 1_000_000 * unit),
+            cpmm_pool(c, a, 1_000_000 * unit, 1_000_000 * unit),
+        ];
+
+        let solver = BellmanFordSolver::new();
+        let find = |graph: &PriceGraph| {
+            let mut index = CycleIndex::new(3, 64);
+            index.add_pools(graph, &[pools[0].id]);
+            solver.find_negative_cycles(graph, &index.cycles_touching(&[pools[0].id])).unwrap()
+        };
+
+        assert!(!find(&PriceGraph::new(0.001).build_from_pools(&pools).unwrap()).is_empty());
+        assert!(find(&PriceGraph::new(1.0).build_from_pools(&pools).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_incremental_update_in_place() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let unit = 1_000_000_000u64;
+        let mut pool = cpmm_pool(a, b, 1_000 * unit, 1_000 * unit);
+
+        let mut graph = PriceGraph::new(1.0);
+        assert_eq!(graph.sync_pools(&[pool.clone()]).unwrap().added, vec![pool.id]);
+        let (ia, ib) = (graph.get_index(&a).unwrap(), graph.get_index(&b).unwrap());
+        let before = graph.get_edges(ia, ib)[0].weight;
+
+        // Повторная синхронизация без изменений ничего не трогает
+        assert!(graph.sync_pools(&[pool.clone()]).unwrap().is_empty());
+
+        // Новый снимок с теми же данными: без перекотировки, но время наблюдения свежее
+        let rescanned = [Arc::new(PoolState { last_updated: 10, ..(*pool).clone() })];
+        assert!(graph.sync_pools(&rescanned).unwrap().is_empty());
+        assert_eq!(graph.get_pool(&pool.id).unwrap().last_updated, 10);
+
+        Arc::make_mut(&mut pool).reserve_b = 1_100 * unit;
+        assert_eq!(graph.sync_pools(&[pool.clone()]).unwrap().updated, vec![pool.id]);
+        assert_eq!(graph.edge_count(), 2);
+        assert!(graph.get_edges(ia, ib)[0].weight < before);
+
+        // Пул пропал из снимка — рёбра удаляются
+        assert_eq!(graph.sync_pools(&[]).unwrap().removed, vec![pool.id]);
+        assert_eq!(graph.edge_count(), 0);
+        assert!(graph.get_edges(ia, ib).is_empty());
+    }
+
+    #[test]
+    fn test_conversion_rate_direct_and_two_hop() {
+        let (sol, usdc, bonk) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let unit = 1_000_000_000u64;
+        let pools = vec![
+            cpmm_pool(sol, usdc, 1_000_000 * unit, 150_000_000 * unit),
+            cpmm_pool(usdc, bonk, 1_000_000 * unit, 50_000_000 * unit),
+        ];
+        let graph = PriceGraph::new(0.01).build_from_pools(&pools).unwrap();
+
+        let direct = graph.conversion_rate(&sol, &usdc).unwrap();
+        assert!((direct / 150.0 - 1.0).abs() < 0.003);
+
+        let two_hop = graph.conversion_rate(&sol, &bonk).unwrap();
+        assert!((two_hop / 7_500.0 - 1.0).abs() < 0.006);
+
+        assert_eq!(graph.conversion_rate(&sol, &sol), Some(1.0));
+        assert!(graph.conversion_rate(&sol, &Pubkey::new_unique()).is_none());
+    }
+}
@@ -0,0 +1,142 @@
+// bot/src/arbitrage/in_flight.rs
+// Общее состояние исполняемых возможностей: полный проход и backrun находят одни
+// и те же циклы и стартуют с одних ATA, поэтому возможность, чьи пулы или
+// стартовый токен уже в полёте, не отправляется
+
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use crate::types::ArbitrageOpportunity;
+
+/// Пулы и стартовые токены возможностей, исполнение которых ещё не завершено
+#[derive(Default)]
+pub struct InFlight {
+    /// Pubkey пулов и mint'ов стартовых токенов (пространства не пересекаются)
+    claimed: Mutex<HashSet<Pubkey>>,
+}
+
+/// Захват одной возможности; пулы и стартовый токен освобождаются при drop
+pub struct InFlightClaim<'a> {
+    owner: &'a InFlight,
+    keys: Vec<Pubkey>,
+}
+
+impl InFlight {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Захват всех пулов возможности (включая разбиения перехода) и её стартового
+    /// токена: объём цикла рассчитан на весь баланс ATA, поэтому с одного ATA
+    /// одновременно исполняется не больше одной возможности.
+    /// None, если что-то из этого уже исполняется
+    pub fn try_claim(&self, opportunity: &ArbitrageOpportunity) -> Option<InFlightClaim<'_>> {
+        let mut keys: Vec<Pubkey> = opportunity.legs.iter().flat_map(|leg| leg.pool_ids()).collect();
+        keys.extend(opportunity.legs.first().map(|leg| leg.input_mint));
+        keys.sort();
+        keys.dedup();
+
+        let mut claimed = self.claimed.lock().unwrap_or_else(|e| e.into_inner());
+        if keys.iter().any(|key| claimed.contains(key)) {
+            return None;
+        }
+        claimed.extend(keys.iter().copied());
+
+        Some(InFlightClaim { owner: self, keys })
+    }
+}
+
+impl Drop for InFlightClaim<'_> {
+    fn drop(&mut self) {
+        let mut claimed = self.owner.claimed.lock().unwrap_or_else(|e| e.into_inner());
+        for key in &self.keys {
+            claimed.remove(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DexProtocol, SubSwap, SwapLeg};
+
+    fn opportunity(pool_ids: &[Pubkey]) -> ArbitrageOpportunity {
+        let legs = pool_ids.iter()
+            .map(|&pool_id| SwapLeg {
+                pool_id,
+                input_mint: Pubkey::new_unique(),
+                output_mint: Pubkey::new_unique(),
+                input_decimals: 9,
+                output_decimals: 9,
+                amount_in: 1_000_000,
+                minimum_amount_out: 0,
+                estimated_amount_out: 1_000_000,
+                sub_swaps: vec![SubSwap {
+                    protocol: DexProtocol::RaydiumCpmm,
+                    pool_id,
+                    amount_in: 1_000_000,
+                    minimum_amount_out: 0,
+                    estimated_amount_out: 1_000_000,
+                    fee_bps: 25,
+                    spot_rate: 1.0,
+                    pool_state_data: vec![],
+                    arrays_crossed: 0,
+                }],
+            })
+            .collect();
+
+        ArbitrageOpportunity {
+            legs,
+            initial_amount: 1_000_000,
+            expected_final_amount: 1_000_000,
+            gross_profit: 0,
+            net_profit: 0,
+            net_profit_lamports: 0,
+            min_net_profit: 0,
+            profit_percentage: 0.0,
+            discovered_at: 0,
+            oldest_pool_update: 0,
+            score: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_claim_blocks_overlapping_until_released() {
+        let in_flight = InFlight::new();
+        let pools: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+
+        let claim = in_flight.try_claim(&opportunity(&pools[0..2])).unwrap();
+        // Тот же цикл из backrun и цикл через общий пул не отправляются
+        assert!(in_flight.try_claim(&opportunity(&pools[0..2])).is_none());
+        assert!(in_flight.try_claim(&opportunity(&pools[1..3])).is_none());
+        // Отказ не захватывает ничего: непересекающийся цикл проходит
+        let other = in_flight.try_claim(&opportunity(&pools[2..4])).unwrap();
+
+        // Завершение исполнения освобождает пулы
+        drop(claim);
+        drop(other);
+        assert!(in_flight.try_claim(&opportunity(&pools[1..3])).is_some());
+    }
+
+    #[test]
+    fn test_claim_one_execution_per_start_mint() {
+        let in_flight = InFlight::new();
+        let sol = Pubkey::new_unique();
+        let from_sol = |pools: &[Pubkey]| {
+            let mut opportunity = opportunity(pools);
+            opportunity.legs[0].input_mint = sol;
+            opportunity
+        };
+
+        let pools: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let claim = in_flight.try_claim(&from_sol(&pools[0..2])).unwrap();
+
+        // Пулы другие, но баланс SOL уже отдан исполняемому циклу
+        assert!(in_flight.try_claim(&from_sol(&pools[2..4])).is_none());
+        assert!(in_flight.try_claim(&opportunity(&pools[2..4])).is_some());
+
+        drop(claim);
+        assert!(in_flight.try_claim(&from_sol(&pools[2..4])).is_some());
+    }
+}
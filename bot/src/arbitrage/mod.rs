@@ -6,80 +6,289 @@ pub mod bellman_ford;
 pub mod opportunity;
 pub mod profit_calculator;
 pub mod pool_math;
+pub mod clmm_quote;
+pub mod dlmm_quote;
+pub mod cycle_index;
+pub mod scoring;
+pub mod reputation;
+pub mod split_route;
+pub mod prune;
+pub mod in_flight;
 
 use anyhow::Result;
-use std::sync::Arc;
-use tracing::{info, debug};
+use rayon::prelude::*;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{info, debug, warn};
 
 use crate::config::BotConfig;
 use crate::types::{PoolState, ArbitrageOpportunity};
 use graph::PriceGraph;
-use bellman_ford::BellmanFordSolver;
+use cycle_index::{cycle_signature, CandidateCycle, CycleIndex, CycleSignature};
+use bellman_ford::{ArbitrageCycle, BellmanFordSolver};
 use opportunity::OpportunityEvaluator;
+use scoring::OpportunityScorer;
+use reputation::{FailureKind, ReputationStore};
+use in_flight::{InFlight, InFlightClaim};
+
+/// Лимит кандидатных циклов, перечисляемых через один новый пул
+const MAX_CYCLES_PER_POOL: usize = 256;
+
+/// Результат поиска возможностей
+#[derive(Debug, Default)]
+pub struct SearchResult {
+    /// Возможности по убыванию чистой прибыли
+    pub opportunities: Vec<ArbitrageOpportunity>,
+    /// Поиск прерван дедлайном: часть кандидатов не оценена,
+    /// их пулы переоцениваются следующим полным проходом
+    pub partial: bool,
+}
+
+/// Состояние поиска, живущее между итерациями
+struct SearchState {
+    /// Граф цен, обновляемый инкрементально
+    graph: PriceGraph,
+    /// Кандидатные циклы по id пула
+    index: CycleIndex,
+    /// Пулы, циклы через которые не оценены из-за дедлайна
+    deferred: HashSet<Pubkey>,
+}
 
 pub struct ArbitrageFinder {
     config: Arc<BotConfig>,
-    graph_builder: PriceGraph,
+    state: RwLock<SearchState>,
     solver: BellmanFordSolver,
     evaluator: OpportunityEvaluator,
+    scorer: OpportunityScorer,
+    reputation: ReputationStore,
+    /// Пулы и стартовые токены исполняемых возможностей (общие для полного прохода и backrun)
+    in_flight: InFlight,
 }
 
 impl ArbitrageFinder {
     pub fn new(config: Arc<BotConfig>) -> Self {
         Self {
             config: config.clone(),
-            graph_builder: PriceGraph::new(),
+            state: RwLock::new(SearchState {
+                graph: PriceGraph::new(config.trading.initial_amount_sol),
+                index: CycleIndex::new(config.trading.max_legs as usize, MAX_CYCLES_PER_POOL),
+                deferred: HashSet::new(),
+            }),
             solver: BellmanFordSolver::new(),
-            evaluator: OpportunityEvaluator::new(config),
+            evaluator: OpportunityEvaluator::new(config.clone()),
+            scorer: OpportunityScorer::new(config.clone()),
+            reputation: ReputationStore::new(&config.reputation),
+            in_flight: InFlight::new(),
         }
     }
 
-    /// Поиск всех арбитражных возможностей в заданных пулах
-    pub fn find_opportunities(&self, pools: &[PoolState]) -> Result<Vec<ArbitrageOpportunity>> {
+    /// Поиск всех арбитражных возможностей в заданных пулах.
+    /// Бюджет trading.search_budget_ms отсчитывается от входа: синхронизация графа,
+    /// прунинг и перечисление циклов расходуют его до оценки. Перечисление дедлайном
+    /// не прерывается, прерывается только оценка. Синхронный CPU-bound вызов:
+    /// из async-контекста — через block_in_place
+    pub fn find_opportunities(&self, pools: &[Arc<PoolState>]) -> Result<SearchResult> {
         if pools.is_empty() {
-            return Ok(vec![]);
+            return Ok(SearchResult::default());
         }
 
-        info!("🔍 Построение графа цен из {} пулов...", pools.len());
+        let started = Instant::now();
+        let deadline = started + Duration::from_millis(self.config.trading.search_budget_ms);
+        info!("🔍 Обновление графа цен из {} пулов...", pools.len());
 
-        // Шаг 1: Построение графа цен
-        let graph = self.graph_builder.build_from_pools(pools)?;
-        debug!("   Граф содержит {} токенов, {} рёбер",
-           graph.token_count(),
-           graph.edge_count());
+        let changed = {
+            let mut guard = self.state.write()
+                .map_err(|_| anyhow::anyhow!("Состояние поиска отравлено"))?;
+            self.sync_state(&mut guard, pools)?
+        };
 
-        // Шаг 2: Поиск отрицательных циклов через Bellman-Ford
-        info!("🧮 Применение алгоритма Bellman-Ford для поиска циклов...");
-        let cycles = self.solver.find_negative_cycles(&graph, self.config.trading.max_legs as usize)?;
+        // Оценка под блокировкой чтения: backrun-события обновляют пулы и ищут
+        // циклы параллельно с полным проходом
+        let guard = self.state.read()
+            .map_err(|_| anyhow::anyhow!("Состояние поиска отравлено"))?;
 
-        if cycles.is_empty() {
-            debug!("   Отрицательных циклов не найдено");
-            return Ok(vec![]);
+        // Шаг 4: Переоценка только циклов через изменившиеся и вошедшие в ядро пулы
+        let touched = guard.index.cycles_touching(&changed);
+        if touched.is_empty() {
+            debug!("   Нет циклов через изменившиеся пулы (в индексе {})", guard.index.len());
+            return Ok(SearchResult::default());
         }
 
-        info!("   Найдено потенциальных циклов: {}", cycles.len());
+        // Шаг 5: Параллельная оценка циклов по стартовым токенам до дедлайна
+        info!("🧮 Переоценка {} из {} циклов...", touched.len(), guard.index.len());
+        let result = self.search(&guard.graph, touched, deadline);
+        drop(guard);
 
-        // ДИАГНОСТИКА: Показать информацию о каждом цикле
-        for (i, cycle) in cycles.iter().enumerate() {
-            info!("🔄 Цикл #{}: {} токенов, вес {:.6}",
-             i + 1, cycle.tokens.len(), cycle.total_weight);
-            info!("   Токены: {:?}", cycle.tokens.iter()
-             .map(|t| format!("{}...", &t.to_string()[..8]))
-             .collect::<Vec<_>>());
+        // Какие именно циклы пропущены, не отслеживается: переносятся все пулы прохода
+        if result.partial {
+            self.defer(changed)?;
+        }
+        info!("✅ Найдено прибыльных возможностей: {} за {:?}",
+              result.opportunities.len(), started.elapsed());
+
+        Ok(result)
+    }
+
+    /// Шаги 1–3 полного прохода под блокировкой записи: граф, ядро и индекс циклов.
+    /// Возвращает пулы, циклы через которые нужно переоценить
+    fn sync_state(&self, state: &mut SearchState, pools: &[Arc<PoolState>]) -> Result<Vec<Pubkey>> {
+        // Шаг 1: Инкрементальное обновление графа цен (веса рёбер — на месте)
+        let update = state.graph.sync_pools(pools)?;
+        debug!("   Граф содержит {} токенов, {} рёбер; пулов: +{} ~{} -{}",
+           state.graph.token_count(),
+           state.graph.edge_count(),
+           update.added.len(),
+           update.updated.len(),
+           update.removed.len());
+
+        // Шаг 2: Сжатие до циклического ядра (листья, SCC, порог ликвидности)
+        let core = prune::cyclic_core(&state.graph, self.config.trading.min_liquidity_multiple);
+        for step in &core.steps {
+            info!("✂️ Прунинг #{} ({}): {} токенов, {} пулов",
+                  step.iteration, step.stage, step.tokens, step.pools);
+        }
+        info!("   Ядро графа: токенов {} -> {}, пулов {} -> {}",
+              core.tokens_before, core.token_count(), core.pools_before, core.pools.len());
+
+        // Шаг 3: Обновление индекса циклов (топология меняется редко).
+        // Перечисление не прерывается дедлайном: недостроенный индекс не восстановится
+        state.index.remove_pools(&update.removed);
+        let entered = state.index.set_core(&state.graph, core.pools);
+
+        // Плюс пулы, не оценённые прошлыми проходами из-за дедлайна
+        let mut changed: HashSet<Pubkey> = std::mem::take(&mut state.deferred);
+        changed.extend(update.changed());
+        changed.extend(entered);
+        changed.retain(|pool_id| state.graph.get_pool(pool_id).is_some());
+        Ok(changed.into_iter().collect())
+    }
+
+    /// Перенос пулов с неоценёнными циклами на следующий полный проход
+    fn defer(&self, pool_ids: impl IntoIterator<Item = Pubkey>) -> Result<()> {
+        let mut guard = self.state.write()
+            .map_err(|_| anyhow::anyhow!("Состояние поиска отравлено"))?;
+        guard.deferred.extend(pool_ids);
+        Ok(())
+    }
+
+    /// Обновление одного пула в графе (событие PoolMonitor) без полного снимка
+    pub fn update_pool(&self, pool: &Arc<PoolState>) -> Result<bool> {
+        let mut guard = self.state.write()
+            .map_err(|_| anyhow::anyhow!("Состояние поиска отравлено"))?;
+        let state = &mut *guard;
+
+        let is_new = state.graph.get_pool(&pool.id).is_none();
+        let changed = state.graph.upsert_pool(pool)?;
+        // Новый пул попадёт в индекс, только если он уже в ядре (иначе — при следующем прунинге)
+        if is_new {
+            state.index.add_pools(&state.graph, &[pool.id]);
+        }
+
+        Ok(changed)
+    }
+
+    /// Backrun: лучшие возможности только через циклы заданного пула
+    /// в пределах бюджета задержки (trading.backrun_budget_ms)
+    pub fn find_opportunities_through(&self, pool_id: &Pubkey) -> Result<SearchResult> {
+        let started = Instant::now();
+        let deadline = started + Duration::from_millis(self.config.trading.backrun_budget_ms);
+
+        let guard = self.state.read()
+            .map_err(|_| anyhow::anyhow!("Состояние поиска отравлено"))?;
+
+        let touched = guard.index.cycles_touching(&[*pool_id]);
+        if touched.is_empty() {
+            return Ok(SearchResult::default());
+        }
+
+        let candidates = touched.len();
+        let result = self.search(&guard.graph, touched, deadline);
+        drop(guard);
+
+        if result.partial {
+            self.defer([*pool_id])?;
         }
 
-        // Шаг 3: Оценка прибыльности каждого цикла
-        let mut opportunities = Vec::new();
+        info!("⚡ Backrun через {}: {} циклов, {} возможностей за {:?}{}",
+              pool_id, candidates, result.opportunities.len(), started.elapsed(),
+              if result.partial { " (частично)" } else { "" });
+
+        Ok(result)
+    }
+
+    /// Параллельный поиск: кандидаты группируются по стартовому токену,
+    /// группы оцениваются на пуле rayon. Внутри группы циклы идут от самого
+    /// отрицательного веса, поэтому при дедлайне отбрасываются худшие
+    fn search(&self, graph: &PriceGraph, candidates: Vec<&CandidateCycle>, deadline: Instant) -> SearchResult {
+        let mut groups: HashMap<Pubkey, Vec<&CandidateCycle>> = HashMap::new();
+        for candidate in candidates {
+            groups.entry(candidate.tokens[0]).or_default().push(candidate);
+        }
+
+        let mut result = groups.into_par_iter()
+            .map(|(start, group)| {
+                match self.solver.find_negative_cycles_until(graph, &group, Some(deadline)) {
+                    Ok((cycles, interrupted)) => {
+                        let mut found = self.evaluate_cycles(&cycles, graph, Some(deadline));
+                        found.partial |= interrupted;
+                        found
+                    }
+                    Err(e) => {
+                        warn!("⚠️ Ошибка поиска от токена {}: {}", start, e);
+                        SearchResult::default()
+                    }
+                }
+            })
+            .reduce(SearchResult::default, |mut acc, found| {
+                acc.opportunities.extend(found.opportunities);
+                acc.partial |= found.partial;
+                acc
+            });
+
+        // Сортировка по убыванию прибыли в SOL
+        result.opportunities.sort_by_key(|o| std::cmp::Reverse(o.net_profit_lamports));
+        result
+    }
+
+    /// Оценка циклов через OpportunityEvaluator.
+    /// Циклы уже отсортированы по весу, поэтому при дедлайне отбрасываются худшие
+    fn evaluate_cycles(
+        &self,
+        cycles: &[ArbitrageCycle],
+        graph: &PriceGraph,
+        deadline: Option<Instant>,
+    ) -> SearchResult {
+        let mut result = SearchResult::default();
 
         for (i, cycle) in cycles.iter().enumerate() {
+            let now = Instant::now();
+            if deadline.is_some_and(|d| now >= d) {
+                debug!("⏱️ Дедлайн оценки: пропущено {} циклов", cycles.len() - i);
+                result.partial = true;
+                break;
+            }
+
+            let signature = cycle_signature(
+                cycle.pool_ids.iter().copied().zip(cycle.tokens.iter().copied()),
+            );
+            if !self.reputation.is_allowed(&signature, &cycle.pool_ids, now) {
+                debug!("🧊 Цикл #{} пропущен: кулдаун или бан пула", i + 1);
+                continue;
+            }
+
             info!("🧮 === АНАЛИЗ ЦИКЛА #{} ===", i + 1);
 
-            match self.evaluator.evaluate_cycle(cycle, pools) {
+            // Параллельные пулы для разбиения перехода тоже проходят проверку репутации
+            let is_pool_allowed = |pool_id: &Pubkey| self.reputation.is_pool_allowed(pool_id, now);
+
+            match self.evaluator.evaluate_cycle(cycle, graph, is_pool_allowed) {
                 Ok(Some(opp)) => {
                     info!("✅ Цикл #{} ПРИБЫЛЕН!", i + 1);
                     // Проверка минимальной прибыли
-                    if opp.net_profit >= self.config.trading.min_profit_lamports {
-                        opportunities.push(opp);
+                    if opp.is_profitable(self.config.trading.min_profit_lamports) {
+                        result.opportunities.push(opp);
                     }
                 }
                 Ok(None) => {
@@ -91,11 +300,47 @@ impl ArbitrageFinder {
             }
         }
 
-        // Сортировка по убыванию прибыли
-        opportunities.sort_by(|a, b| b.net_profit.cmp(&a.net_profit));
+        result
+    }
 
-        info!("✅ Найдено прибыльных возможностей: {}", opportunities.len());
+    /// Риск-скорректированный выбор набора возможностей без общих пулов и стартовых токенов
+    pub fn select_opportunities(&self, opportunities: Vec<ArbitrageOpportunity>) -> Vec<ArbitrageOpportunity> {
+        self.scorer.select(opportunities)
+    }
 
-        Ok(opportunities)
+    /// Захват пулов и стартового токена возможности на время исполнения.
+    /// None — через эти пулы или с этого ATA уже исполняется другая возможность
+    /// (тот же цикл из полного прохода и backrun, весь баланс ATA уже в сделке)
+    pub fn claim(&self, opportunity: &ArbitrageOpportunity) -> Option<InFlightClaim<'_>> {
+        self.in_flight.try_claim(opportunity)
     }
-}
\ No newline at end of file
+
+    /// Балансы ATA кошелька: выбор стартового токена цикла и лимит объёма
+    pub fn set_balances(&self, balances: HashMap<Pubkey, u64>) {
+        self.evaluator.set_balances(balances);
+    }
+
+    /// Учёт успешного исполнения (история успеха, сброс кулдаунов)
+    pub fn record_success(&self, opportunity: &ArbitrageOpportunity) {
+        self.scorer.record_outcome(opportunity, true);
+        let (signature, pool_ids) = Self::opportunity_key(opportunity);
+        self.reputation.record_success(&signature, &pool_ids);
+    }
+
+    /// Учёт неудачи исполнения: история успеха, кулдауны цикла и пулов, авто-бан.
+    /// Только для неудач самого цикла (откат, провал симуляции, отказ оракула)
+    pub fn record_failure(&self, opportunity: &ArbitrageOpportunity, kind: FailureKind) {
+        self.scorer.record_outcome(opportunity, false);
+        let (signature, pool_ids) = Self::opportunity_key(opportunity);
+        self.reputation.record_failure(signature, &pool_ids, kind, Instant::now());
+    }
+
+    /// Подпись цикла (по пулам цикла) и все пулы возможности, включая разбиения
+    fn opportunity_key(opportunity: &ArbitrageOpportunity) -> (CycleSignature, Vec<Pubkey>) {
+        let signature = cycle_signature(
+            opportunity.legs.iter().map(|leg| (leg.pool_id, leg.input_mint)),
+        );
+        let pool_ids = opportunity.legs.iter().flat_map(|leg| leg.pool_ids()).collect();
+        (signature, pool_ids)
+    }
+}
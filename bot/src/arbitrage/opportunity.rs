@@ -2,20 +2,32 @@
 // Оценка и валидация арбитражных возможностей
 
 use anyhow::Result;
-use std::collections::HashMap;
-use std::sync::Arc;
-use tracing::info;
-
 use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use tracing::{debug, info};
 
 use super::bellman_ford::ArbitrageCycle;
+use super::graph::PriceGraph;
+use super::pool_math::{calculate_minimum_amount_out, liquidity_arrays_crossed, spot_rate};
 use super::profit_calculator::ProfitCalculator;
+use super::split_route::split_hop;
 use crate::config::BotConfig;
-use crate::types::{ArbitrageOpportunity, DexProtocol, PoolState, SwapLeg};
+use crate::types::{ArbitrageOpportunity, SubSwap, SwapLeg};
+
+/// Стартовая точка цикла: ротация, с которой он исполняется
+struct CycleStart {
+    tokens: Vec<Pubkey>,
+    pool_ids: Vec<Pubkey>,
+    amount: u64,
+}
 
 pub struct OpportunityEvaluator {
     config: Arc<BotConfig>,
     profit_calc: ProfitCalculator,
+    /// Балансы ATA кошелька по mint.
+    /// None — балансы не загружены: старт с tokens[0] на эталонный объём
+    balances: RwLock<Option<HashMap<Pubkey, u64>>>,
 }
 
 impl OpportunityEvaluator {
@@ -23,25 +35,65 @@ impl OpportunityEvaluator {
         Self {
             profit_calc: ProfitCalculator::new(config.clone()),
             config,
+            balances: RwLock::new(None),
         }
     }
 
-    /// Строим таблицу decimals по mint-адресам из списка пулов.
-    fn build_decimals_map(&self, pools: &[PoolState]) -> HashMap<Pubkey, u8> {
-        let mut m = HashMap::new();
-        for p in pools {
-            // Заполняем только если не было значений ранее
-            m.entry(p.token_a).or_insert(p.decimals_a);
-            m.entry(p.token_b).or_insert(p.decimals_b);
+    /// Обновление балансов кошелька (и набора существующих ATA для расчёта ренты)
+    pub fn set_balances(&self, balances: HashMap<Pubkey, u64>) {
+        self.profit_calc.set_existing_atas(balances.keys().copied().collect());
+        if let Ok(mut guard) = self.balances.write() {
+            *guard = Some(balances);
         }
-        m
+    }
+
+    /// Выбор ротации цикла, начинающейся с токена, который есть в кошельке.
+    /// Объём — эталонный, но не больше баланса ATA; среди держимых токенов
+    /// выбирается самый крупный объём в пересчёте на SOL. Баланс не делится
+    /// между возможностями: с одного ATA одновременно исполняется одна (InFlight)
+    fn choose_start(&self, cycle: &ArbitrageCycle, graph: &PriceGraph) -> Option<CycleStart> {
+        let legs = cycle.pool_ids.len();
+        let guard = self.balances.read().ok()?;
+
+        let Some(balances) = guard.as_ref() else {
+            return Some(CycleStart {
+                tokens: cycle.tokens.clone(),
+                pool_ids: cycle.pool_ids.clone(),
+                amount: graph.reference_amount(&cycle.tokens[0]),
+            });
+        };
+
+        let (offset, amount) = (0..legs)
+            .filter_map(|offset| {
+                let token = cycle.tokens[offset];
+                let balance = balances.get(&token).copied().unwrap_or(0);
+                let amount = graph.reference_amount(&token).min(balance);
+                if amount == 0 {
+                    return None;
+                }
+
+                let value_lamports = graph
+                    .conversion_rate(&token, &spl_token::native_mint::ID)
+                    .map_or(0.0, |rate| amount as f64 * rate);
+                Some((offset, amount, value_lamports))
+            })
+            .max_by(|a, b| a.2.total_cmp(&b.2))
+            .map(|(offset, amount, _)| (offset, amount))?;
+
+        let mut tokens: Vec<Pubkey> = cycle.tokens[offset..legs].to_vec();
+        tokens.extend_from_slice(&cycle.tokens[..=offset]);
+        let mut pool_ids: Vec<Pubkey> = cycle.pool_ids[offset..].to_vec();
+        pool_ids.extend_from_slice(&cycle.pool_ids[..offset]);
+
+        Some(CycleStart { tokens, pool_ids, amount })
     }
 
     /// Оценка цикла и создание ArbitrageOpportunity
     pub fn evaluate_cycle(
         &self,
         cycle: &ArbitrageCycle,
-        pools: &[PoolState],
+        graph: &PriceGraph,
+        is_pool_allowed: impl Fn(&Pubkey) -> bool,
     ) -> Result<Option<ArbitrageOpportunity>> {
         // Минимум три токена (A -> B -> C -> A)
         if cycle.tokens.len() < 3 {
@@ -51,18 +103,17 @@ impl OpportunityEvaluator {
         info!("🔍 === ДЕТАЛЬНАЯ ДИАГНОСТИКА ЦИКЛА ===");
         info!("Токенов в цикле: {}", cycle.tokens.len());
 
-        // Построим карту decimals из пулов
-        let decimals_map = self.build_decimals_map(pools);
-
-        // Начальная сумма: интерпретируем initial_amount_sol как количество в UI-единицах
-        // стартового токена и переводим в атомы стартового токена.
-        let start_mint = cycle.tokens[0];
-        let start_decimals = *decimals_map
-            .get(&start_mint)
+        // Старт с токена из кошелька; объём — initial_amount_sol в UI-единицах
+        // стартового токена, ограниченный балансом ATA
+        let Some(start) = self.choose_start(cycle, graph) else {
+            debug!("❌ ОТКЛОНЕНО: в кошельке нет ни одного токена цикла");
+            return Ok(None);
+        };
+        let start_mint = start.tokens[0];
+        let start_decimals = graph
+            .token_decimals(&start_mint)
             .ok_or_else(|| anyhow::anyhow!("Не найдены decimals для стартового токена"))?;
-        let ui_amount = self.config.trading.initial_amount_sol; // используем как UI количество
-        let mut current_amount: u64 =
-            (ui_amount * 10f64.powi(start_decimals as i32)) as u64;
+        let mut current_amount = start.amount;
 
         info!(
             "💰 Начальная сумма: {} atoms (mint: {}, decimals: {})",
@@ -71,22 +122,21 @@ impl OpportunityEvaluator {
 
         // Построение swap legs с детальным логированием
         let mut legs: Vec<SwapLeg> = Vec::new();
+        let mut oldest_update = i64::MAX;
+        // Пулы цикла и уже задействованные параллельные пулы: один пул — один своп
+        let mut used_pools: HashSet<Pubkey> = start.pool_ids.iter().copied().collect();
 
-        for i in 0..cycle.tokens.len() - 1 {
-            let input_mint = cycle.tokens[i];
-            let output_mint = cycle.tokens[i + 1];
+        for i in 0..start.tokens.len() - 1 {
+            let input_mint = start.tokens[i];
+            let output_mint = start.tokens[i + 1];
 
             info!("🔄 === СВОП #{} ===", i + 1);
             info!("От: {}", input_mint);
             info!("К:  {}", output_mint);
 
-            // Находим пул для этой пары
-            let pool = pools
-                .iter()
-                .find(|p| {
-                    (p.token_a == input_mint && p.token_b == output_mint)
-                        || (p.token_a == output_mint && p.token_b == input_mint)
-                })
+            // Пул, выбранный при поиске цикла
+            let pool = start.pool_ids.get(i)
+                .and_then(|pool_id| graph.get_pool(pool_id))
                 .ok_or_else(|| anyhow::anyhow!("Пул не найден для пары токенов"))?;
 
             info!("📊 Найден пул: {}", pool.id);
@@ -99,11 +149,50 @@ impl OpportunityEvaluator {
             let a_to_b = input_mint == pool.token_a;
             info!("🔀 Направление: {}", if a_to_b { "A→B" } else { "B→A" });
 
-            // Рассчитываем ожидаемый выход
-            let (estimated_out, min_out) =
-                self.calculate_swap_amounts(pool, current_amount, a_to_b)?;
+            // Параллельные пулы пары, не занятые другими переходами цикла
+            let mut candidates = vec![pool];
+            candidates.extend(
+                graph.parallel_pools(&input_mint, &output_mint)
+                    .into_iter()
+                    .filter(|p| !used_pools.contains(&p.id) && is_pool_allowed(&p.id)),
+            );
+
+            // Разбиение входа между пулами с выравниванием предельных цен
+            let allocations = split_hop(&candidates, &input_mint, current_amount)?;
 
-            info!("💸 Входная сумма: {} atoms", current_amount);
+            let mut sub_swaps = Vec::with_capacity(allocations.len());
+            for allocation in &allocations {
+                let sub_pool = graph.get_pool(&allocation.pool_id)
+                    .ok_or_else(|| anyhow::anyhow!("Пул {} не найден", allocation.pool_id))?;
+                let min_out = calculate_minimum_amount_out(
+                    allocation.amount_out,
+                    self.config.trading.max_slippage_bps,
+                );
+
+                info!("   ↳ {:?} {}: {} -> {} atoms (мин. {})",
+                      sub_pool.protocol, sub_pool.id, allocation.amount_in, allocation.amount_out, min_out);
+
+                used_pools.insert(sub_pool.id);
+                oldest_update = oldest_update.min(sub_pool.last_updated);
+                let sub_a_to_b = input_mint == sub_pool.token_a;
+                sub_swaps.push(SubSwap {
+                    protocol: sub_pool.protocol,
+                    pool_id: sub_pool.id,
+                    amount_in: allocation.amount_in,
+                    minimum_amount_out: min_out,
+                    estimated_amount_out: allocation.amount_out,
+                    fee_bps: sub_pool.fee_bps,
+                    spot_rate: spot_rate(sub_pool, sub_a_to_b).unwrap_or(0.0),
+                    pool_state_data: sub_pool.full_state_data.clone(),
+                    arrays_crossed: liquidity_arrays_crossed(sub_pool, allocation.amount_in, sub_a_to_b)
+                        .min(u8::MAX as usize) as u8,
+                });
+            }
+
+            let estimated_out: u64 = sub_swaps.iter().map(|sub| sub.estimated_amount_out).sum();
+            let min_out: u64 = sub_swaps.iter().map(|sub| sub.minimum_amount_out).sum();
+
+            info!("💸 Входная сумма: {} atoms ({} пул(ов))", current_amount, sub_swaps.len());
             info!("💰 Ожидаемый выход: {} atoms", estimated_out);
             info!("📉 Минимальный выход: {} atoms", min_out);
 
@@ -127,15 +216,15 @@ impl OpportunityEvaluator {
             }
 
             let leg = SwapLeg {
-                protocol: pool.protocol,
                 pool_id: pool.id,
                 input_mint,
                 output_mint,
+                input_decimals: if a_to_b { pool.decimals_a } else { pool.decimals_b },
+                output_decimals: if a_to_b { pool.decimals_b } else { pool.decimals_a },
                 amount_in: current_amount,
                 minimum_amount_out: min_out,
                 estimated_amount_out: estimated_out,
-                fee_bps: pool.fee_bps,
-                pool_state_data: pool.full_state_data.clone(),
+                sub_swaps,
             };
 
             legs.push(leg);
@@ -156,24 +245,38 @@ impl OpportunityEvaluator {
             final_amount, start_mint, start_decimals
         );
 
-        // ProfitCalculator оставляем как есть — он работает на u64.
-        // В devnet не учитываем SOL комиссии (они в другой единице).
+        // Расходы в SOL переводим в атомы стартового токена по курсу из графа
+        let lamport_price = graph.conversion_rate(&spl_token::native_mint::ID, &start_mint);
+        let costs = self.profit_calc.execution_costs(&legs);
+        info!(
+            "🧾 Расходы: {} lamports (подпись {}, priority {}, Jito {}, ATA {}), курс: {:?} atoms/lamport",
+            costs.total_lamports(), costs.signature_fee, costs.priority_fee,
+            costs.jito_tip, costs.ata_rent, lamport_price
+        );
+
         let (gross_profit, net_profit) =
             self.profit_calc
-                .calculate_net_profit(initial_amount, final_amount, &legs)?;
+                .calculate_net_profit(initial_amount, final_amount, &legs, lamport_price)?;
+
+        // Прибыль в lamports для сравнения циклов с разными стартовыми токенами
+        // (без курса SOL — devnet — атомы берутся как есть)
+        let net_profit_lamports = match lamport_price {
+            Some(price) if price > 0.0 => (net_profit as f64 / price) as i64,
+            _ => net_profit,
+        };
 
         info!("💎 Валовая прибыль: {} atoms", gross_profit);
-        info!("🏦 Чистая прибыль: {} atoms", net_profit);
+        info!("🏦 Чистая прибыль: {} atoms ({} lamports)", net_profit, net_profit_lamports);
         info!(
-            "📊 Минимальный порог: {} atoms",
+            "📊 Минимальный порог: {} lamports",
             self.config.trading.min_profit_lamports
         );
 
-        // Проверка прибыльности
-        if net_profit < self.config.trading.min_profit_lamports {
+        // Проверка прибыльности: порог задан в lamports, прибыль — в атомах стартового токена
+        if net_profit_lamports < self.config.trading.min_profit_lamports as i64 {
             info!(
-                "❌ ОТКЛОНЕНО: Прибыль {} < {} (порог)",
-                net_profit, self.config.trading.min_profit_lamports
+                "❌ ОТКЛОНЕНО: Прибыль {} < {} lamports (порог)",
+                net_profit_lamports, self.config.trading.min_profit_lamports
             );
             info!("💡 Попробуйте понизить MIN_PROFIT_LAMPORTS в .env файле");
             return Ok(None);
@@ -185,6 +288,21 @@ impl OpportunityEvaluator {
             0.0
         };
 
+        // Порог относительно объёма сделки
+        let profit_bps = profit_percentage * 100.0;
+        if profit_bps < self.config.trading.min_profit_bps as f64 {
+            info!(
+                "❌ ОТКЛОНЕНО: {:.2} bps < {} bps (порог)",
+                profit_bps, self.config.trading.min_profit_bps
+            );
+            return Ok(None);
+        }
+
+        let min_net_profit = match lamport_price {
+            Some(price) if price > 0.0 => (self.config.trading.min_profit_lamports as f64 * price).ceil() as i64,
+            _ => self.config.trading.min_profit_lamports as i64,
+        };
+
         info!("✅ ПРИНЯТО: Арбитражная возможность одобрена!");
         info!("📈 Процент прибыли: {:.4}%", profit_percentage);
 
@@ -194,37 +312,106 @@ impl OpportunityEvaluator {
             expected_final_amount: final_amount,
             gross_profit,
             net_profit,
+            net_profit_lamports,
+            min_net_profit,
             profit_percentage,
             discovered_at: chrono::Utc::now().timestamp(),
+            oldest_pool_update: oldest_update,
+            score: 0.0,
         };
 
         Ok(Some(opportunity))
     }
+}
 
-    /// Расчёт ожидаемого и минимального выхода свопа в атомарных единицах токена
-    fn calculate_swap_amounts(
-        &self,
-        pool: &PoolState,
-        amount_in: u64,
-        a_to_b: bool,
-    ) -> Result<(u64, u64)> {
-        let (reserve_in, reserve_out) = if a_to_b {
-            (pool.reserve_a, pool.reserve_b)
-        } else {
-            (pool.reserve_b, pool.reserve_a)
-        };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DexProtocol, PoolState};
+
+    const SOL: Pubkey = spl_token::native_mint::ID;
+
+    fn pool(token_a: Pubkey, token_b: Pubkey, reserve_a: u64, reserve_b: u64) -> Arc<PoolState> {
+        Arc::new(PoolState {
+            id: Pubkey::new_unique(),
+            protocol: DexProtocol::RaydiumCpmm,
+            token_a,
+            token_b,
+            reserve_a,
+            reserve_b,
+            fee_bps: 25,
+            last_updated: 0,
+            full_state_data: vec![],
+            decimals_a: 9,
+            decimals_b: 9,
+            liquidity_accounts: vec![],
+        })
+    }
 
-        // Для всех тестовых пулов (AMM/CPMM/DLMM) используем CPMM-формулу
-        let estimated_out = {
-            use crate::arbitrage::pool_math::calculate_cpmm_output;
-            calculate_cpmm_output(reserve_in, reserve_out, amount_in, pool.fee_bps)?
+    /// Цикл SOL -> X -> Y -> SOL; X стоит 2 SOL, Y — 0.5 SOL
+    fn setup() -> (PriceGraph, ArbitrageCycle, Pubkey, Pubkey) {
+        let (x, y) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pools = vec![
+            pool(SOL, x, 2_000_000_000_000_000, 1_000_000_000_000_000),
+            pool(x, y, 1_000_000_000_000_000, 4_000_000_000_000_000),
+            pool(y, SOL, 2_000_000_000_000_000, 1_000_000_000_000_000),
+        ];
+        let mut graph = PriceGraph::new(1.0);
+        graph.sync_pools(&pools).unwrap();
+
+        let cycle = ArbitrageCycle {
+            tokens: vec![SOL, x, y, SOL],
+            pool_ids: pools.iter().map(|p| p.id).collect(),
+            total_weight: 0.0,
         };
+        (graph, cycle, x, y)
+    }
 
-        // Минимальный выход с учётом slippage
-        use crate::arbitrage::pool_math::calculate_minimum_amount_out;
-        let min_out =
-            calculate_minimum_amount_out(estimated_out, self.config.trading.max_slippage_bps);
+    fn evaluator(balances: &[(Pubkey, u64)]) -> OpportunityEvaluator {
+        let evaluator = OpportunityEvaluator::new(Arc::new(BotConfig::for_tests()));
+        evaluator.set_balances(balances.iter().copied().collect());
+        evaluator
+    }
 
-        Ok((estimated_out, min_out))
+    #[test]
+    fn test_choose_start_rotates_to_largest_sol_value() {
+        let (graph, cycle, x, y) = setup();
+
+        // 1 X ≈ 2 SOL дороже 1 Y ≈ 0.5 SOL: старт с X, цикл повёрнут
+        let start = evaluator(&[(x, 1_000_000_000), (y, 1_000_000_000)])
+            .choose_start(&cycle, &graph)
+            .unwrap();
+        assert_eq!(start.tokens, vec![x, y, SOL, x]);
+        assert_eq!(start.pool_ids, vec![cycle.pool_ids[1], cycle.pool_ids[2], cycle.pool_ids[0]]);
+        assert_eq!(start.amount, 1_000_000_000);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_choose_start_caps_amount_at_balance() {
+        let (graph, cycle, x, y) = setup();
+
+        // 0.1 X ≈ 0.2 SOL уступает 1 Y ≈ 0.5 SOL
+        let start = evaluator(&[(x, 100_000_000), (y, 1_000_000_000)])
+            .choose_start(&cycle, &graph)
+            .unwrap();
+        assert_eq!(start.tokens[0], y);
+        assert_eq!(start.amount, 1_000_000_000);
+
+        // Баланс меньше эталонного объёма — объём равен балансу
+        let start = evaluator(&[(x, 300_000_000)]).choose_start(&cycle, &graph).unwrap();
+        assert_eq!((start.tokens[0], start.amount), (x, 300_000_000));
+
+        // Баланс больше эталонного объёма — эталонный объём
+        let start = evaluator(&[(SOL, 5_000_000_000)]).choose_start(&cycle, &graph).unwrap();
+        assert_eq!((start.tokens.clone(), start.amount), (cycle.tokens.clone(), 1_000_000_000));
+    }
+
+    #[test]
+    fn test_choose_start_without_cycle_tokens() {
+        let (graph, cycle, x, _) = setup();
+
+        assert!(evaluator(&[(Pubkey::new_unique(), 1_000_000_000), (x, 0)])
+            .choose_start(&cycle, &graph)
+            .is_none());
+    }
+}
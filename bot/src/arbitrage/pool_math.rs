@@ -2,6 +2,13 @@
 
 use anyhow::Result;
 
+use super::{clmm_quote, dlmm_quote};
+use crate::dex_structs::{ClmmPoolState, DlmmLbPair};
+use crate::types::{DexProtocol, PoolState};
+
+/// 2^64: знаменатель цен Q64.64 (sqrt_price_x64 CLMM, цена бина DLMM)
+const Q64: f64 = 18_446_744_073_709_551_616.0;
+
 /// Расчет выхода для пула CPMM (Constant Product Market Maker)
 pub fn calculate_cpmm_output(
     reserve_in: u64,
@@ -24,56 +31,95 @@ pub fn calculate_cpmm_output(
 }
 
 /// Расчет выхода для пула CLMM (Concentrated Liquidity)
+/// с переходами через инициализированные тики (см. clmm_quote)
 pub fn calculate_clmm_output(
-    liquidity: u128,
-    sqrt_price_current: u128,
-    sqrt_price_next: u128,
+    pool: &PoolState,
     amount_in: u64,
-    fee_bps: u16,
+    a_to_b: bool,
 ) -> Result<u64> {
-    if liquidity == 0 {
-        anyhow::bail!("Нулевая ликвидность в CLMM пуле");
-    }
-
-    let fee_multiplier = 1.0 - (fee_bps as f64 / 10000.0);
-    let _amount_in_with_fee = (amount_in as f64) * fee_multiplier;
-
-    let l_f64 = liquidity as f64;
-    let sqrt_p_current = sqrt_price_current as f64;
-    let sqrt_p_next = sqrt_price_next as f64;
-
-    let delta_y = l_f64 * (sqrt_p_next - sqrt_p_current) / (sqrt_p_current * sqrt_p_next);
-    let amount_out = delta_y * fee_multiplier;
-
-    Ok(amount_out as u64)
+    Ok(clmm_quote::quote_pool(pool, amount_in, a_to_b)?.amount_out)
 }
 
 /// Расчет выхода для пула DLMM (Dynamic Liquidity Market Maker)
+/// бин за бином с динамической комиссией (см. dlmm_quote)
 pub fn calculate_dlmm_output(
-    bin_liquidity: u64,
-    bin_price: f64,
-    composition: f64,
+    pool: &PoolState,
     amount_in: u64,
-    base_fee_bps: u16,
-    variable_fee_bps: u16,
+    a_to_b: bool,
 ) -> Result<u64> {
-    if bin_liquidity == 0 {
-        anyhow::bail!("Нулевая ликвидность в DLMM бине");
-    }
+    Ok(dlmm_quote::quote_pool(pool, amount_in, a_to_b)?.amount_out)
+}
 
-    let total_fee_bps = base_fee_bps + variable_fee_bps;
-    let fee_multiplier = 1.0 - (total_fee_bps as f64 / 10000.0);
+/// Ожидаемый выход свопа через котировку протокола пула:
+/// CLMM с загруженными TickArray считаем через тики, DLMM с LbPair — через бины,
+/// для остальных (и тестовых пулов без данных) — CPMM-формула
+pub fn quote_exact_in(
+    pool: &PoolState,
+    amount_in: u64,
+    a_to_b: bool,
+) -> Result<u64> {
+    match pool.protocol {
+        DexProtocol::RaydiumClmm if !pool.liquidity_accounts.is_empty() => {
+            calculate_clmm_output(pool, amount_in, a_to_b)
+        }
+        DexProtocol::MeteoraDlmm if !pool.full_state_data.is_empty() => {
+            calculate_dlmm_output(pool, amount_in, a_to_b)
+        }
+        _ => {
+            let (reserve_in, reserve_out) = if a_to_b {
+                (pool.reserve_a, pool.reserve_b)
+            } else {
+                (pool.reserve_b, pool.reserve_a)
+            };
+            calculate_cpmm_output(reserve_in, reserve_out, amount_in, pool.fee_bps)
+        }
+    }
+}
 
-    let l_f64 = bin_liquidity as f64;
-    let reserve_y = composition * l_f64;
-    let reserve_x = l_f64 / (bin_price * (1.0 - composition));
+/// Количество TickArray (CLMM) или BinArray (DLMM), которые проходит своп по котировке
+/// (0 — пул без массивов ликвидности или котировка недоступна)
+pub fn liquidity_arrays_crossed(pool: &PoolState, amount_in: u64, a_to_b: bool) -> usize {
+    match pool.protocol {
+        DexProtocol::RaydiumClmm if !pool.liquidity_accounts.is_empty() => {
+            clmm_quote::quote_pool(pool, amount_in, a_to_b).map_or(0, |quote| quote.tick_arrays.len())
+        }
+        DexProtocol::MeteoraDlmm if !pool.full_state_data.is_empty() => {
+            dlmm_quote::quote_pool(pool, amount_in, a_to_b).map_or(0, |quote| quote.bin_arrays.len())
+        }
+        _ => 0,
+    }
+}
 
-    let amount_in_with_fee = (amount_in as f64) * fee_multiplier;
-    let numerator = reserve_y * amount_in_with_fee;
-    let denominator = reserve_x + amount_in_with_fee;
+/// Mid-цена пула: атомов token_b за атом token_a, без комиссии и price impact.
+/// CLMM — из sqrt_price_x64, DLMM — из активного бина, остальные — отношение резервов
+pub fn spot_price(pool: &PoolState) -> Result<f64> {
+    match pool.protocol {
+        DexProtocol::RaydiumClmm if !pool.full_state_data.is_empty() => {
+            let state = ClmmPoolState::try_from_slice(&pool.full_state_data)?;
+            let sqrt_price = state.sqrt_price_x64 as f64 / Q64;
+            // sqrt_price_x64² — цена token_1 в token_0
+            let price = sqrt_price * sqrt_price;
+            Ok(if pool.token_a == state.token_mint_0 { price } else { 1.0 / price })
+        }
+        DexProtocol::MeteoraDlmm if !pool.full_state_data.is_empty() => {
+            let pair = DlmmLbPair::try_from_slice(&pool.full_state_data)?;
+            // Цена активного бина — Y за X
+            let price = dlmm_quote::price_from_id(pair.active_id, pair.bin_step)? as f64 / Q64;
+            Ok(if pool.token_a == pair.token_x_mint { price } else { 1.0 / price })
+        }
+        _ => {
+            if pool.reserve_a == 0 || pool.reserve_b == 0 {
+                anyhow::bail!("Нулевые резервы пула {}", pool.id);
+            }
+            Ok(pool.reserve_b as f64 / pool.reserve_a as f64)
+        }
+    }
+}
 
-    let amount_out = numerator / denominator;
-    Ok(amount_out as u64)
+/// Mid-курс пула в направлении свопа: атомов выхода за атом входа
+pub fn spot_rate(pool: &PoolState, a_to_b: bool) -> Result<f64> {
+    let price = spot_price(pool)?;
+    Ok(if a_to_b { price } else { 1.0 / price })
 }
 
 /// Расчет минимального выхода с учетом slippage
@@ -2,49 +2,117 @@
 // Расчёт чистой прибыли с учётом всех комиссий
 
 use anyhow::Result;
-use std::sync::Arc;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
 
+use super::scoring::estimated_compute_units;
 use crate::config::BotConfig;
 use crate::types::SwapLeg;
 
+/// Базовая комиссия за одну подпись (lamports)
+pub const SIGNATURE_FEE_LAMPORTS: u64 = 5_000;
+/// Rent-exempt минимум токен-аккаунта (165 байт)
+pub const TOKEN_ACCOUNT_RENT_LAMPORTS: u64 = 2_039_280;
+
+/// Расходы на исполнение арбитражной транзакции (lamports)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExecutionCosts {
+    pub signature_fee: u64,
+    /// Лимит CU × цена CU (micro-lamports), как в ComputeBudget исполнителя
+    pub priority_fee: u64,
+    pub jito_tip: u64,
+    /// Рента за создание недостающих ATA
+    pub ata_rent: u64,
+}
+
+impl ExecutionCosts {
+    pub fn total_lamports(&self) -> u64 {
+        self.signature_fee + self.priority_fee + self.jito_tip + self.ata_rent
+    }
+}
+
 pub struct ProfitCalculator {
     config: Arc<BotConfig>,
+    /// Mint'ы, для которых у кошелька уже есть ATA.
+    /// None — набор ещё не загружен, рента за ATA не учитывается
+    existing_atas: RwLock<Option<HashSet<Pubkey>>>,
 }
 
 impl ProfitCalculator {
     pub fn new(config: Arc<BotConfig>) -> Self {
-        Self { config }
+        Self {
+            config,
+            existing_atas: RwLock::new(None),
+        }
+    }
+
+    /// Обновление набора mint'ов с существующими ATA кошелька
+    pub fn set_existing_atas(&self, mints: HashSet<Pubkey>) {
+        if let Ok(mut guard) = self.existing_atas.write() {
+            *guard = Some(mints);
+        }
+    }
+
+    /// Расходы на исполнение цикла в lamports.
+    /// Приоритетная комиссия — по модели исполнителя (fee_market): лимит CU —
+    /// потребление × compute_unit_margin, только потребление до симуляции оценено
+    /// по протоколам свопов; цена CU — резервная цена рынка комиссий
+    pub fn execution_costs(&self, legs: &[SwapLeg]) -> ExecutionCosts {
+        let trading = &self.config.trading;
+
+        let unit_limit = (estimated_compute_units(legs) as f64 * trading.compute_unit_margin.max(1.0)).ceil();
+        let priority_fee = (unit_limit as u128 * trading.priority_fee_micro_lamports as u128)
+            .div_ceil(1_000_000) as u64;
+
+        let jito_tip = self.config.jito.as_ref().map_or(0, |jito| jito.tip_lamports);
+
+        // Рента за ATA выходных токенов, которых у кошелька ещё нет
+        let ata_rent = match self.existing_atas.read().ok().as_deref() {
+            Some(Some(existing)) => {
+                let missing: HashSet<&Pubkey> = legs.iter()
+                    .map(|leg| &leg.output_mint)
+                    .filter(|mint| !existing.contains(*mint))
+                    .collect();
+                missing.len() as u64 * TOKEN_ACCOUNT_RENT_LAMPORTS
+            }
+            _ => 0,
+        };
+
+        ExecutionCosts {
+            signature_fee: SIGNATURE_FEE_LAMPORTS,
+            priority_fee,
+            jito_tip,
+            ata_rent,
+        }
     }
 
-    /// Расчёт чистой прибыли с учётом комиссий
-    /// В devnet НЕ вычитаем SOL-комиссии (другая единица) — работаем в атомах стартового токена
+    /// Расчёт валовой и чистой прибыли в атомах стартового токена (со знаком).
+    /// `lamport_price` — атомов стартового токена за один lamport
+    /// (None — курс SOL недоступен, расходы не конвертируются)
     pub fn calculate_net_profit(
         &self,
         initial_amount: u64,
         final_amount: u64,
-        _legs: &[SwapLeg],
-    ) -> Result<(u64, u64)> {
-        // Валовая прибыль в атомах токена
-        let gross_profit = if final_amount >= initial_amount {
-            final_amount - initial_amount
-        } else {
-            initial_amount - final_amount
-        };
+        legs: &[SwapLeg],
+        lamport_price: Option<f64>,
+    ) -> Result<(i64, i64)> {
+        // Валовая прибыль в атомах токена: убыточный цикл даёт отрицательное значение
+        let gross_profit = i64::try_from(final_amount as i128 - initial_amount as i128)?;
 
-        // Devnet: игнорируем комиссии в SOL (lamports), т.к. считаем в атомах токена
-        let is_devnet = self.config.rpc.url.contains("devnet");
-
-        let net_profit = if is_devnet {
-            gross_profit
-        } else {
-            // Для mainnet здесь следовало бы:
-            // 1) рассчитать SOL-комиссии (tx_base_fee, priority_fee, jito_tip)
-            // 2) сконвертировать их в атомы токена A через прайс-оракул
-            // 3) вычесть из gross_profit
-            gross_profit
+        let costs = self.execution_costs(legs);
+        let costs_in_token = match lamport_price {
+            Some(price) => (costs.total_lamports() as f64 * price).ceil() as i64,
+            None => {
+                // Без курса SOL расходы сравнивать не с чем: в mainnet это отказ
+                if !self.config.rpc.url.contains("devnet") {
+                    anyhow::bail!("Нет курса SOL для стартового токена — расходы не конвертировать");
+                }
+                0
+            }
         };
 
-        Ok((gross_profit, net_profit))
+        Ok((gross_profit, gross_profit - costs_in_token))
     }
 
     /// Оценка максимально допустимого slippage (оставлено без изменений)
@@ -57,4 +125,37 @@ impl ProfitCalculator {
         if expected_profit == 0 { return 0.0; }
         (max_loss as f64 / expected_profit as f64) * 100.0
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_losing_cycle_has_negative_net_profit() {
+        let calc = ProfitCalculator::new(Arc::new(BotConfig::for_tests()));
+        let costs = calc.execution_costs(&[]);
+        // Подпись и приоритет по модели исполнителя: 30k CU × 1.15 × 0.1 lamports/CU
+        assert_eq!(costs.total_lamports(), 5_000 + 3_450);
+
+        let (gross, net) = calc.calculate_net_profit(1_000_000, 990_000, &[], Some(1.0)).unwrap();
+        assert_eq!(gross, -10_000);
+        assert_eq!(net, -10_000 - 8_450);
+
+        // Прибыль меньше расходов тоже уходит в минус
+        let (gross, net) = calc.calculate_net_profit(1_000_000, 1_005_000, &[], Some(1.0)).unwrap();
+        assert_eq!((gross, net), (5_000, -3_450));
+    }
+
+    #[test]
+    fn test_mainnet_without_sol_price_bails() {
+        let mut config = BotConfig::for_tests();
+        let calc = ProfitCalculator::new(Arc::new(config.clone()));
+        assert!(calc.calculate_net_profit(1_000_000, 1_100_000, &[], None).is_err());
+
+        // На devnet расходы без курса не учитываются
+        config.rpc.url = "https://api.devnet.solana.com".to_string();
+        let calc = ProfitCalculator::new(Arc::new(config));
+        assert_eq!(calc.calculate_net_profit(1_000_000, 1_100_000, &[], None).unwrap(), (100_000, 100_000));
+    }
+}
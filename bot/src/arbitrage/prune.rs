@@ -0,0 +1,392 @@
+// bot/src/arbitrage/prune.rs
+// Сжатие графа до циклического ядра перед поиском: листовые токены с одним пулом
+// и пулы вне сильно связных компонент не могут входить в цикл, но стоят времени поиска
+
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use super::graph::PriceGraph;
+use super::pool_math::quote_exact_in;
+use crate::types::{PoolState, PriceEdge};
+
+/// Допустимое ухудшение курса на глубине порога относительно курса ребра.
+/// Для CPMM с резервом, равным глубине, курс падает ровно вдвое
+const MAX_DEPTH_IMPACT: f64 = 0.5;
+
+/// Этап прунинга
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PruneStage {
+    /// Пулы, не выдерживающие кратного объёма сделки по котировке
+    Liquidity,
+    /// Итеративное удаление токенов со степенью < 2 (2-ядро)
+    TwoCore,
+    /// Пулы, ни одно направленное ребро которых не лежит внутри SCC
+    Scc,
+}
+
+impl fmt::Display for PruneStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PruneStage::Liquidity => write!(f, "ликвидность"),
+            PruneStage::TwoCore => write!(f, "2-ядро"),
+            PruneStage::Scc => write!(f, "SCC"),
+        }
+    }
+}
+
+/// Размер графа после этапа прунинга
+#[derive(Debug, Clone, Copy)]
+pub struct PruneStep {
+    pub iteration: usize,
+    pub stage: PruneStage,
+    pub tokens: usize,
+    pub pools: usize,
+}
+
+/// Циклическое ядро графа: пулы, через которые возможен цикл
+#[derive(Debug, Clone)]
+pub struct CyclicCore {
+    pub pools: HashSet<Pubkey>,
+    pub tokens_before: usize,
+    pub pools_before: usize,
+    /// Размер графа после каждого этапа каждой итерации
+    pub steps: Vec<PruneStep>,
+}
+
+impl CyclicCore {
+    pub fn token_count(&self) -> usize {
+        self.steps.last().map_or(self.tokens_before, |step| step.tokens)
+    }
+}
+
+/// Вычисление циклического ядра: порог ликвидности, затем 2-ядро и SCC до неподвижной точки.
+/// `liquidity_multiple` — какое кратное эталонного объёма сделки пул должен котировать
+/// с ограниченным price impact (0 — без порога). Глубина проверяется котировкой,
+/// а не резервами: у CLMM и DLMM ликвидность лежит в тиках и бинах
+pub fn cyclic_core(graph: &PriceGraph, liquidity_multiple: f64) -> CyclicCore {
+    // Токен -> пулы, в которых он участвует
+    let mut incidence: HashMap<usize, Vec<Pubkey>> = HashMap::new();
+    // Пул -> (индекс token_a, индекс token_b)
+    let mut endpoints: HashMap<Pubkey, (usize, usize)> = HashMap::new();
+    for pool in graph.pools() {
+        let (Some(a), Some(b)) = (graph.get_index(&pool.token_a), graph.get_index(&pool.token_b)) else {
+            continue;
+        };
+        incidence.entry(a).or_default().push(pool.id);
+        incidence.entry(b).or_default().push(pool.id);
+        endpoints.insert(pool.id, (a, b));
+    }
+
+    let mut core = CyclicCore {
+        pools: HashSet::new(),
+        tokens_before: incidence.len(),
+        pools_before: endpoints.len(),
+        steps: Vec::new(),
+    };
+
+    // Этап 0: котируемость хотя бы в одну сторону и глубина каждого котируемого направления
+    let mut alive: HashSet<Pubkey> = graph.pools()
+        .filter(|pool| endpoints.contains_key(&pool.id))
+        .filter(|pool| {
+            let quotable: Vec<(&Pubkey, &PriceEdge)> = [&pool.token_a, &pool.token_b].into_iter()
+                .filter_map(|from| Some((from, graph.pool_edge(&pool.id, from)?)))
+                .filter(|(_, edge)| edge.weight.is_finite())
+                .collect();
+            !quotable.is_empty()
+                && quotable.iter().all(|&(from, edge)| {
+                    is_deep(pool, *from == pool.token_a, edge, liquidity_multiple)
+                })
+        })
+        .map(|pool| pool.id)
+        .collect();
+    record(&mut core, 0, PruneStage::Liquidity, &alive, &endpoints);
+
+    for iteration in 1.. {
+        peel_two_core(&mut alive, &incidence, &endpoints);
+        record(&mut core, iteration, PruneStage::TwoCore, &alive, &endpoints);
+
+        let before = alive.len();
+        retain_cyclic_edges(graph, &mut alive, &endpoints);
+        record(&mut core, iteration, PruneStage::Scc, &alive, &endpoints);
+
+        // SCC ничего не убрал — 2-ядро уже в неподвижной точке
+        if alive.len() == before {
+            break;
+        }
+    }
+
+    core.pools = alive;
+    core
+}
+
+/// Пул котирует `liquidity_multiple` эталонных объёмов с курсом не хуже
+/// (1 - MAX_DEPTH_IMPACT) от курса ребра на эталонном объёме
+fn is_deep(pool: &PoolState, a_to_b: bool, edge: &PriceEdge, liquidity_multiple: f64) -> bool {
+    if liquidity_multiple <= 0.0 {
+        return true;
+    }
+
+    let depth = (liquidity_multiple * edge.reference_amount as f64) as u64;
+    if depth == 0 {
+        return true;
+    }
+
+    let Ok(amount_out) = quote_exact_in(pool, depth, a_to_b) else {
+        return false;
+    };
+    amount_out as f64 / depth as f64 >= (-edge.weight).exp() * (1.0 - MAX_DEPTH_IMPACT)
+}
+
+fn record(
+    core: &mut CyclicCore,
+    iteration: usize,
+    stage: PruneStage,
+    alive: &HashSet<Pubkey>,
+    endpoints: &HashMap<Pubkey, (usize, usize)>,
+) {
+    let tokens: HashSet<usize> = alive.iter()
+        .filter_map(|pool_id| endpoints.get(pool_id))
+        .flat_map(|(a, b)| [*a, *b])
+        .collect();
+
+    core.steps.push(PruneStep { iteration, stage, tokens: tokens.len(), pools: alive.len() });
+}
+
+/// Удаление токенов степени < 2 вместе с их пулами, пока такие есть.
+/// Степень — число живых пулов токена: два параллельных пула дают 2-цикл
+fn peel_two_core(
+    alive: &mut HashSet<Pubkey>,
+    incidence: &HashMap<usize, Vec<Pubkey>>,
+    endpoints: &HashMap<Pubkey, (usize, usize)>,
+) {
+    let mut degree: HashMap<usize, usize> = HashMap::new();
+    for pool_id in alive.iter() {
+        let (a, b) = endpoints[pool_id];
+        *degree.entry(a).or_default() += 1;
+        *degree.entry(b).or_default() += 1;
+    }
+
+    let mut queue: Vec<usize> = degree.iter()
+        .filter(|(_, d)| **d < 2)
+        .map(|(token, _)| *token)
+        .collect();
+
+    while let Some(token) = queue.pop() {
+        for pool_id in incidence.get(&token).into_iter().flatten() {
+            if !alive.remove(pool_id) {
+                continue;
+            }
+
+            let (a, b) = endpoints[pool_id];
+            for end in [a, b] {
+                let d = degree.entry(end).or_default();
+                *d -= 1;
+                // Токен попадает в очередь ровно один раз — при переходе 2 -> 1
+                if end != token && *d == 1 {
+                    queue.push(end);
+                }
+            }
+        }
+    }
+}
+
+/// Оставляет пулы, у которых хотя бы одно котируемое направление
+/// соединяет токены одной сильно связной компоненты (Косарайю)
+fn retain_cyclic_edges(
+    graph: &PriceGraph,
+    alive: &mut HashSet<Pubkey>,
+    endpoints: &HashMap<Pubkey, (usize, usize)>,
+) {
+    let edges: Vec<(Pubkey, usize, usize)> = alive.iter()
+        .flat_map(|pool_id| {
+            let (a, b) = endpoints[pool_id];
+            [(*pool_id, a, b), (*pool_id, b, a)]
+        })
+        .filter(|(pool_id, from, _)| {
+            graph.get_token(*from)
+                .and_then(|token| graph.pool_edge(pool_id, token))
+                .is_some_and(|edge| edge.weight.is_finite())
+        })
+        .collect();
+
+    let mut forward: HashMap<usize, Vec<usize>> = HashMap::new();
+    let mut backward: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (_, from, to) in &edges {
+        forward.entry(*from).or_default().push(*to);
+        backward.entry(*to).or_default().push(*from);
+    }
+
+    // Проход 1: порядок завершения DFS по прямым рёбрам
+    let mut order = Vec::with_capacity(forward.len());
+    let mut visited = HashSet::new();
+    let mut nodes: Vec<usize> = forward.keys().chain(backward.keys()).copied().collect();
+    nodes.sort_unstable();
+    nodes.dedup();
+
+    for &root in &nodes {
+        if !visited.insert(root) {
+            continue;
+        }
+        let mut stack = vec![(root, 0usize)];
+        while let Some((node, next)) = stack.pop() {
+            let successors = forward.get(&node).map_or(&[][..], |v| v.as_slice());
+            if let Some(&succ) = successors.get(next) {
+                stack.push((node, next + 1));
+                if visited.insert(succ) {
+                    stack.push((succ, 0));
+                }
+            } else {
+                order.push(node);
+            }
+        }
+    }
+
+    // Проход 2: компоненты по обратным рёбрам в обратном порядке завершения
+    let mut component: HashMap<usize, usize> = HashMap::new();
+    for &root in order.iter().rev() {
+        if component.contains_key(&root) {
+            continue;
+        }
+        let id = component.len();
+        component.insert(root, id);
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            for &pred in backward.get(&node).into_iter().flatten() {
+                if let std::collections::hash_map::Entry::Vacant(entry) = component.entry(pred) {
+                    entry.insert(id);
+                    stack.push(pred);
+                }
+            }
+        }
+    }
+
+    let cyclic: HashSet<Pubkey> = edges.iter()
+        .filter(|(_, from, to)| component.get(from) == component.get(to))
+        .map(|(pool_id, _, _)| *pool_id)
+        .collect();
+
+    alive.retain(|pool_id| cyclic.contains(pool_id));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arbitrage::clmm_quote::sqrt_price_at_tick;
+    use crate::dex_structs::{ClmmPoolState, TickArrayState, TickState};
+    use crate::types::{DexProtocol, LiquidityAccount};
+    use std::sync::Arc;
+
+    fn pool(token_a: Pubkey, token_b: Pubkey, reserve: u64) -> PoolState {
+        PoolState {
+            id: Pubkey::new_unique(),
+            protocol: DexProtocol::RaydiumCpmm,
+            token_a,
+            token_b,
+            reserve_a: reserve,
+            reserve_b: reserve,
+            fee_bps: 25,
+            last_updated: 0,
+            full_state_data: vec![],
+            decimals_a: 9,
+            decimals_b: 9,
+            liquidity_accounts: vec![],
+        }
+    }
+
+    /// CLMM пул на тике 0 с одной позицией [-600, 590); резервы не заполнены
+    fn clmm_pool(token_a: Pubkey, token_b: Pubkey, liquidity: u128) -> PoolState {
+        let state = ClmmPoolState {
+            amm_config: Pubkey::new_unique(),
+            token_mint_0: token_a,
+            token_mint_1: token_b,
+            token_vault_0: Pubkey::new_unique(),
+            token_vault_1: Pubkey::new_unique(),
+            observation_key: Pubkey::new_unique(),
+            mint_decimals_0: 9,
+            mint_decimals_1: 9,
+            tick_spacing: 10,
+            liquidity,
+            sqrt_price_x64: sqrt_price_at_tick(0).unwrap(),
+            tick_current: 0,
+            tick_array_bitmap: [0; 16],
+        };
+        let tick = |tick: i32, liquidity_net: i128| TickState {
+            tick,
+            liquidity_net,
+            liquidity_gross: liquidity_net.unsigned_abs(),
+        };
+        let array = |start_tick_index: i32, ticks: Vec<TickState>| TickArrayState {
+            address: Pubkey::new_unique(),
+            start_tick_index,
+            ticks,
+        };
+        let arrays = [
+            array(-600, vec![tick(-600, liquidity as i128)]),
+            array(0, vec![tick(590, -(liquidity as i128))]),
+        ];
+
+        PoolState {
+            protocol: DexProtocol::RaydiumClmm,
+            reserve_a: 0,
+            reserve_b: 0,
+            full_state_data: state.to_account_data(),
+            liquidity_accounts: arrays.iter()
+                .map(|array| LiquidityAccount { pubkey: array.address, data: array.to_account_data() })
+                .collect(),
+            ..pool(token_a, token_b, 0)
+        }
+    }
+
+    #[test]
+    fn test_core_drops_leaves_and_shallow_pools() {
+        let tokens: Vec<Pubkey> = (0..6).map(|_| Pubkey::new_unique()).collect();
+        let (a, b, c, d, e, f) = (tokens[0], tokens[1], tokens[2], tokens[3], tokens[4], tokens[5]);
+        let deep = 1_000_000_000_000;
+
+        let triangle = vec![pool(a, b, deep), pool(b, c, deep), pool(c, a, deep)];
+        let mut pools = triangle.clone();
+        // Хвост C-D-E: листовая цепочка, снимается 2-ядром
+        pools.push(pool(c, d, deep));
+        pools.push(pool(d, e, deep));
+        // Параллельный мелкий пул к F: 2-цикл есть, но он ниже порога ликвидности
+        pools.push(pool(a, f, deep));
+        pools.push(pool(a, f, 2_000_000_000));
+        let pools: Vec<Arc<PoolState>> = pools.into_iter().map(Arc::new).collect();
+
+        let mut graph = PriceGraph::new(1.0);
+        graph.sync_pools(&pools).unwrap();
+
+        let core = cyclic_core(&graph, 10.0);
+        let expected: HashSet<Pubkey> = triangle.iter().map(|p| p.id).collect();
+        assert_eq!(core.pools, expected);
+        assert_eq!((core.tokens_before, core.pools_before), (6, 7));
+        assert_eq!(core.token_count(), 3);
+        assert_eq!(core.steps[0].pools, 6);
+
+        // Без порога параллельные пулы A/F образуют 2-цикл и остаются в ядре
+        assert_eq!(cyclic_core(&graph, 0.0).pools.len(), 5);
+    }
+
+    #[test]
+    fn test_clmm_depth_checked_by_quote_not_reserves() {
+        let (a, b, c) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let deep = 1_000_000_000_000;
+
+        // Виртуальные резервы L/√P = L·√P: 1000 и 1 эталонных объёмов
+        let deep_clmm = clmm_pool(a, b, 1_000_000_000_000);
+        let shallow_clmm = clmm_pool(a, b, 1_000_000_000);
+        let pools: Vec<Arc<PoolState>> = [deep_clmm.clone(), shallow_clmm.clone(), pool(b, c, deep), pool(c, a, deep)]
+            .into_iter()
+            .map(Arc::new)
+            .collect();
+
+        let mut graph = PriceGraph::new(1.0);
+        graph.sync_pools(&pools).unwrap();
+
+        let core = cyclic_core(&graph, 10.0);
+        assert!(core.pools.contains(&deep_clmm.id));
+        assert!(!core.pools.contains(&shallow_clmm.id));
+        assert_eq!(core.pools.len(), 3);
+    }
+}
@@ -0,0 +1,279 @@
+// bot/src/arbitrage/reputation.rs
+// Репутация циклов и пулов: экспоненциальные кулдауны после неудач исполнения,
+// автоматический бан пулов и ручные allow/deny списки из конфига
+
+use dashmap::{DashMap, DashSet};
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use super::cycle_index::CycleSignature;
+use crate::config::{PubkeyString, ReputationConfig};
+
+/// Кулдаун пула начинается только со второй неудачи подряд:
+/// один неудачный цикл не должен замораживать все циклы через хаб
+const POOL_COOLDOWN_AFTER_FAILURES: u32 = 2;
+
+/// Имена ошибок проскальзывания в логах Anchor ("Error Code: <имя>"):
+/// arbitrage-executor, CPMM, CLMM и DLMM
+const SLIPPAGE_ERROR_NAMES: [&str; 7] = [
+    "SlippageExceeded",
+    "ExceededSlippage",
+    "PriceSlippageCheck",
+    "TooLittleOutputReceived",
+    "TooMuchInputPaid",
+    "ExceededAmountSlippageTolerance",
+    "ExceededBinSlippageTolerance",
+];
+
+/// Категория неудачи исполнения цикла. Строится из декодированной ошибки
+/// программы (executor::outcome); ошибки инфраструктуры сюда не попадают
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureKind {
+    /// ArbitrageError::InsufficientProfit (Custom 6000)
+    InsufficientProfit,
+    /// ArbitrageError::SlippageExceeded (Custom 6001) или slippage-ошибка DEX
+    Slippage,
+    Other,
+}
+
+impl FailureKind {
+    /// Запасной разбор логов симуляции, если код ошибки не декодирован:
+    /// только строки Anchor "Error Code: <имя>."
+    pub fn from_simulation_logs(logs: &[String]) -> Self {
+        let error_name = logs.iter().rev().find_map(|line| {
+            let (_, rest) = line.split_once("Error Code: ")?;
+            rest.split(['.', ' ']).next()
+        });
+
+        match error_name {
+            Some("InsufficientProfit") => FailureKind::InsufficientProfit,
+            Some(name) if SLIPPAGE_ERROR_NAMES.contains(&name) => FailureKind::Slippage,
+            _ => FailureKind::Other,
+        }
+    }
+
+    /// Неудачи, говорящие о неверной котировке пула (ведут к бану)
+    fn is_quote_failure(self) -> bool {
+        matches!(self, FailureKind::InsufficientProfit | FailureKind::Slippage)
+    }
+}
+
+/// Неудачи подряд и конец текущего кулдауна
+#[derive(Debug, Clone, Copy, Default)]
+struct FailureRecord {
+    consecutive: u32,
+    /// Неудачи InsufficientProfit/slippage с последнего успеха
+    quote_failures: u32,
+    blocked_until: Option<Instant>,
+}
+
+impl FailureRecord {
+    fn is_blocked(&self, now: Instant) -> bool {
+        self.blocked_until.is_some_and(|until| now < until)
+    }
+}
+
+pub struct ReputationStore {
+    cooldown_base: Duration,
+    cooldown_max: Duration,
+    blacklist_after_failures: u32,
+    /// Пулы, которые не банятся автоматически
+    allowlist: HashSet<Pubkey>,
+    /// Ручной запрет из конфига
+    denylist: HashSet<Pubkey>,
+    /// Автоматически забаненные пулы
+    blacklist: DashSet<Pubkey>,
+    cycles: DashMap<CycleSignature, FailureRecord>,
+    pools: DashMap<Pubkey, FailureRecord>,
+}
+
+impl ReputationStore {
+    pub fn new(config: &ReputationConfig) -> Self {
+        Self {
+            cooldown_base: Duration::from_secs(config.cooldown_base_secs),
+            cooldown_max: Duration::from_secs(config.cooldown_max_secs),
+            blacklist_after_failures: config.blacklist_after_failures,
+            allowlist: Self::parse_pool_list(&config.pool_allowlist, "POOL_ALLOWLIST"),
+            denylist: Self::parse_pool_list(&config.pool_denylist, "POOL_DENYLIST"),
+            blacklist: DashSet::new(),
+            cycles: DashMap::new(),
+            pools: DashMap::new(),
+        }
+    }
+
+    /// Пул запрещён вручную или забанен автоматически
+    pub fn is_banned(&self, pool_id: &Pubkey) -> bool {
+        self.denylist.contains(pool_id) || self.blacklist.contains(pool_id)
+    }
+
+    /// Пул не забанен и не на кулдауне
+    pub fn is_pool_allowed(&self, pool_id: &Pubkey, now: Instant) -> bool {
+        !self.is_banned(pool_id)
+            && !self.pools.get(pool_id).is_some_and(|record| record.is_blocked(now))
+    }
+
+    /// Можно ли исполнять цикл сейчас: ни цикл, ни его пулы не на кулдауне и не забанены
+    pub fn is_allowed(&self, signature: &CycleSignature, pool_ids: &[Pubkey], now: Instant) -> bool {
+        if self.cycles.get(signature).is_some_and(|record| record.is_blocked(now)) {
+            return false;
+        }
+        pool_ids.iter().all(|pool_id| self.is_pool_allowed(pool_id, now))
+    }
+
+    /// Учёт неудачи исполнения цикла
+    pub fn record_failure(
+        &self,
+        signature: CycleSignature,
+        pool_ids: &[Pubkey],
+        kind: FailureKind,
+        now: Instant,
+    ) {
+        let cycle_cooldown = {
+            let mut record = self.cycles.entry(signature).or_default();
+            Self::register(&mut record, kind);
+            let cooldown = self.cooldown(record.consecutive);
+            record.blocked_until = Some(now + cooldown);
+            cooldown
+        };
+        info!("🧊 Цикл на кулдауне {:?} ({:?})", cycle_cooldown, kind);
+
+        for pool_id in pool_ids {
+            let mut record = self.pools.entry(*pool_id).or_default();
+            Self::register(&mut record, kind);
+
+            if record.consecutive >= POOL_COOLDOWN_AFTER_FAILURES {
+                let cooldown = self.cooldown(record.consecutive - POOL_COOLDOWN_AFTER_FAILURES + 1);
+                record.blocked_until = Some(now + cooldown);
+            }
+
+            if record.quote_failures >= self.blacklist_after_failures
+                && !self.allowlist.contains(pool_id)
+                && self.blacklist.insert(*pool_id)
+            {
+                warn!("⛔ Пул {} забанен: {} неудач InsufficientProfit/slippage подряд",
+                      pool_id, record.quote_failures);
+            }
+        }
+    }
+
+    /// Учёт успешного исполнения: сброс счётчиков цикла и его пулов
+    pub fn record_success(&self, signature: &CycleSignature, pool_ids: &[Pubkey]) {
+        self.cycles.remove(signature);
+        for pool_id in pool_ids {
+            self.pools.remove(pool_id);
+        }
+    }
+
+    /// Количество автоматически забаненных пулов
+    pub fn blacklisted_count(&self) -> usize {
+        self.blacklist.len()
+    }
+
+    fn register(record: &mut FailureRecord, kind: FailureKind) {
+        record.consecutive += 1;
+        if kind.is_quote_failure() {
+            record.quote_failures += 1;
+        }
+    }
+
+    /// base × 2^(failures − 1), не больше cooldown_max
+    fn cooldown(&self, failures: u32) -> Duration {
+        let exponent = failures.saturating_sub(1).min(31);
+        self.cooldown_base
+            .saturating_mul(1u32 << exponent)
+            .min(self.cooldown_max)
+    }
+
+    fn parse_pool_list(list: &[PubkeyString], name: &str) -> HashSet<Pubkey> {
+        list.iter()
+            .filter_map(|entry| match entry.to_pubkey() {
+                Ok(pubkey) => Some(pubkey),
+                Err(_) => {
+                    warn!("⚠️ Некорректный pubkey в {}: {}", name, entry.0);
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arbitrage::cycle_index::cycle_signature;
+
+    fn store(allowlist: Vec<PubkeyString>) -> ReputationStore {
+        ReputationStore::new(&ReputationConfig {
+            cooldown_base_secs: 10,
+            cooldown_max_secs: 60,
+            blacklist_after_failures: 3,
+            pool_allowlist: allowlist,
+            pool_denylist: vec![],
+        })
+    }
+
+    #[test]
+    fn test_classify_simulation_logs() {
+        let logs = |line: &str| vec!["Program log: Instruction: ExecuteArbitrage".to_string(), line.to_string()];
+
+        assert_eq!(
+            FailureKind::from_simulation_logs(&logs("Program log: AnchorError occurred. Error Code: InsufficientProfit. Error Number: 6000.")),
+            FailureKind::InsufficientProfit,
+        );
+        assert_eq!(
+            FailureKind::from_simulation_logs(&logs("Program log: AnchorError occurred. Error Code: TooMuchInputPaid. Error Number: 6023.")),
+            FailureKind::Slippage,
+        );
+        // Произвольный текст со словом slippage не классифицируется
+        assert_eq!(
+            FailureKind::from_simulation_logs(&logs("Program log: max slippage 500 bps")),
+            FailureKind::Other,
+        );
+    }
+
+    #[test]
+    fn test_exponential_cooldown_and_success_reset() {
+        let store = store(vec![]);
+        let pools = vec![Pubkey::new_unique(), Pubkey::new_unique()];
+        let signature = cycle_signature(pools.iter().map(|p| (*p, Pubkey::default())));
+        let now = Instant::now();
+
+        store.record_failure(signature.clone(), &pools, FailureKind::Other, now);
+        assert!(!store.is_allowed(&signature, &pools, now + Duration::from_secs(9)));
+        assert!(store.is_allowed(&signature, &pools, now + Duration::from_secs(10)));
+
+        // Вторая неудача: цикл на 20 с, пулы — на базовый кулдаун
+        store.record_failure(signature.clone(), &pools, FailureKind::Other, now);
+        assert!(!store.is_allowed(&signature, &pools, now + Duration::from_secs(19)));
+        let other = cycle_signature([(pools[0], Pubkey::new_unique())]);
+        assert!(!store.is_allowed(&other, &pools[..1], now + Duration::from_secs(9)));
+        assert!(store.is_allowed(&other, &pools[..1], now + Duration::from_secs(10)));
+
+        for _ in 0..5 {
+            store.record_failure(signature.clone(), &pools, FailureKind::Other, now);
+        }
+        assert!(store.is_allowed(&signature, &pools, now + Duration::from_secs(60)));
+
+        store.record_success(&signature, &pools);
+        assert!(store.is_allowed(&signature, &pools, now));
+    }
+
+    #[test]
+    fn test_blacklist_after_quote_failures_respects_allowlist() {
+        let trusted = Pubkey::new_unique();
+        let store = store(vec![PubkeyString(trusted.to_string())]);
+        let pools = vec![Pubkey::new_unique(), trusted];
+        let signature = cycle_signature(pools.iter().map(|p| (*p, Pubkey::default())));
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            store.record_failure(signature.clone(), &pools, FailureKind::Slippage, now);
+        }
+
+        assert!(store.is_banned(&pools[0]));
+        assert!(!store.is_banned(&trusted));
+        assert_eq!(store.blacklisted_count(), 1);
+    }
+}
@@ -0,0 +1,250 @@
+// bot/src/arbitrage/scoring.rs
+// Риск-скорректированная оценка возможностей и выбор непересекающегося набора
+
+use dashmap::DashMap;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::debug;
+
+use crate::config::BotConfig;
+use crate::types::{ArbitrageOpportunity, DexProtocol, SwapLeg};
+
+/// CU инструкции исполнителя без свопов (проверки, переводы)
+const EXECUTOR_BASE_COMPUTE_UNITS: u64 = 30_000;
+/// Время, за которое доверие к данным пула падает вдвое (сек)
+const STALENESS_HALF_LIFE_SECS: f64 = 10.0;
+
+/// Оценка CU одного свопа по протоколу (до симуляции)
+pub fn estimated_swap_compute_units(protocol: DexProtocol) -> u64 {
+    match protocol {
+        DexProtocol::RaydiumAmmV4 => 45_000,
+        DexProtocol::RaydiumCpmm => 50_000,
+        DexProtocol::RaydiumClmm => 90_000,
+        DexProtocol::MeteoraDlmm => 80_000,
+    }
+}
+
+/// Оценка CU всей транзакции: инструкция исполнителя и все sub-swap'ы
+pub fn estimated_compute_units(legs: &[SwapLeg]) -> u64 {
+    EXECUTOR_BASE_COMPUTE_UNITS
+        + legs.iter()
+            .flat_map(|leg| &leg.sub_swaps)
+            .map(|sub| estimated_swap_compute_units(sub.protocol))
+            .sum::<u64>()
+}
+
+/// Статистика исполнений через пул
+#[derive(Debug, Clone, Copy, Default)]
+struct PoolStats {
+    attempts: u32,
+    successes: u32,
+}
+
+impl PoolStats {
+    /// Вероятность успеха со сглаживанием Лапласа (новый пул — 0.5)
+    fn success_rate(&self) -> f64 {
+        (self.successes as f64 + 1.0) / (self.attempts as f64 + 2.0)
+    }
+}
+
+pub struct OpportunityScorer {
+    config: Arc<BotConfig>,
+    /// История исполнений по пулам
+    stats: DashMap<Pubkey, PoolStats>,
+}
+
+impl OpportunityScorer {
+    pub fn new(config: Arc<BotConfig>) -> Self {
+        Self {
+            config,
+            stats: DashMap::new(),
+        }
+    }
+
+    /// Историческая вероятность успеха цикла: произведение по пулам
+    pub fn success_rate(&self, opportunity: &ArbitrageOpportunity) -> f64 {
+        opportunity.legs.iter()
+            .flat_map(|leg| leg.pool_ids())
+            .map(|pool_id| self.stats.get(&pool_id).map_or(0.5, |s| s.success_rate()))
+            .product()
+    }
+
+    /// Риск-скорректированная оценка (в lamports):
+    /// прибыль × вероятность успеха × свежесть данных / нагрузка по CU
+    pub fn score(&self, opportunity: &ArbitrageOpportunity, now: i64) -> f64 {
+        if opportunity.net_profit_lamports <= 0 {
+            return 0.0;
+        }
+
+        let age_secs = (now - opportunity.oldest_pool_update).max(0) as f64;
+        let freshness = 0.5f64.powf(age_secs / STALENESS_HALF_LIFE_SECS);
+
+        // Цикл, занимающий весь CU лимит, оценивается вдвое ниже бесплатного
+        let cu_limit = self.config.trading.compute_unit_limit.max(1) as f64;
+        let cu_load = 1.0 + estimated_compute_units(&opportunity.legs) as f64 / cu_limit;
+
+        opportunity.net_profit_lamports as f64 * self.success_rate(opportunity) * freshness / cu_load
+    }
+
+    /// Оценка и выбор возможностей без общих пулов и общего стартового токена
+    /// (по убыванию оценки). ATA стартового токена — такое же общее состояние,
+    /// как пул: два цикла с одного баланса не исполняются параллельно
+    pub fn select(&self, mut opportunities: Vec<ArbitrageOpportunity>) -> Vec<ArbitrageOpportunity> {
+        let now = chrono::Utc::now().timestamp();
+        for opportunity in &mut opportunities {
+            opportunity.score = self.score(opportunity, now);
+        }
+        opportunities.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+        let mut used_pools = HashSet::new();
+        let mut used_starts = HashSet::new();
+        let mut selected = Vec::new();
+
+        for opportunity in opportunities {
+            if opportunity.score <= 0.0 {
+                continue;
+            }
+
+            let pools: Vec<Pubkey> = opportunity.legs.iter().flat_map(|leg| leg.pool_ids()).collect();
+            if pools.iter().any(|pool| used_pools.contains(pool)) {
+                debug!("   Пропуск возможности: пересекается по пулам с более выгодной");
+                continue;
+            }
+
+            let start_mint = opportunity.legs.first().map(|leg| leg.input_mint);
+            if start_mint.is_some_and(|mint| used_starts.contains(&mint)) {
+                debug!("   Пропуск возможности: стартовый токен занят более выгодной");
+                continue;
+            }
+
+            used_pools.extend(pools);
+            used_starts.extend(start_mint);
+            selected.push(opportunity);
+        }
+
+        selected
+    }
+
+    /// Учёт результата исполнения для будущих оценок
+    pub fn record_outcome(&self, opportunity: &ArbitrageOpportunity, success: bool) {
+        for pool_id in opportunity.legs.iter().flat_map(|leg| leg.pool_ids()) {
+            let mut stats = self.stats.entry(pool_id).or_default();
+            stats.attempts += 1;
+            if success {
+                stats.successes += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{SubSwap, SwapLeg};
+
+    fn opportunity(pools: &[(Pubkey, DexProtocol)], net_profit_lamports: i64, oldest_pool_update: i64) -> ArbitrageOpportunity {
+        let legs = pools.iter()
+            .map(|&(pool_id, protocol)| SwapLeg {
+                pool_id,
+                input_mint: Pubkey::new_unique(),
+                output_mint: Pubkey::new_unique(),
+                input_decimals: 9,
+                output_decimals: 9,
+                amount_in: 1_000_000,
+                minimum_amount_out: 0,
+                estimated_amount_out: 1_000_000,
+                sub_swaps: vec![SubSwap {
+                    protocol,
+                    pool_id,
+                    amount_in: 1_000_000,
+                    minimum_amount_out: 0,
+                    estimated_amount_out: 1_000_000,
+                    fee_bps: 25,
+                    spot_rate: 1.0,
+                    pool_state_data: vec![],
+                    arrays_crossed: 0,
+                }],
+            })
+            .collect();
+
+        ArbitrageOpportunity {
+            legs,
+            initial_amount: 1_000_000,
+            expected_final_amount: 1_000_000,
+            gross_profit: net_profit_lamports,
+            net_profit: net_profit_lamports,
+            net_profit_lamports,
+            min_net_profit: 0,
+            profit_percentage: 0.0,
+            discovered_at: oldest_pool_update,
+            oldest_pool_update,
+            score: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_score_weights_staleness_compute_and_success() {
+        let scorer = OpportunityScorer::new(Arc::new(BotConfig::for_tests()));
+        let cpmm = [(Pubkey::new_unique(), DexProtocol::RaydiumCpmm), (Pubkey::new_unique(), DexProtocol::RaydiumCpmm)];
+        let fresh = opportunity(&cpmm, 1_000_000, 100);
+
+        // Новые пулы — 0.5 каждый; CU: (30k + 2 × 50k) / 400k
+        let base = scorer.score(&fresh, 100);
+        assert!((base - 1_000_000.0 * 0.25 / (1.0 + 130_000.0 / 400_000.0)).abs() < 1e-6);
+
+        // Данные на период полураспада старше — оценка вдвое ниже
+        assert!((scorer.score(&fresh, 110) / base - 0.5).abs() < 1e-9);
+
+        // CLMM тяжелее по CU при той же прибыли
+        let clmm = [(Pubkey::new_unique(), DexProtocol::RaydiumClmm), (Pubkey::new_unique(), DexProtocol::RaydiumClmm)];
+        assert!(scorer.score(&opportunity(&clmm, 1_000_000, 100), 100) < base);
+
+        // Успешное исполнение поднимает вероятность успеха пулов до 2/3
+        scorer.record_outcome(&fresh, true);
+        let after_success = scorer.score(&fresh, 100);
+        assert!((after_success / base - (4.0 / 9.0) / 0.25).abs() < 1e-9);
+
+        assert_eq!(scorer.score(&opportunity(&cpmm, 0, 100), 100), 0.0);
+    }
+
+    #[test]
+    fn test_select_disjoint_pools_by_score() {
+        let scorer = OpportunityScorer::new(Arc::new(BotConfig::for_tests()));
+        let now = chrono::Utc::now().timestamp();
+        let pool = |_| (Pubkey::new_unique(), DexProtocol::RaydiumCpmm);
+        let pools: Vec<(Pubkey, DexProtocol)> = (0..5).map(pool).collect();
+
+        let small = opportunity(&pools[0..2], 1_000_000, now);
+        let best = opportunity(&pools[1..3], 3_000_000, now); // делит пул с small
+        let second = opportunity(&pools[3..5], 2_000_000, now);
+        let losing = opportunity(&[pool(5)], -1, now);
+
+        let selected = scorer.select(vec![small, losing, second.clone(), best.clone()]);
+        let first_pools: Vec<Pubkey> = selected.iter().map(|o| o.legs[0].pool_id).collect();
+
+        assert_eq!(first_pools, vec![best.legs[0].pool_id, second.legs[0].pool_id]);
+        assert!(selected[0].score > selected[1].score);
+    }
+
+    #[test]
+    fn test_select_one_opportunity_per_start_mint() {
+        let scorer = OpportunityScorer::new(Arc::new(BotConfig::for_tests()));
+        let now = chrono::Utc::now().timestamp();
+        let pool = |_| (Pubkey::new_unique(), DexProtocol::RaydiumCpmm);
+        let pools: Vec<(Pubkey, DexProtocol)> = (0..6).map(pool).collect();
+        let sol = Pubkey::new_unique();
+
+        // Пулы не пересекаются, но первые две стартуют с одного ATA
+        let mut best = opportunity(&pools[0..2], 3_000_000, now);
+        let mut same_start = opportunity(&pools[2..4], 2_000_000, now);
+        best.legs[0].input_mint = sol;
+        same_start.legs[0].input_mint = sol;
+        let other_start = opportunity(&pools[4..6], 1_000_000, now);
+
+        let selected = scorer.select(vec![same_start, other_start.clone(), best.clone()]);
+        let first_pools: Vec<Pubkey> = selected.iter().map(|o| o.legs[0].pool_id).collect();
+
+        assert_eq!(first_pools, vec![best.legs[0].pool_id, other_start.legs[0].pool_id]);
+    }
+}
@@ -0,0 +1,157 @@
+// bot/src/arbitrage/split_route.rs
+// Разбиение входа перехода между параллельными пулами пары:
+// вход раздаётся порциями в пул с наибольшим предельным выходом,
+// что выравнивает предельные цены и снижает суммарное проскальзывание
+
+use anyhow::Result;
+use solana_sdk::pubkey::Pubkey;
+
+use super::pool_math::quote_exact_in;
+use crate::types::PoolState;
+
+/// Максимум sub-swap'ов в одном переходе (ограничение программы исполнителя)
+pub const MAX_SUB_SWAPS_PER_HOP: usize = 3;
+/// Количество порций, на которые делится вход
+const SPLIT_STEPS: u64 = 20;
+/// Разбиение применяется, только если выигрыш над одним пулом не меньше порога
+/// (каждый дополнительный CPI стоит compute units)
+const MIN_SPLIT_GAIN_BPS: f64 = 1.0;
+
+/// Доля входа перехода, направленная в один пул
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HopAllocation {
+    pub pool_id: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+}
+
+/// Распределение amount_in между пулами (pools[0] — пул цикла).
+/// Возвращает непустой список долей с ненулевым входом
+pub fn split_hop(pools: &[&PoolState], input_mint: &Pubkey, amount_in: u64) -> Result<Vec<HopAllocation>> {
+    let primary = pools.first()
+        .ok_or_else(|| anyhow::anyhow!("Нет пулов для перехода"))?;
+    let quote = |pool: &PoolState, amount: u64| -> Option<u64> {
+        if amount == 0 {
+            return Some(0);
+        }
+        quote_exact_in(pool, amount, *input_mint == pool.token_a).ok()
+    };
+
+    let single_out = quote(primary, amount_in)
+        .ok_or_else(|| anyhow::anyhow!("Пул {} не котирует переход", primary.id))?;
+    let single = vec![HopAllocation { pool_id: primary.id, amount_in, amount_out: single_out }];
+
+    if pools.len() < 2 || amount_in < SPLIT_STEPS {
+        return Ok(single);
+    }
+
+    // Остаток от деления достаётся первой порции
+    let chunk = amount_in / SPLIT_STEPS;
+    let first = amount_in - chunk * (SPLIT_STEPS - 1);
+
+    let mut allocated = vec![0u64; pools.len()];
+    let mut outputs = vec![0u64; pools.len()];
+    // Котировка пула после добавления следующей порции (кэш до изменения доли пула)
+    let mut next_outputs: Vec<Option<u64>> = pools.iter().map(|pool| quote(pool, first)).collect();
+
+    for step in 0..SPLIT_STEPS {
+        let size = if step == 0 { first } else { chunk };
+        let used = allocated.iter().filter(|amount| **amount > 0).count();
+
+        let best = (0..pools.len())
+            .filter(|i| used < MAX_SUB_SWAPS_PER_HOP || allocated[*i] > 0)
+            .filter_map(|i| next_outputs[i].map(|out| (i, out.saturating_sub(outputs[i]))))
+            .max_by_key(|(_, gain)| *gain);
+
+        let Some((i, _)) = best else {
+            return Ok(single);
+        };
+
+        allocated[i] += size;
+        outputs[i] = next_outputs[i].unwrap_or(0);
+        next_outputs[i] = quote(pools[i], allocated[i] + chunk);
+
+        // Первая порция меняет базу котировок остальных пулов
+        if step == 0 {
+            for (j, pool) in pools.iter().enumerate().filter(|(j, _)| *j != i) {
+                next_outputs[j] = quote(pool, chunk);
+            }
+        }
+    }
+
+    let split_out: u64 = outputs.iter().sum();
+    if (split_out as f64) < single_out as f64 * (1.0 + MIN_SPLIT_GAIN_BPS / 10_000.0) {
+        return Ok(single);
+    }
+
+    Ok(pools.iter()
+        .zip(allocated.iter().zip(&outputs))
+        .filter(|(_, (amount, _))| **amount > 0)
+        .map(|(pool, (amount, out))| HopAllocation {
+            pool_id: pool.id,
+            amount_in: *amount,
+            amount_out: *out,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::DexProtocol;
+
+    fn pool(token_a: Pubkey, token_b: Pubkey, reserve: u64) -> PoolState {
+        PoolState {
+            id: Pubkey::new_unique(),
+            protocol: DexProtocol::RaydiumCpmm,
+            token_a,
+            token_b,
+            reserve_a: reserve,
+            reserve_b: reserve,
+            fee_bps: 25,
+            last_updated: 0,
+            full_state_data: vec![],
+            decimals_a: 9,
+            decimals_b: 9,
+            liquidity_accounts: vec![],
+        }
+    }
+
+    #[test]
+    fn test_split_equalizes_between_equal_pools() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (p1, p2) = (pool(a, b, 1_000_000_000), pool(a, b, 1_000_000_000));
+        let amount = 100_000_000;
+
+        let single = split_hop(&[&p1], &a, amount).unwrap();
+        let split = split_hop(&[&p1, &p2], &a, amount).unwrap();
+
+        assert_eq!(split.len(), 2);
+        assert_eq!(split.iter().map(|s| s.amount_in).sum::<u64>(), amount);
+        assert_eq!(split[0].amount_in, split[1].amount_in);
+        assert!(split.iter().map(|s| s.amount_out).sum::<u64>() > single[0].amount_out);
+    }
+
+    #[test]
+    fn test_small_amount_stays_in_one_pool() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let deep = pool(a, b, 1_000_000_000_000);
+        let shallow = pool(a, b, 1_000_000);
+
+        // Предельный выигрыш от мелкого пула меньше порога — разбиения нет
+        let split = split_hop(&[&deep, &shallow], &a, 1_000_000).unwrap();
+        assert_eq!(split.len(), 1);
+        assert_eq!(split[0].pool_id, deep.id);
+    }
+
+    #[test]
+    fn test_split_limited_to_max_sub_swaps() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let pools: Vec<PoolState> = (0..5).map(|_| pool(a, b, 100_000_000)).collect();
+        let refs: Vec<&PoolState> = pools.iter().collect();
+
+        let split = split_hop(&refs, &a, 100_000_000).unwrap();
+        assert_eq!(split.len(), MAX_SUB_SWAPS_PER_HOP);
+        assert_eq!(split.iter().map(|s| s.amount_in).sum::<u64>(), 100_000_000);
+    }
+}
@@ -13,6 +13,10 @@ pub struct BotConfig {
     pub dex: DexConfig,
     pub jito: Option<JitoConfig>,
     pub monitoring: MonitoringConfig,
+    pub reputation: ReputationConfig,
+    pub oracle: OracleConfig,
+    pub lookup_tables: LookupTableConfig,
+    pub ata: AtaConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,6 +25,8 @@ pub struct RpcConfig {
     pub ws_url: String,
     pub commitment: String,
     pub timeout_seconds: u64,
+    /// Период обновления кэша блокхеша и слота в фоне
+    pub blockhash_refresh_ms: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,8 +42,25 @@ pub struct TradingConfig {
     pub max_slippage_bps: u16,
     pub initial_amount_sol: f64,
     pub max_legs: u8,
+    /// Лимит CU для нормировки нагрузки в оценке возможностей (итоговый лимит — по симуляции)
     pub compute_unit_limit: u32,
+    /// Цена CU для оценки прибыли и при недоступном рынке комиссий (micro-lamports)
     pub priority_fee_micro_lamports: u64,
+    /// Запас к потреблению CU по симуляции (1.15 = +15%)
+    pub compute_unit_margin: f64,
+    /// Перцентиль getRecentPrioritizationFees для цены CU
+    pub priority_fee_percentile: u8,
+    /// Доля ожидаемой прибыли, которую могут занять комиссии транзакции
+    pub max_fee_profit_share: f64,
+    /// Ожидание подтверждения транзакции, отправленной через RPC (с)
+    pub confirm_timeout_secs: u64,
+    /// Бюджет задержки backrun-поиска через один пул (мс)
+    pub backrun_budget_ms: u64,
+    /// Бюджет полного прохода от входа, включая синхронизацию графа (мс);
+    /// по истечении результат частичный
+    pub search_budget_ms: u64,
+    /// Порог ликвидности: пул котирует столько объёмов сделки с ограниченным impact
+    pub min_liquidity_multiple: f64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -47,6 +70,8 @@ pub struct DexConfig {
     pub raydium_clmm: PubkeyString,
     pub meteora_dlmm: PubkeyString,
     pub openbook_id: PubkeyString,  // НОВОЕ ПОЛЕ
+    /// AMM V4: swap_base_in_v2 без аккаунтов OpenBook вместо legacy swap_base_in
+    pub raydium_amm_swap_v2: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -54,12 +79,96 @@ pub struct JitoConfig {
     pub block_engine_url: String,
     pub tip_account: PubkeyString,
     pub tip_lamports: u64,
+    /// Сколько ждать подтверждения bundle (сек)
+    pub confirm_timeout_secs: u64,
+    /// Когда отправлять транзакцию через RPC, если Jito не помог
+    pub fallback: JitoFallback,
+}
+
+/// Политика отправки через RPC при неудаче Jito. Явный отказ block engine
+/// (JSON-RPC ошибка, HTTP 4xx) значит, что транзакция никуда не ушла: она
+/// пересобирается без tip и подписывается заново. Если bundle мог быть принят
+/// (сбой сети, 5xx, неразборчивый ответ, таймаут подтверждения), отправляется
+/// та же подписанная транзакция с tip — в блок она попадёт не больше одного раза
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum JitoFallback {
+    /// Только Jito
+    Never,
+    /// Block engine отклонил bundle (новая транзакция без tip)
+    /// или не ответил (та же транзакция)
+    OnError,
+    /// Также bundle не подтверждён за confirm_timeout_secs (та же транзакция,
+    /// со свежим блокхешем — только если прежний истёк)
+    OnTimeout,
+}
+
+impl FromStr for JitoFallback {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "never" => Ok(Self::Never),
+            "on_error" => Ok(Self::OnError),
+            "on_timeout" => Ok(Self::OnTimeout),
+            other => anyhow::bail!("ожидается never, on_error или on_timeout, получено {}", other),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitoringConfig {
     pub log_level: String,
     pub telemetry_enabled: bool,
+    /// Порог события PoolChanged по изменению цены (%)
+    pub pool_change_price_pct: f64,
+    /// Порог события PoolChanged по изменению резервов (%)
+    pub pool_change_reserve_pct: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReputationConfig {
+    /// Кулдаун после первой неудачи (сек), далее удваивается
+    pub cooldown_base_secs: u64,
+    /// Верхняя граница кулдауна (сек)
+    pub cooldown_max_secs: u64,
+    /// Число неудач InsufficientProfit/slippage подряд до автоматического бана пула
+    pub blacklist_after_failures: u32,
+    /// Пулы, которые никогда не банятся автоматически
+    pub pool_allowlist: Vec<PubkeyString>,
+    /// Пулы, исключённые из исполнения вручную
+    pub pool_denylist: Vec<PubkeyString>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OracleConfig {
+    /// Пары (mint, аккаунт цены Pyth)
+    pub pyth_feeds: Vec<(PubkeyString, PubkeyString)>,
+    /// Допустимое отклонение курса ноги от оракула сверх доверительных интервалов (bps)
+    pub max_deviation_bps: u16,
+    /// Цена старше этого числа слотов не используется
+    pub max_staleness_slots: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LookupTableConfig {
+    /// Address Lookup Tables, доступные для v0-транзакций
+    pub addresses: Vec<PubkeyString>,
+    /// Создавать и расширять собственные таблицы часто используемыми аккаунтами
+    pub auto_extend: bool,
+    /// Аккаунт добавляется в таблицу после стольких транзакций с ним
+    pub min_usage: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AtaConfig {
+    /// Создавать недостающие ATA отдельной транзакцией до арбитража (иначе — в самой транзакции)
+    pub setup_transaction: bool,
+    /// Закрывать пустые ATA, созданные ботом, для возврата ренты
+    pub cleanup_enabled: bool,
+    /// Интервал очистки и минимальный простой ATA перед закрытием (сек)
+    pub cleanup_interval_secs: u64,
+    /// Mint'ы, ATA которых никогда не закрываются
+    pub keep_mints: Vec<PubkeyString>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -93,6 +202,11 @@ impl BotConfig {
             )
         };
 
+        let raydium_amm_swap_v2 = std::env::var("RAYDIUM_AMM_SWAP_V2")
+            .unwrap_or_else(|_| "true".to_string())
+            .parse()
+            .context("Invalid RAYDIUM_AMM_SWAP_V2")?;
+
         // ПРАВИЛЬНЫЕ PROGRAM IDs ДЛЯ DEVNET/MAINNET
         let dex = if is_devnet {
             DexConfig {
@@ -101,6 +215,7 @@ impl BotConfig {
                 raydium_clmm: PubkeyString("DRayAUgENGQBKVaX8owNhgzkEDyoHTGVEGHVJT1E9pfH".to_string()),
                 meteora_dlmm: PubkeyString("LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo".to_string()),
                 openbook_id: PubkeyString("opnb2LAfJYbRMAHHvqjCwQxanZn7ReEHp1k81EohpZb".to_string()),
+                raydium_amm_swap_v2,
             }
         } else {
             DexConfig {
@@ -109,6 +224,7 @@ impl BotConfig {
                 raydium_clmm: PubkeyString("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK".to_string()),
                 meteora_dlmm: PubkeyString("LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo".to_string()),
                 openbook_id: PubkeyString("srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX".to_string()),
+                raydium_amm_swap_v2,
             }
         };
 
@@ -118,6 +234,10 @@ impl BotConfig {
                 ws_url,
                 commitment: "confirmed".to_string(),
                 timeout_seconds: 30,
+                blockhash_refresh_ms: std::env::var("BLOCKHASH_REFRESH_MS")
+                    .unwrap_or_else(|_| "400".to_string())
+                    .parse()
+                    .context("Invalid BLOCKHASH_REFRESH_MS")?,
             },
             wallet: WalletConfig {
                 path: std::env::var("WALLET_PATH")
@@ -148,16 +268,234 @@ impl BotConfig {
                 max_legs: 5,
                 compute_unit_limit: 400_000,
                 priority_fee_micro_lamports: 100_000,
+                compute_unit_margin: std::env::var("COMPUTE_UNIT_MARGIN")
+                    .unwrap_or_else(|_| "1.15".to_string())
+                    .parse()
+                    .context("Invalid COMPUTE_UNIT_MARGIN")?,
+                priority_fee_percentile: std::env::var("PRIORITY_FEE_PERCENTILE")
+                    .unwrap_or_else(|_| "75".to_string())
+                    .parse()
+                    .context("Invalid PRIORITY_FEE_PERCENTILE")?,
+                max_fee_profit_share: std::env::var("MAX_FEE_PROFIT_SHARE")
+                    .unwrap_or_else(|_| "0.5".to_string())
+                    .parse()
+                    .context("Invalid MAX_FEE_PROFIT_SHARE")?,
+                confirm_timeout_secs: std::env::var("CONFIRM_TIMEOUT_SECS")
+                    .unwrap_or_else(|_| "30".to_string())
+                    .parse()
+                    .context("Invalid CONFIRM_TIMEOUT_SECS")?,
+                backrun_budget_ms: std::env::var("BACKRUN_BUDGET_MS")
+                    .unwrap_or_else(|_| "30".to_string())
+                    .parse()
+                    .context("Invalid BACKRUN_BUDGET_MS")?,
+                search_budget_ms: std::env::var("SEARCH_BUDGET_MS")
+                    .unwrap_or_else(|_| "250".to_string())
+                    .parse()
+                    .context("Invalid SEARCH_BUDGET_MS")?,
+                min_liquidity_multiple: std::env::var("MIN_LIQUIDITY_MULTIPLE")
+                    .unwrap_or_else(|_| "10".to_string())
+                    .parse()
+                    .context("Invalid MIN_LIQUIDITY_MULTIPLE")?,
             },
             dex,
-            jito: None, // Отключаем Jito на devnet
+            jito: jito_from_env(is_devnet)?,
             monitoring: MonitoringConfig {
                 log_level: std::env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string()),
                 telemetry_enabled: std::env::var("TELEMETRY_ENABLED")
                     .unwrap_or_else(|_| "false".to_string())
                     .parse()
                     .unwrap_or(false),
+                pool_change_price_pct: std::env::var("POOL_CHANGE_PRICE_PCT")
+                    .unwrap_or_else(|_| "0.5".to_string())
+                    .parse()
+                    .context("Invalid POOL_CHANGE_PRICE_PCT")?,
+                pool_change_reserve_pct: std::env::var("POOL_CHANGE_RESERVE_PCT")
+                    .unwrap_or_else(|_| "2.0".to_string())
+                    .parse()
+                    .context("Invalid POOL_CHANGE_RESERVE_PCT")?,
+            },
+            reputation: ReputationConfig {
+                cooldown_base_secs: std::env::var("FAILURE_COOLDOWN_BASE_SECS")
+                    .unwrap_or_else(|_| "10".to_string())
+                    .parse()
+                    .context("Invalid FAILURE_COOLDOWN_BASE_SECS")?,
+                cooldown_max_secs: std::env::var("FAILURE_COOLDOWN_MAX_SECS")
+                    .unwrap_or_else(|_| "600".to_string())
+                    .parse()
+                    .context("Invalid FAILURE_COOLDOWN_MAX_SECS")?,
+                blacklist_after_failures: std::env::var("POOL_BLACKLIST_AFTER_FAILURES")
+                    .unwrap_or_else(|_| "5".to_string())
+                    .parse()
+                    .context("Invalid POOL_BLACKLIST_AFTER_FAILURES")?,
+                pool_allowlist: pubkey_list_from_env("POOL_ALLOWLIST"),
+                pool_denylist: pubkey_list_from_env("POOL_DENYLIST"),
+            },
+            oracle: OracleConfig {
+                pyth_feeds: pyth_feeds_from_env("PYTH_FEEDS")?,
+                max_deviation_bps: std::env::var("ORACLE_MAX_DEVIATION_BPS")
+                    .unwrap_or_else(|_| "300".to_string())
+                    .parse()
+                    .context("Invalid ORACLE_MAX_DEVIATION_BPS")?,
+                max_staleness_slots: std::env::var("ORACLE_MAX_STALENESS_SLOTS")
+                    .unwrap_or_else(|_| "100".to_string())
+                    .parse()
+                    .context("Invalid ORACLE_MAX_STALENESS_SLOTS")?,
+            },
+            lookup_tables: LookupTableConfig {
+                addresses: pubkey_list_from_env("ALT_ADDRESSES"),
+                auto_extend: std::env::var("ALT_AUTO_EXTEND")
+                    .unwrap_or_else(|_| "true".to_string())
+                    .parse()
+                    .context("Invalid ALT_AUTO_EXTEND")?,
+                min_usage: std::env::var("ALT_MIN_USAGE")
+                    .unwrap_or_else(|_| "3".to_string())
+                    .parse()
+                    .context("Invalid ALT_MIN_USAGE")?,
             },
+            ata: AtaConfig {
+                setup_transaction: std::env::var("ATA_SETUP_TRANSACTION")
+                    .unwrap_or_else(|_| "false".to_string())
+                    .parse()
+                    .context("Invalid ATA_SETUP_TRANSACTION")?,
+                cleanup_enabled: std::env::var("ATA_CLEANUP")
+                    .unwrap_or_else(|_| "false".to_string())
+                    .parse()
+                    .context("Invalid ATA_CLEANUP")?,
+                cleanup_interval_secs: std::env::var("ATA_CLEANUP_INTERVAL_SECS")
+                    .unwrap_or_else(|_| "600".to_string())
+                    .parse()
+                    .context("Invalid ATA_CLEANUP_INTERVAL_SECS")?,
+                keep_mints: pubkey_list_from_env("ATA_KEEP_MINTS"),
+            },
+        })
+    }
+}
+
+/// Jito из переменных окружения: включается JITO_ENABLED (block engine недоступен на devnet)
+fn jito_from_env(is_devnet: bool) -> Result<Option<JitoConfig>> {
+    let enabled: bool = std::env::var("JITO_ENABLED")
+        .unwrap_or_else(|_| "false".to_string())
+        .parse()
+        .context("Invalid JITO_ENABLED")?;
+    if !enabled || is_devnet {
+        return Ok(None);
+    }
+
+    Ok(Some(JitoConfig {
+        block_engine_url: std::env::var("JITO_BLOCK_ENGINE_URL")
+            .unwrap_or_else(|_| "https://mainnet.block-engine.jito.wtf".to_string()),
+        tip_account: PubkeyString(
+            std::env::var("JITO_TIP_ACCOUNT")
+                .unwrap_or_else(|_| "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5".to_string()),
+        ),
+        tip_lamports: std::env::var("JITO_TIP_LAMPORTS")
+            .unwrap_or_else(|_| "300000".to_string())
+            .parse()
+            .context("Invalid JITO_TIP_LAMPORTS")?,
+        confirm_timeout_secs: std::env::var("JITO_CONFIRM_TIMEOUT_SECS")
+            .unwrap_or_else(|_| "30".to_string())
+            .parse()
+            .context("Invalid JITO_CONFIRM_TIMEOUT_SECS")?,
+        fallback: std::env::var("JITO_FALLBACK")
+            .unwrap_or_else(|_| "on_error".to_string())
+            .parse()
+            .context("Invalid JITO_FALLBACK")?,
+    }))
+}
+
+/// Список pubkey из переменной окружения через запятую (пусто, если не задана)
+fn pubkey_list_from_env(name: &str) -> Vec<PubkeyString> {
+    std::env::var(name)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| PubkeyString(s.to_string()))
+        .collect()
+}
+
+/// Фиды Pyth из переменной окружения: `mint:price_account` через запятую
+fn pyth_feeds_from_env(name: &str) -> Result<Vec<(PubkeyString, PubkeyString)>> {
+    std::env::var(name)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|entry| {
+            let (mint, account) = entry.split_once(':')
+                .with_context(|| format!("Invalid {}: ожидается mint:price_account, получено {}", name, entry))?;
+            Ok((PubkeyString(mint.trim().to_string()), PubkeyString(account.trim().to_string())))
         })
+        .collect()
+}
+#[cfg(test)]
+impl BotConfig {
+    /// Конфигурация mainnet со значениями по умолчанию из load(), без окружения
+    pub fn for_tests() -> Self {
+        Self {
+            rpc: RpcConfig {
+                url: "https://api.mainnet-beta.solana.com".to_string(),
+                ws_url: "wss://api.mainnet-beta.solana.com".to_string(),
+                commitment: "confirmed".to_string(),
+                timeout_seconds: 30,
+                blockhash_refresh_ms: 400,
+            },
+            wallet: WalletConfig { path: PathBuf::new() },
+            trading: TradingConfig {
+                executor_program_id: PubkeyString(Pubkey::default().to_string()),
+                min_profit_lamports: 1000,
+                min_profit_bps: 10,
+                max_slippage_bps: 500,
+                initial_amount_sol: 0.01,
+                max_legs: 5,
+                compute_unit_limit: 400_000,
+                priority_fee_micro_lamports: 100_000,
+                compute_unit_margin: 1.15,
+                priority_fee_percentile: 75,
+                max_fee_profit_share: 0.5,
+                confirm_timeout_secs: 30,
+                backrun_budget_ms: 30,
+                search_budget_ms: 250,
+                min_liquidity_multiple: 10.0,
+            },
+            dex: DexConfig {
+                raydium_amm_v4: PubkeyString("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8".to_string()),
+                raydium_cpmm: PubkeyString("CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C".to_string()),
+                raydium_clmm: PubkeyString("CAMMCzo5YL8w4VFF8KVHrK22GGUsp5VTaW7grrKgrWqK".to_string()),
+                meteora_dlmm: PubkeyString("LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo".to_string()),
+                openbook_id: PubkeyString("srmqPvymJeFKQ4zGQed1GFppgkRHL9kaELCbyksJtPX".to_string()),
+                raydium_amm_swap_v2: true,
+            },
+            jito: None,
+            monitoring: MonitoringConfig {
+                log_level: "info".to_string(),
+                telemetry_enabled: false,
+                pool_change_price_pct: 0.5,
+                pool_change_reserve_pct: 2.0,
+            },
+            reputation: ReputationConfig {
+                cooldown_base_secs: 10,
+                cooldown_max_secs: 600,
+                blacklist_after_failures: 5,
+                pool_allowlist: vec![],
+                pool_denylist: vec![],
+            },
+            oracle: OracleConfig {
+                pyth_feeds: vec![],
+                max_deviation_bps: 300,
+                max_staleness_slots: 100,
+            },
+            lookup_tables: LookupTableConfig {
+                addresses: vec![],
+                auto_extend: true,
+                min_usage: 3,
+            },
+            ata: AtaConfig {
+                setup_transaction: false,
+                cleanup_enabled: false,
+                cleanup_interval_secs: 600,
+                keep_mints: vec![],
+            },
+        }
     }
-}
\ No newline at end of file
+}
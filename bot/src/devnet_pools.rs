@@ -29,6 +29,7 @@ pub fn get_devnet_pools() -> Result<Vec<PoolState>> {
         full_state_data: vec![],
         decimals_a: 9,
         decimals_b: 9,
+        liquidity_accounts: vec![],
     });
 
     // Пул #2: TOKEN_B - TOKEN_C (CLMM pool) - 1:1
@@ -44,6 +45,7 @@ pub fn get_devnet_pools() -> Result<Vec<PoolState>> {
         full_state_data: vec![],
         decimals_a: 9,
         decimals_b: 9,
+        liquidity_accounts: vec![],
     });
 
     // Пул #3: TOKEN_C - TOKEN_A (CLMM pool) - ДИСБАЛАНС 10:1 (Для создания возможности)
@@ -60,6 +62,7 @@ pub fn get_devnet_pools() -> Result<Vec<PoolState>> {
         full_state_data: vec![],
         decimals_a: 9,
         decimals_b: 9,
+        liquidity_accounts: vec![],
     });
 
     // Теперь у нас есть A-B, B-C, C-A. Это замкнутый цикл.
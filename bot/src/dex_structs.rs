@@ -6,24 +6,31 @@ use anyhow::{Result, anyhow};
 /// Минимальная длина данных для Raydium AMM V4 AmmInfo
 const MIN_AMM_INFO_LEN: usize = 752;
 
-// ПРАВИЛЬНЫЕ ОФСЕТЫ ДЛЯ RAYDIUM AMM V4
-const MARKET_ID_OFFSET: usize = 224;
-const MARKET_PROGRAM_ID_OFFSET: usize = 256;
-const BASE_VAULT_OFFSET: usize = 384;
-const QUOTE_VAULT_OFFSET: usize = 416;
-const BASE_MINT_OFFSET: usize = 632;
-const QUOTE_MINT_OFFSET: usize = 664;
-
-// Дополнительные важные поля
+// ОФСЕТЫ RAYDIUM AMM V4 (AmmInfo: 16 u64, Fees, StateData, затем ключи)
 const STATUS_OFFSET: usize = 0;
 const NONCE_OFFSET: usize = 8;
-const OPEN_ORDERS_OFFSET: usize = 168;
-const TARGET_ORDERS_OFFSET: usize = 200;
+const COIN_DECIMALS_OFFSET: usize = 32;
+const PC_DECIMALS_OFFSET: usize = 40;
+const SWAP_FEE_NUMERATOR_OFFSET: usize = 176;
+const SWAP_FEE_DENOMINATOR_OFFSET: usize = 184;
+const BASE_VAULT_OFFSET: usize = 336;
+const QUOTE_VAULT_OFFSET: usize = 368;
+const BASE_MINT_OFFSET: usize = 400;
+const QUOTE_MINT_OFFSET: usize = 432;
+const OPEN_ORDERS_OFFSET: usize = 496;
+const MARKET_ID_OFFSET: usize = 528;
+const MARKET_PROGRAM_ID_OFFSET: usize = 560;
+const TARGET_ORDERS_OFFSET: usize = 592;
+
+/// Seed authority пула AMM V4: ["amm authority", nonce]
+pub const AMM_AUTHORITY_SEED: &[u8] = b"amm authority";
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct AmmInfo {
     pub status: u64,
     pub nonce: u64,
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
     pub market_id: Pubkey,
     pub market_program_id: Pubkey,
     pub base_mint: Pubkey,
@@ -52,37 +59,87 @@ impl AmmInfo {
             ));
         }
 
-        let status = read_u64(data, STATUS_OFFSET)?;
-        let nonce = read_u64(data, NONCE_OFFSET)?;
-        let market_id = read_pubkey(data, MARKET_ID_OFFSET)?;
-        let market_program_id = read_pubkey(data, MARKET_PROGRAM_ID_OFFSET)?;
-        let base_mint = read_pubkey(data, BASE_MINT_OFFSET)?;
-        let quote_mint = read_pubkey(data, QUOTE_MINT_OFFSET)?;
-        let base_vault = read_pubkey(data, BASE_VAULT_OFFSET)?;
-        let quote_vault = read_pubkey(data, QUOTE_VAULT_OFFSET)?;
-        let open_orders = read_pubkey(data, OPEN_ORDERS_OFFSET)?;
-        let target_orders = read_pubkey(data, TARGET_ORDERS_OFFSET)?;
-
-        // Упрощенные комиссии (реальные офсеты зависят от версии)
         let fees = Fees {
-            swap_fee_numerator: 25,     // 0.25% = 25 bps
-            swap_fee_denominator: 10_000,
+            swap_fee_numerator: read_u64(data, SWAP_FEE_NUMERATOR_OFFSET)?,
+            swap_fee_denominator: read_u64(data, SWAP_FEE_DENOMINATOR_OFFSET)?,
         };
+        if fees.swap_fee_denominator == 0 {
+            return Err(anyhow!("Нулевой знаменатель комиссии AmmInfo"));
+        }
 
         Ok(Self {
-            status,
-            nonce,
-            market_id,
-            market_program_id,
-            base_mint,
-            quote_mint,
-            base_vault,
-            quote_vault,
-            open_orders,
-            target_orders,
+            status: read_u64(data, STATUS_OFFSET)?,
+            nonce: read_u64(data, NONCE_OFFSET)?,
+            base_decimals: read_u64(data, COIN_DECIMALS_OFFSET)? as u8,
+            quote_decimals: read_u64(data, PC_DECIMALS_OFFSET)? as u8,
+            market_id: read_pubkey(data, MARKET_ID_OFFSET)?,
+            market_program_id: read_pubkey(data, MARKET_PROGRAM_ID_OFFSET)?,
+            base_mint: read_pubkey(data, BASE_MINT_OFFSET)?,
+            quote_mint: read_pubkey(data, QUOTE_MINT_OFFSET)?,
+            base_vault: read_pubkey(data, BASE_VAULT_OFFSET)?,
+            quote_vault: read_pubkey(data, QUOTE_VAULT_OFFSET)?,
+            open_orders: read_pubkey(data, OPEN_ORDERS_OFFSET)?,
+            target_orders: read_pubkey(data, TARGET_ORDERS_OFFSET)?,
             fees,
         })
     }
+
+    /// Authority пула: PDA программы AMM с nonce из AmmInfo
+    pub fn authority(&self, amm_program_id: &Pubkey) -> Result<Pubkey> {
+        Pubkey::create_program_address(&[AMM_AUTHORITY_SEED, &[self.nonce as u8]], amm_program_id)
+            .map_err(|e| anyhow!("Некорректный nonce authority AMM: {}", e))
+    }
+}
+
+// -------------------------------------------------------------------------
+// OPENBOOK / SERUM MARKET (аккаунты рынка для legacy swap_base_in AMM V4)
+// -------------------------------------------------------------------------
+
+/// Длина MarketState v3: 5 байт "serum" + 376 байт состояния + 7 байт "padding"
+const MIN_OPENBOOK_MARKET_LEN: usize = 388;
+
+// Офсеты учитывают 5-байтовый префикс "serum"
+const MARKET_VAULT_SIGNER_NONCE_OFFSET: usize = 45;
+const MARKET_BASE_VAULT_OFFSET: usize = 117;
+const MARKET_QUOTE_VAULT_OFFSET: usize = 165;
+const MARKET_EVENT_QUEUE_OFFSET: usize = 253;
+const MARKET_BIDS_OFFSET: usize = 285;
+const MARKET_ASKS_OFFSET: usize = 317;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenbookMarket {
+    pub vault_signer_nonce: u64,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub event_queue: Pubkey,
+    pub bids: Pubkey,
+    pub asks: Pubkey,
+}
+
+impl OpenbookMarket {
+    pub fn try_from_slice(data: &[u8]) -> Result<Self> {
+        if data.len() < MIN_OPENBOOK_MARKET_LEN || &data[..5] != b"serum" {
+            return Err(anyhow!("Аккаунт не является рынком OpenBook: {} байт", data.len()));
+        }
+
+        Ok(Self {
+            vault_signer_nonce: read_u64(data, MARKET_VAULT_SIGNER_NONCE_OFFSET)?,
+            base_vault: read_pubkey(data, MARKET_BASE_VAULT_OFFSET)?,
+            quote_vault: read_pubkey(data, MARKET_QUOTE_VAULT_OFFSET)?,
+            event_queue: read_pubkey(data, MARKET_EVENT_QUEUE_OFFSET)?,
+            bids: read_pubkey(data, MARKET_BIDS_OFFSET)?,
+            asks: read_pubkey(data, MARKET_ASKS_OFFSET)?,
+        })
+    }
+
+    /// Vault signer рынка: create_program_address([market, nonce (LE)], market_program)
+    pub fn vault_signer(&self, market_id: &Pubkey, market_program_id: &Pubkey) -> Result<Pubkey> {
+        Pubkey::create_program_address(
+            &[market_id.as_ref(), &self.vault_signer_nonce.to_le_bytes()],
+            market_program_id,
+        )
+        .map_err(|e| anyhow!("Некорректный vault_signer_nonce рынка {}: {}", market_id, e))
+    }
 }
 
 // -------------------------------------------------------------------------
@@ -181,6 +238,314 @@ impl ClmmPoolInfo {
 }
 
 
+// -------------------------------------------------------------------------
+// RAYDIUM CLMM: полный PoolState, AmmConfig и TickArray (для котировки)
+// -------------------------------------------------------------------------
+
+/// Размер аккаунта PoolState Raydium CLMM (с дискриминатором)
+pub const CLMM_POOL_STATE_LEN: usize = 1544;
+/// Размер аккаунта AmmConfig Raydium CLMM
+pub const CLMM_AMM_CONFIG_LEN: usize = 117;
+/// Размер аккаунта TickArrayState Raydium CLMM
+pub const CLMM_TICK_ARRAY_LEN: usize = 10240;
+/// Количество тиков в одном TickArray
+pub const TICK_ARRAY_SIZE: i32 = 60;
+
+// Офсеты PoolState (включая 8-байтовый Anchor дискриминатор)
+const CLMM_AMM_CONFIG_OFFSET: usize = 9;
+const CLMM_MINT_0_OFFSET: usize = 73;
+const CLMM_MINT_1_OFFSET: usize = 105;
+const CLMM_VAULT_0_OFFSET: usize = 137;
+const CLMM_VAULT_1_OFFSET: usize = 169;
+const CLMM_OBSERVATION_OFFSET: usize = 201;
+const CLMM_DECIMALS_0_OFFSET: usize = 233;
+const CLMM_DECIMALS_1_OFFSET: usize = 234;
+const CLMM_TICK_SPACING_OFFSET: usize = 235;
+const CLMM_LIQUIDITY_OFFSET: usize = 237;
+const CLMM_SQRT_PRICE_OFFSET: usize = 253;
+const CLMM_TICK_CURRENT_OFFSET: usize = 269;
+const CLMM_TICK_ARRAY_BITMAP_OFFSET: usize = 904;
+
+// Офсеты AmmConfig
+const CLMM_TRADE_FEE_RATE_OFFSET: usize = 47;
+
+// Офсеты TickArrayState и TickState
+const TICK_ARRAY_START_INDEX_OFFSET: usize = 40;
+const TICK_ARRAY_TICKS_OFFSET: usize = 44;
+const TICK_STATE_LEN: usize = 168;
+const TICK_LIQUIDITY_NET_OFFSET: usize = 4;
+const TICK_LIQUIDITY_GROSS_OFFSET: usize = 20;
+
+/// Состояние CLMM пула, необходимое для симуляции свопа
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClmmPoolState {
+    pub amm_config: Pubkey,
+    pub token_mint_0: Pubkey,
+    pub token_mint_1: Pubkey,
+    pub token_vault_0: Pubkey,
+    pub token_vault_1: Pubkey,
+    pub observation_key: Pubkey,
+    pub mint_decimals_0: u8,
+    pub mint_decimals_1: u8,
+    pub tick_spacing: u16,
+    pub liquidity: u128,
+    pub sqrt_price_x64: u128,
+    pub tick_current: i32,
+    pub tick_array_bitmap: [u64; 16],
+}
+
+impl ClmmPoolState {
+    pub fn try_from_slice(data: &[u8]) -> Result<Self> {
+        if data.len() < CLMM_POOL_STATE_LEN {
+            return Err(anyhow!(
+                "Недостаточно данных для CLMM PoolState: {} байт, требуется {}",
+                data.len(),
+                CLMM_POOL_STATE_LEN
+            ));
+        }
+
+        let mut tick_array_bitmap = [0u64; 16];
+        for (i, word) in tick_array_bitmap.iter_mut().enumerate() {
+            *word = read_u64(data, CLMM_TICK_ARRAY_BITMAP_OFFSET + i * 8)?;
+        }
+
+        Ok(Self {
+            amm_config: read_pubkey(data, CLMM_AMM_CONFIG_OFFSET)?,
+            token_mint_0: read_pubkey(data, CLMM_MINT_0_OFFSET)?,
+            token_mint_1: read_pubkey(data, CLMM_MINT_1_OFFSET)?,
+            token_vault_0: read_pubkey(data, CLMM_VAULT_0_OFFSET)?,
+            token_vault_1: read_pubkey(data, CLMM_VAULT_1_OFFSET)?,
+            observation_key: read_pubkey(data, CLMM_OBSERVATION_OFFSET)?,
+            mint_decimals_0: data[CLMM_DECIMALS_0_OFFSET],
+            mint_decimals_1: data[CLMM_DECIMALS_1_OFFSET],
+            tick_spacing: read_u16(data, CLMM_TICK_SPACING_OFFSET)?,
+            liquidity: read_u128(data, CLMM_LIQUIDITY_OFFSET)?,
+            sqrt_price_x64: read_u128(data, CLMM_SQRT_PRICE_OFFSET)?,
+            tick_current: read_i32(data, CLMM_TICK_CURRENT_OFFSET)?,
+            tick_array_bitmap,
+        })
+    }
+}
+
+/// Конфигурация комиссий CLMM (AmmConfig)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClmmAmmConfig {
+    /// Комиссия свопа в единицах 1e-6 (2500 = 0.25%)
+    pub trade_fee_rate: u32,
+}
+
+impl ClmmAmmConfig {
+    pub fn try_from_slice(data: &[u8]) -> Result<Self> {
+        if data.len() < CLMM_AMM_CONFIG_LEN {
+            return Err(anyhow!("Недостаточно данных для CLMM AmmConfig: {} байт", data.len()));
+        }
+
+        Ok(Self {
+            trade_fee_rate: read_u32(data, CLMM_TRADE_FEE_RATE_OFFSET)?,
+        })
+    }
+}
+
+/// Инициализированный тик внутри TickArray
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TickState {
+    pub tick: i32,
+    pub liquidity_net: i128,
+    pub liquidity_gross: u128,
+}
+
+/// TickArray CLMM пула (хранятся только инициализированные тики)
+#[derive(Debug, Clone, PartialEq)]
+pub struct TickArrayState {
+    pub address: Pubkey,
+    pub start_tick_index: i32,
+    pub ticks: Vec<TickState>,
+}
+
+impl TickArrayState {
+    pub fn try_from_slice(address: Pubkey, data: &[u8]) -> Result<Self> {
+        if data.len() < CLMM_TICK_ARRAY_LEN {
+            return Err(anyhow!("Недостаточно данных для TickArray {}: {} байт", address, data.len()));
+        }
+
+        let start_tick_index = read_i32(data, TICK_ARRAY_START_INDEX_OFFSET)?;
+        let mut ticks = Vec::new();
+
+        for i in 0..TICK_ARRAY_SIZE as usize {
+            let base = TICK_ARRAY_TICKS_OFFSET + i * TICK_STATE_LEN;
+            let liquidity_gross = read_u128(data, base + TICK_LIQUIDITY_GROSS_OFFSET)?;
+            if liquidity_gross == 0 {
+                continue;
+            }
+
+            ticks.push(TickState {
+                tick: read_i32(data, base)?,
+                liquidity_net: read_u128(data, base + TICK_LIQUIDITY_NET_OFFSET)? as i128,
+                liquidity_gross,
+            });
+        }
+
+        Ok(Self {
+            address,
+            start_tick_index,
+            ticks,
+        })
+    }
+}
+
+
+// -------------------------------------------------------------------------
+// METEORA DLMM: LbPair и BinArray (для котировки)
+// -------------------------------------------------------------------------
+
+/// Минимальная длина LbPair, покрывающая все читаемые поля (до bin_array_bitmap)
+pub const DLMM_LB_PAIR_MIN_LEN: usize = 712;
+/// Размер аккаунта BinArray Meteora DLMM
+pub const DLMM_BIN_ARRAY_LEN: usize = 10136;
+/// Количество бинов в одном BinArray
+pub const MAX_BIN_PER_ARRAY: i32 = 70;
+
+// Офсеты LbPair: StaticParameters
+const DLMM_BASE_FACTOR_OFFSET: usize = 8;
+const DLMM_FILTER_PERIOD_OFFSET: usize = 10;
+const DLMM_DECAY_PERIOD_OFFSET: usize = 12;
+const DLMM_REDUCTION_FACTOR_OFFSET: usize = 14;
+const DLMM_VARIABLE_FEE_CONTROL_OFFSET: usize = 16;
+const DLMM_MAX_VOLATILITY_ACCUMULATOR_OFFSET: usize = 20;
+const DLMM_MIN_BIN_ID_OFFSET: usize = 24;
+const DLMM_MAX_BIN_ID_OFFSET: usize = 28;
+const DLMM_BASE_FEE_POWER_FACTOR_OFFSET: usize = 34;
+// Офсеты LbPair: VariableParameters
+const DLMM_VOLATILITY_ACCUMULATOR_OFFSET: usize = 40;
+const DLMM_VOLATILITY_REFERENCE_OFFSET: usize = 44;
+const DLMM_INDEX_REFERENCE_OFFSET: usize = 48;
+const DLMM_LAST_UPDATE_TIMESTAMP_OFFSET: usize = 56;
+// Офсеты LbPair: состояние пары
+const DLMM_ACTIVE_ID_OFFSET: usize = 76;
+const DLMM_BIN_STEP_OFFSET: usize = 80;
+const DLMM_TOKEN_X_MINT_OFFSET: usize = 88;
+const DLMM_TOKEN_Y_MINT_OFFSET: usize = 120;
+const DLMM_RESERVE_X_OFFSET: usize = 152;
+const DLMM_RESERVE_Y_OFFSET: usize = 184;
+const DLMM_ORACLE_OFFSET: usize = 552;
+const DLMM_BIN_ARRAY_BITMAP_OFFSET: usize = 584;
+
+// Офсеты BinArray и Bin
+const BIN_ARRAY_INDEX_OFFSET: usize = 8;
+const BIN_ARRAY_BINS_OFFSET: usize = 56;
+const BIN_LEN: usize = 144;
+const BIN_AMOUNT_Y_OFFSET: usize = 8;
+const BIN_PRICE_OFFSET: usize = 16;
+
+/// Пара Meteora DLMM: параметры комиссий, волатильность и активный бин
+#[derive(Debug, Clone, PartialEq)]
+pub struct DlmmLbPair {
+    pub base_factor: u16,
+    pub filter_period: u16,
+    pub decay_period: u16,
+    pub reduction_factor: u16,
+    pub variable_fee_control: u32,
+    pub max_volatility_accumulator: u32,
+    pub min_bin_id: i32,
+    pub max_bin_id: i32,
+    pub base_fee_power_factor: u8,
+    pub volatility_accumulator: u32,
+    pub volatility_reference: u32,
+    pub index_reference: i32,
+    pub last_update_timestamp: i64,
+    pub active_id: i32,
+    pub bin_step: u16,
+    pub token_x_mint: Pubkey,
+    pub token_y_mint: Pubkey,
+    pub reserve_x: Pubkey,
+    pub reserve_y: Pubkey,
+    pub oracle: Pubkey,
+    pub bin_array_bitmap: [u64; 16],
+}
+
+impl DlmmLbPair {
+    pub fn try_from_slice(data: &[u8]) -> Result<Self> {
+        if data.len() < DLMM_LB_PAIR_MIN_LEN {
+            return Err(anyhow!(
+                "Недостаточно данных для DLMM LbPair: {} байт, требуется минимум {}",
+                data.len(),
+                DLMM_LB_PAIR_MIN_LEN
+            ));
+        }
+
+        let mut bin_array_bitmap = [0u64; 16];
+        for (i, word) in bin_array_bitmap.iter_mut().enumerate() {
+            *word = read_u64(data, DLMM_BIN_ARRAY_BITMAP_OFFSET + i * 8)?;
+        }
+
+        Ok(Self {
+            base_factor: read_u16(data, DLMM_BASE_FACTOR_OFFSET)?,
+            filter_period: read_u16(data, DLMM_FILTER_PERIOD_OFFSET)?,
+            decay_period: read_u16(data, DLMM_DECAY_PERIOD_OFFSET)?,
+            reduction_factor: read_u16(data, DLMM_REDUCTION_FACTOR_OFFSET)?,
+            variable_fee_control: read_u32(data, DLMM_VARIABLE_FEE_CONTROL_OFFSET)?,
+            max_volatility_accumulator: read_u32(data, DLMM_MAX_VOLATILITY_ACCUMULATOR_OFFSET)?,
+            min_bin_id: read_i32(data, DLMM_MIN_BIN_ID_OFFSET)?,
+            max_bin_id: read_i32(data, DLMM_MAX_BIN_ID_OFFSET)?,
+            base_fee_power_factor: data[DLMM_BASE_FEE_POWER_FACTOR_OFFSET],
+            volatility_accumulator: read_u32(data, DLMM_VOLATILITY_ACCUMULATOR_OFFSET)?,
+            volatility_reference: read_u32(data, DLMM_VOLATILITY_REFERENCE_OFFSET)?,
+            index_reference: read_i32(data, DLMM_INDEX_REFERENCE_OFFSET)?,
+            last_update_timestamp: read_i64(data, DLMM_LAST_UPDATE_TIMESTAMP_OFFSET)?,
+            active_id: read_i32(data, DLMM_ACTIVE_ID_OFFSET)?,
+            bin_step: read_u16(data, DLMM_BIN_STEP_OFFSET)?,
+            token_x_mint: read_pubkey(data, DLMM_TOKEN_X_MINT_OFFSET)?,
+            token_y_mint: read_pubkey(data, DLMM_TOKEN_Y_MINT_OFFSET)?,
+            reserve_x: read_pubkey(data, DLMM_RESERVE_X_OFFSET)?,
+            reserve_y: read_pubkey(data, DLMM_RESERVE_Y_OFFSET)?,
+            oracle: read_pubkey(data, DLMM_ORACLE_OFFSET)?,
+            bin_array_bitmap,
+        })
+    }
+}
+
+/// Ликвидность одного бина DLMM
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DlmmBin {
+    pub amount_x: u64,
+    pub amount_y: u64,
+    /// Цена бина Q64.64 (0, если бин ещё не инициализирован)
+    pub price: u128,
+}
+
+/// BinArray DLMM: 70 последовательных бинов, начиная с index * 70
+#[derive(Debug, Clone, PartialEq)]
+pub struct DlmmBinArray {
+    pub address: Pubkey,
+    pub index: i64,
+    pub bins: Vec<DlmmBin>,
+}
+
+impl DlmmBinArray {
+    pub fn try_from_slice(address: Pubkey, data: &[u8]) -> Result<Self> {
+        if data.len() < DLMM_BIN_ARRAY_LEN {
+            return Err(anyhow!("Недостаточно данных для BinArray {}: {} байт", address, data.len()));
+        }
+
+        let mut bins = Vec::with_capacity(MAX_BIN_PER_ARRAY as usize);
+        for i in 0..MAX_BIN_PER_ARRAY as usize {
+            let base = BIN_ARRAY_BINS_OFFSET + i * BIN_LEN;
+            bins.push(DlmmBin {
+                amount_x: read_u64(data, base)?,
+                amount_y: read_u64(data, base + BIN_AMOUNT_Y_OFFSET)?,
+                price: read_u128(data, base + BIN_PRICE_OFFSET)?,
+            });
+        }
+
+        Ok(Self {
+            address,
+            index: read_i64(data, BIN_ARRAY_INDEX_OFFSET)?,
+            bins,
+        })
+    }
+}
+
+
 /// Чтение Pubkey из данных по офсету с проверкой границ
 pub fn read_pubkey(data: &[u8], offset: usize) -> Result<Pubkey> {
     if offset + 32 > data.len() {
@@ -213,4 +578,88 @@ pub fn read_u64(data: &[u8], offset: usize) -> Result<u64> {
     let slice = &data[offset..offset + 8];
     let bytes: [u8; 8] = slice.try_into()?;
     Ok(u64::from_le_bytes(bytes))
-}
\ No newline at end of file
+}
+
+/// Чтение массива фиксированной длины по офсету с проверкой границ
+fn read_array<const N: usize>(data: &[u8], offset: usize) -> Result<[u8; N]> {
+    if offset + N > data.len() {
+        return Err(anyhow!(
+            "Недостаточно данных по офсету {}: нужно еще {} байт, есть {}",
+            offset,
+            N,
+            data.len().saturating_sub(offset)
+        ));
+    }
+
+    Ok(data[offset..offset + N].try_into()?)
+}
+
+/// Чтение u16 (little-endian) из данных по офсету
+pub fn read_u16(data: &[u8], offset: usize) -> Result<u16> {
+    Ok(u16::from_le_bytes(read_array(data, offset)?))
+}
+
+/// Чтение u32 (little-endian) из данных по офсету
+pub fn read_u32(data: &[u8], offset: usize) -> Result<u32> {
+    Ok(u32::from_le_bytes(read_array(data, offset)?))
+}
+
+/// Чтение i32 (little-endian) из данных по офсету
+pub fn read_i32(data: &[u8], offset: usize) -> Result<i32> {
+    Ok(i32::from_le_bytes(read_array(data, offset)?))
+}
+
+/// Чтение i64 (little-endian) из данных по офсету
+pub fn read_i64(data: &[u8], offset: usize) -> Result<i64> {
+    Ok(i64::from_le_bytes(read_array(data, offset)?))
+}
+
+/// Чтение u128 (little-endian) из данных по офсету
+pub fn read_u128(data: &[u8], offset: usize) -> Result<u128> {
+    Ok(u128::from_le_bytes(read_array(data, offset)?))
+}
+
+#[cfg(test)]
+fn write_bytes(data: &mut [u8], offset: usize, bytes: &[u8]) {
+    data[offset..offset + bytes.len()].copy_from_slice(bytes);
+}
+
+#[cfg(test)]
+impl ClmmPoolState {
+    /// Аккаунт PoolState с полями по офсетам try_from_slice (для тестов котировки)
+    pub fn to_account_data(&self) -> Vec<u8> {
+        let mut data = vec![0u8; CLMM_POOL_STATE_LEN];
+        write_bytes(&mut data, CLMM_AMM_CONFIG_OFFSET, self.amm_config.as_ref());
+        write_bytes(&mut data, CLMM_MINT_0_OFFSET, self.token_mint_0.as_ref());
+        write_bytes(&mut data, CLMM_MINT_1_OFFSET, self.token_mint_1.as_ref());
+        write_bytes(&mut data, CLMM_VAULT_0_OFFSET, self.token_vault_0.as_ref());
+        write_bytes(&mut data, CLMM_VAULT_1_OFFSET, self.token_vault_1.as_ref());
+        write_bytes(&mut data, CLMM_OBSERVATION_OFFSET, self.observation_key.as_ref());
+        data[CLMM_DECIMALS_0_OFFSET] = self.mint_decimals_0;
+        data[CLMM_DECIMALS_1_OFFSET] = self.mint_decimals_1;
+        write_bytes(&mut data, CLMM_TICK_SPACING_OFFSET, &self.tick_spacing.to_le_bytes());
+        write_bytes(&mut data, CLMM_LIQUIDITY_OFFSET, &self.liquidity.to_le_bytes());
+        write_bytes(&mut data, CLMM_SQRT_PRICE_OFFSET, &self.sqrt_price_x64.to_le_bytes());
+        write_bytes(&mut data, CLMM_TICK_CURRENT_OFFSET, &self.tick_current.to_le_bytes());
+        for (i, word) in self.tick_array_bitmap.iter().enumerate() {
+            write_bytes(&mut data, CLMM_TICK_ARRAY_BITMAP_OFFSET + i * 8, &word.to_le_bytes());
+        }
+        data
+    }
+}
+
+#[cfg(test)]
+impl TickArrayState {
+    /// Аккаунт TickArray: инициализированные тики по порядку в первых слотах
+    pub fn to_account_data(&self) -> Vec<u8> {
+        let mut data = vec![0u8; CLMM_TICK_ARRAY_LEN];
+        write_bytes(&mut data, TICK_ARRAY_START_INDEX_OFFSET, &self.start_tick_index.to_le_bytes());
+        for (i, tick) in self.ticks.iter().enumerate() {
+            let base = TICK_ARRAY_TICKS_OFFSET + i * TICK_STATE_LEN;
+            write_bytes(&mut data, base, &tick.tick.to_le_bytes());
+            write_bytes(&mut data, base + TICK_LIQUIDITY_NET_OFFSET, &tick.liquidity_net.to_le_bytes());
+            write_bytes(&mut data, base + TICK_LIQUIDITY_GROSS_OFFSET, &tick.liquidity_gross.to_le_bytes());
+        }
+        data
+    }
+}
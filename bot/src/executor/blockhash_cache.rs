@@ -0,0 +1,158 @@
+// bot/src/executor/blockhash_cache.rs
+// Кэш последнего блокхеша, высоты блока и слота. Обновляется фоновой задачей,
+// сборка транзакций читает его без обращения к RPC
+
+use anyhow::{Context, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::hash::Hash;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{debug, warn};
+
+/// Запас блоков до истечения: транзакция должна успеть дойти до лидера
+const EXPIRY_MARGIN_BLOCKS: u64 = 10;
+/// Кэш без обновлений дольше этого времени не используется для новых транзакций
+const MAX_STALENESS: Duration = Duration::from_secs(10);
+
+/// Блокхеш и высота блока, до которой принимаются транзакции с ним
+#[derive(Debug, Clone, Copy)]
+pub struct RecentBlockhash {
+    pub blockhash: Hash,
+    pub last_valid_block_height: u64,
+}
+
+#[derive(Debug)]
+struct ChainState {
+    latest: RecentBlockhash,
+    slot: u64,
+    block_height: u64,
+    updated_at: Instant,
+    /// Полученные блокхеши, ещё не истёкшие: по ним проверяются собранные транзакции
+    known: HashMap<Hash, u64>,
+}
+
+pub struct BlockhashCache {
+    rpc_client: Arc<RpcClient>,
+    state: RwLock<ChainState>,
+}
+
+impl BlockhashCache {
+    /// Создание с первой загрузкой: без блокхеша транзакции не собираются
+    pub async fn new(rpc_client: Arc<RpcClient>) -> Result<Self> {
+        let (latest, slot, block_height) = Self::fetch(&rpc_client).await?;
+
+        Ok(Self {
+            rpc_client,
+            state: RwLock::new(ChainState {
+                latest,
+                slot,
+                block_height,
+                updated_at: Instant::now(),
+                known: HashMap::from([(latest.blockhash, latest.last_valid_block_height)]),
+            }),
+        })
+    }
+
+    /// Последний блокхеш. Ошибка, если фоновое обновление давно не проходило
+    pub fn latest(&self) -> Result<RecentBlockhash> {
+        let state = self.state.read().map_err(|_| anyhow::anyhow!("Кэш блокхеша отравлен"))?;
+
+        let age = state.updated_at.elapsed();
+        if age > MAX_STALENESS {
+            anyhow::bail!("Кэш блокхеша не обновлялся {:.1}s", age.as_secs_f64());
+        }
+        if is_expired(state.block_height, state.latest.last_valid_block_height) {
+            anyhow::bail!("Последний блокхеш {} истекает", state.latest.blockhash);
+        }
+
+        Ok(state.latest)
+    }
+
+    /// Текущий слот на момент последнего обновления
+    pub fn slot(&self) -> u64 {
+        self.state.read().map(|state| state.slot).unwrap_or_default()
+    }
+
+    /// Истёк ли блокхеш (с запасом EXPIRY_MARGIN_BLOCKS).
+    /// Неизвестный кэшу блокхеш считается истёкшим
+    pub fn is_expired(&self, blockhash: &Hash) -> bool {
+        let Ok(state) = self.state.read() else {
+            return true;
+        };
+        state.known.get(blockhash)
+            .map_or(true, |&last_valid| is_expired(state.block_height, last_valid))
+    }
+
+    /// Транзакция с этим блокхешем уже не может попасть в блок (без запаса)
+    pub fn is_invalid(&self, blockhash: &Hash) -> bool {
+        let Ok(state) = self.state.read() else {
+            return true;
+        };
+        state.known.get(blockhash)
+            .map_or(true, |&last_valid| state.block_height > last_valid)
+    }
+
+    async fn refresh(&self) -> Result<()> {
+        let (latest, slot, block_height) = Self::fetch(&self.rpc_client).await?;
+
+        let mut state = self.state.write().map_err(|_| anyhow::anyhow!("Кэш блокхеша отравлен"))?;
+        state.known.insert(latest.blockhash, latest.last_valid_block_height);
+        state.known.retain(|_, last_valid| *last_valid >= block_height);
+        state.latest = latest;
+        state.slot = slot;
+        state.block_height = block_height;
+        state.updated_at = Instant::now();
+
+        debug!("🧱 Блокхеш {} (действителен до {}), слот {}, высота {}",
+               latest.blockhash, latest.last_valid_block_height, slot, block_height);
+        Ok(())
+    }
+
+    /// Блокхеш и эпоха запрашиваются параллельно
+    async fn fetch(rpc_client: &RpcClient) -> Result<(RecentBlockhash, u64, u64)> {
+        let ((blockhash, last_valid_block_height), epoch_info) = tokio::try_join!(
+            rpc_client.get_latest_blockhash_with_commitment(rpc_client.commitment()),
+            rpc_client.get_epoch_info(),
+        ).context("RPC getLatestBlockhash/getEpochInfo")?;
+
+        Ok((
+            RecentBlockhash { blockhash, last_valid_block_height },
+            epoch_info.absolute_slot,
+            epoch_info.block_height,
+        ))
+    }
+}
+
+fn is_expired(block_height: u64, last_valid_block_height: u64) -> bool {
+    block_height + EXPIRY_MARGIN_BLOCKS > last_valid_block_height
+}
+
+/// Фоновое обновление кэша блокхеша и слота
+pub async fn start_blockhash_refresh_task(cache: Arc<BlockhashCache>, period: Duration) {
+    let mut refresh_interval = interval(period);
+    refresh_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        refresh_interval.tick().await;
+
+        if let Err(e) = cache.refresh().await {
+            warn!("⚠️ Ошибка обновления блокхеша: {:#}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blockhash_expires_before_last_valid_height() {
+        assert!(!is_expired(1_000, 1_150));
+        assert!(!is_expired(1_140, 1_150));
+        // Внутри запаса транзакция может не успеть дойти до лидера
+        assert!(is_expired(1_141, 1_150));
+        assert!(is_expired(1_200, 1_150));
+    }
+}
@@ -0,0 +1,168 @@
+// bot/src/executor/fee_market.rs
+// Compute budget транзакции: лимит CU по симуляции с запасом,
+// цена CU по рынку приоритетных комиссий для записываемых аккаунтов
+
+use anyhow::{Context, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tracing::{debug, warn};
+
+use crate::config::TradingConfig;
+
+/// Максимальный лимит CU транзакции: с ним выполняется симуляция
+pub const SIMULATION_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+/// Базовая комиссия за подпись (lamports)
+const LAMPORTS_PER_SIGNATURE: u64 = 5_000;
+/// Лимит аккаунтов в запросе getRecentPrioritizationFees
+const MAX_FEE_ACCOUNTS: usize = 128;
+
+/// Лимит и цена CU для инструкций ComputeBudget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ComputeBudget {
+    pub unit_limit: u32,
+    /// Цена CU (micro-lamports)
+    pub unit_price: u64,
+}
+
+impl ComputeBudget {
+    /// Бюджет для симуляции: максимальный лимит, цена не влияет на потребление
+    pub fn simulation() -> Self {
+        Self { unit_limit: SIMULATION_COMPUTE_UNIT_LIMIT, unit_price: 0 }
+    }
+
+    /// Приоритетная комиссия в lamports
+    pub fn priority_fee_lamports(&self) -> u64 {
+        (self.unit_limit as u128 * self.unit_price as u128).div_ceil(1_000_000) as u64
+    }
+}
+
+pub struct FeeMarket {
+    rpc_client: Arc<RpcClient>,
+    /// Запас к потреблению CU по симуляции (1.15 = +15%)
+    compute_unit_margin: f64,
+    /// Перцентиль недавних приоритетных комиссий
+    percentile: u8,
+    /// Доля ожидаемой прибыли, которую могут занять комиссии
+    max_fee_profit_share: f64,
+    /// Цена CU, если рынок комиссий недоступен
+    fallback_unit_price: u64,
+}
+
+impl FeeMarket {
+    pub fn new(rpc_client: Arc<RpcClient>, trading: &TradingConfig) -> Self {
+        Self {
+            rpc_client,
+            compute_unit_margin: trading.compute_unit_margin,
+            percentile: trading.priority_fee_percentile,
+            max_fee_profit_share: trading.max_fee_profit_share,
+            fallback_unit_price: trading.priority_fee_micro_lamports,
+        }
+    }
+
+    /// Compute budget по потреблению CU из симуляции и рынку комиссий.
+    /// Цена ограничена так, чтобы все комиссии не превышали долю ожидаемой прибыли
+    pub async fn compute_budget(
+        &self,
+        instructions: &[Instruction],
+        units_consumed: u64,
+        expected_profit_lamports: i64,
+    ) -> ComputeBudget {
+        let unit_limit = compute_unit_limit(units_consumed, self.compute_unit_margin);
+
+        let market_price = match self.recent_fee_percentile(instructions).await {
+            Ok(Some(price)) => price,
+            Ok(None) => self.fallback_unit_price,
+            Err(e) => {
+                warn!("⚠️ Рынок приоритетных комиссий недоступен: {:#}", e);
+                self.fallback_unit_price
+            }
+        };
+
+        let max_fee = (expected_profit_lamports.max(0) as f64 * self.max_fee_profit_share) as u64;
+        let unit_price = cap_unit_price(market_price, unit_limit, max_fee);
+        if unit_price < market_price {
+            debug!("   💸 Цена CU ограничена прибылью: {} -> {} µlamports/CU", market_price, unit_price);
+        }
+
+        ComputeBudget { unit_limit, unit_price }
+    }
+
+    /// Перцентиль приоритетных комиссий последних слотов для записываемых аккаунтов.
+    /// None — данных нет (ни одной транзакции с этими аккаунтами)
+    async fn recent_fee_percentile(&self, instructions: &[Instruction]) -> Result<Option<u64>> {
+        let writable: Vec<Pubkey> = instructions.iter()
+            .flat_map(|ix| ix.accounts.iter())
+            .filter(|meta| meta.is_writable)
+            .map(|meta| meta.pubkey)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .take(MAX_FEE_ACCOUNTS)
+            .collect();
+
+        let fees: Vec<u64> = self.rpc_client.get_recent_prioritization_fees(&writable)
+            .await
+            .context("RPC getRecentPrioritizationFees")?
+            .into_iter()
+            .map(|fee| fee.prioritization_fee)
+            .collect();
+
+        Ok(percentile(fees, self.percentile))
+    }
+}
+
+/// Лимит CU: потребление × запас, не выше лимита транзакции
+pub fn compute_unit_limit(units_consumed: u64, margin: f64) -> u32 {
+    let limit = (units_consumed as f64 * margin.max(1.0)).ceil() as u64;
+    limit.clamp(1, SIMULATION_COMPUTE_UNIT_LIMIT as u64) as u32
+}
+
+/// Перцентиль (nearest-rank); None для пустой выборки
+pub fn percentile(mut values: Vec<u64>, percentile: u8) -> Option<u64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_unstable();
+    let rank = (percentile.min(100) as usize * values.len()).div_ceil(100);
+    Some(values[rank.saturating_sub(1)])
+}
+
+/// Цена CU, при которой базовая и приоритетная комиссии укладываются в max_fee_lamports
+pub fn cap_unit_price(unit_price: u64, unit_limit: u32, max_fee_lamports: u64) -> u64 {
+    let priority_budget = max_fee_lamports.saturating_sub(LAMPORTS_PER_SIGNATURE) as u128;
+    let max_price = priority_budget * 1_000_000 / unit_limit.max(1) as u128;
+    unit_price.min(max_price.min(u64::MAX as u128) as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_unit_limit_applies_margin_and_cap() {
+        assert_eq!(compute_unit_limit(100_000, 1.15), 115_000);
+        assert_eq!(compute_unit_limit(100_000, 0.5), 100_000);
+        assert_eq!(compute_unit_limit(1_300_000, 1.2), SIMULATION_COMPUTE_UNIT_LIMIT);
+    }
+
+    #[test]
+    fn test_percentile_nearest_rank() {
+        assert_eq!(percentile(vec![], 75), None);
+        assert_eq!(percentile(vec![40, 10, 30, 20], 75), Some(30));
+        assert_eq!(percentile(vec![40, 10, 30, 20], 100), Some(40));
+        assert_eq!(percentile(vec![40, 10, 30, 20], 0), Some(10));
+    }
+
+    #[test]
+    fn test_unit_price_capped_by_profit_share() {
+        // 200_000 CU, комиссии до 25_000 lamports: на приоритет остаётся 20_000
+        assert_eq!(cap_unit_price(1_000_000, 200_000, 25_000), 100_000);
+        assert_eq!(cap_unit_price(50_000, 200_000, 25_000), 50_000);
+        // Прибыль не покрывает даже базовую комиссию
+        assert_eq!(cap_unit_price(50_000, 200_000, 1_000), 0);
+
+        let budget = ComputeBudget { unit_limit: 200_000, unit_price: 100_000 };
+        assert_eq!(budget.priority_fee_lamports(), 20_000);
+    }
+}
@@ -1,115 +1,101 @@
 // bot/src/executor/jito_client.rs
 // Клиент для интеграции с Jito Block Engine (MEV защита)
 
-// bot/src/executor/jito_client.rs
-
 use anyhow::{Result, Context};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use solana_sdk::{
+    instruction::Instruction,
     pubkey::Pubkey,
-    transaction::Transaction,
-    // УДАЛИТЕ эту строку: signature::Signature,
+    transaction::VersionedTransaction,
 };
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::str::FromStr;
+use serde_json::{json, Value};
+use std::fmt;
+use std::time::{Duration, Instant};
 use tracing::{info, warn, debug};
 
-// УДАЛИТЕ эту строку - она дублирует импорт:
-// use solana_sdk::pubkey::Pubkey;
+use crate::config::JitoConfig;
 
-/// Конфигурация Jito
-pub struct JitoConfig {
-    pub block_engine_url: String,
-    pub tip_account: Pubkey,
-    pub tip_lamports: u64,
+/// Интервал опроса статуса bundle
+const BUNDLE_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Block engine явно отклонил запрос: JSON-RPC ошибка или HTTP 4xx.
+/// Для sendBundle это значит, что bundle не принят и в блок не попадёт.
+/// Сбой транспорта, 5xx и неразборчивый ответ так не классифицируются:
+/// bundle при них мог быть принят
+#[derive(Debug)]
+pub struct BundleRejected {
+    pub reason: String,
 }
 
-impl Default for JitoConfig {
-    fn default() -> Self {
-        Self {
-            block_engine_url: "https://mainnet.block-engine.jito.wtf".to_string(),
-            tip_account: Pubkey::from_str("96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5")
-                .expect("Invalid Jito tip account"),
-            tip_lamports: 300_000, // 0.0003 SOL минимальный tip
-        }
+impl fmt::Display for BundleRejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Jito отклонил запрос: {}", self.reason)
     }
 }
 
+impl std::error::Error for BundleRejected {}
+
 /// Клиент Jito Block Engine
 pub struct JitoClient {
-    config: JitoConfig,
+    block_engine_url: String,
+    tip_account: Pubkey,
+    tip_lamports: u64,
     http_client: Client,
 }
 
 impl JitoClient {
-    pub fn new(config: JitoConfig) -> Self {
-        Self {
-            config,
+    pub fn new(config: &JitoConfig) -> Result<Self> {
+        Ok(Self {
+            block_engine_url: config.block_engine_url.trim_end_matches('/').to_string(),
+            tip_account: config.tip_account.to_pubkey().context("Invalid JITO_TIP_ACCOUNT")?,
+            tip_lamports: config.tip_lamports,
             http_client: Client::new(),
-        }
+        })
     }
 
-    /// Отправка bundle транзакций в Jito
+    /// Tip-перевод от плательщика: кладётся в саму транзакцию арбитража,
+    /// поэтому платится только вместе с успешным исполнением
+    pub fn tip_instruction(&self, payer: &Pubkey) -> Instruction {
+        create_tip_instruction(payer, &self.tip_account, self.tip_lamports)
+    }
+
+    /// Отправка bundle транзакций в Jito.
+    /// Ошибка BundleRejected — bundle точно не принят; любая другая ошибка
+    /// не исключает, что block engine его получил
     ///
     /// ВАЖНО: Jito Block Engine недоступен на devnet!
     /// Эта функция работает только на mainnet.
-    pub async fn send_bundle(&self, transactions: Vec<Transaction>) -> Result<String> {
+    pub async fn send_bundle(&self, transactions: &[VersionedTransaction]) -> Result<String> {
         // Проверка devnet
-        if self.config.block_engine_url.contains("devnet") {
+        if self.block_engine_url.contains("devnet") {
             warn!("⚠️  Jito Block Engine недоступен на devnet. Используйте обычную отправку.");
-            anyhow::bail!("Jito не поддерживается на devnet");
+            return Err(BundleRejected { reason: "Jito не поддерживается на devnet".to_string() }.into());
         }
 
         info!("📦 Отправка bundle из {} транзакций в Jito...", transactions.len());
 
-        // Сериализация транзакций в base64
-        let encoded_txs: Vec<String> = transactions
+        // Сериализация транзакций в base64 (кодировка указывается явно: по умолчанию block engine ждёт base58)
+        let encoded_txs = transactions
             .iter()
-            .map(|tx| {
-                let serialized = bincode::serialize(tx).expect("Failed to serialize tx");
-                bs58::encode(serialized).into_string()
-            })
-            .collect();
+            .map(|tx| Ok(BASE64.encode(bincode::serialize(tx).context("Сериализация транзакции")?)))
+            .collect::<Result<Vec<String>>>()?;
 
         debug!("   Сериализовано {} транзакций", encoded_txs.len());
 
         // Подготовка JSON-RPC запроса
-        let request = SendBundleRequest {
-            jsonrpc: "2.0".to_string(),
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0",
             id: 1,
-            method: "sendBundle".to_string(),
-            params: vec![encoded_txs],
+            method: "sendBundle",
+            params: json!([encoded_txs, { "encoding": "base64" }]),
         };
 
-        // Отправка POST запроса к Jito API
-        let endpoint = format!("{}/api/v1/bundles", self.config.block_engine_url);
-        debug!("   Endpoint: {}", endpoint);
-
-        let response = self.http_client
-            .post(&endpoint)
-            .json(&request)
-            .send()
-            .await
+        let response: JsonRpcResponse<String> = self.call(&request).await
             .context("Не удалось отправить bundle в Jito")?;
 
-        // Проверка статуса ответа
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-            anyhow::bail!("Jito вернул ошибку {}: {}", status, error_text);
-        }
-
-        // Парсинг ответа
-        let bundle_response: SendBundleResponse = response
-            .json()
-            .await
-            .context("Не удалось распарсить ответ Jito")?;
-
-        if let Some(error) = bundle_response.error {
-            anyhow::bail!("Jito RPC ошибка: {:?}", error);
-        }
-
-        let bundle_id = bundle_response.result
+        let bundle_id = response.into_result()?
             .ok_or_else(|| anyhow::anyhow!("Нет bundle_id в ответе Jito"))?;
 
         info!("   ✅ Bundle отправлен, ID: {}", bundle_id);
@@ -117,74 +103,81 @@ impl JitoClient {
         Ok(bundle_id)
     }
 
-    /// Проверка статуса bundle
-    pub async fn get_bundle_status(&self, bundle_id: &str) -> Result<BundleStatus> {
-        let request = GetBundleStatusRequest {
-            jsonrpc: "2.0".to_string(),
+    /// Проверка статуса bundle. None — block engine ещё не знает о bundle
+    pub async fn get_bundle_status(&self, bundle_id: &str) -> Result<Option<BundleStatus>> {
+        let request = JsonRpcRequest {
+            jsonrpc: "2.0",
             id: 1,
-            method: "getBundleStatuses".to_string(),
-            params: vec![vec![bundle_id.to_string()]],
+            method: "getBundleStatuses",
+            params: json!([[bundle_id]]),
         };
 
-        let endpoint = format!("{}/api/v1/bundles", self.config.block_engine_url);
-
-        let response = self.http_client
-            .post(&endpoint)
-            .json(&request)
-            .send()
-            .await
+        let response: JsonRpcResponse<BundleStatusResult> = self.call(&request).await
             .context("Не удалось получить статус bundle")?;
 
-        let status_response: GetBundleStatusResponse = response
-            .json()
-            .await
-            .context("Не удалось распарсить статус bundle")?;
-
-        let statuses = status_response.result.value
-            .ok_or_else(|| anyhow::anyhow!("Нет статусов в ответе"))?;
-
-        let status = statuses.first()
-            .ok_or_else(|| anyhow::anyhow!("Пустой массив статусов"))?;
-
-        Ok(status.clone())
+        Ok(response.into_result()?
+            .and_then(|result| result.value.into_iter().flatten().next()))
     }
 
-    /// Ожидание подтверждения bundle (с таймаутом)
+    /// Ожидание подтверждения bundle.
+    /// Ok(None) — bundle не подтверждён за timeout; ошибка — bundle провалился.
+    /// Ошибка опроса статуса не прерывает ожидание: bundle уже отправлен
+    /// и может попасть в блок
     pub async fn wait_for_confirmation(
         &self,
         bundle_id: &str,
-        timeout_seconds: u64,
-    ) -> Result<BundleStatus> {
-        use tokio::time::{sleep, Duration};
-
-        let start = std::time::Instant::now();
-        let timeout = Duration::from_secs(timeout_seconds);
-
-        loop {
-            if start.elapsed() > timeout {
-                anyhow::bail!("Таймаут ожидания подтверждения bundle");
-            }
-
-            let status = self.get_bundle_status(bundle_id).await?;
-
-            match status.confirmation_status.as_str() {
-                "confirmed" | "finalized" => {
-                    info!("   ✅ Bundle подтверждён: {}", status.confirmation_status);
-                    return Ok(status);
+        timeout: Duration,
+    ) -> Result<Option<BundleStatus>> {
+        let start = Instant::now();
+
+        while start.elapsed() < timeout {
+            match self.get_bundle_status(bundle_id).await {
+                Err(e) => {
+                    warn!("⚠️ Ошибка опроса статуса bundle {}: {:#}", bundle_id, e);
+                }
+                Ok(Some(status)) if !status.is_ok() => {
+                    anyhow::bail!("Bundle провалился: {}", status.err);
                 }
-                "failed" => {
-                    anyhow::bail!("Bundle провалился: {:?}", status.err);
+                Ok(Some(status)) if matches!(status.confirmation_status.as_str(), "confirmed" | "finalized") => {
+                    info!("   ✅ Bundle подтверждён: {} (слот {:?})", status.confirmation_status, status.slot);
+                    return Ok(Some(status));
                 }
-                "pending" => {
-                    debug!("   Bundle в ожидании... ({}s)", start.elapsed().as_secs());
-                    sleep(Duration::from_millis(500)).await;
+                Ok(Some(status)) => {
+                    debug!("   Bundle {}... ({}ms)", status.confirmation_status, start.elapsed().as_millis());
                 }
-                _ => {
-                    debug!("   Неизвестный статус: {}", status.confirmation_status);
-                    sleep(Duration::from_millis(500)).await;
+                Ok(None) => {
+                    debug!("   Bundle в ожидании... ({}ms)", start.elapsed().as_millis());
                 }
             }
+
+            tokio::time::sleep(BUNDLE_POLL_INTERVAL.min(timeout.saturating_sub(start.elapsed()))).await;
+        }
+
+        warn!("⏱️ Bundle {} не подтверждён за {:?}", bundle_id, timeout);
+        Ok(None)
+    }
+
+    async fn call<T: for<'de> Deserialize<'de>>(&self, request: &JsonRpcRequest) -> Result<JsonRpcResponse<T>> {
+        let endpoint = format!("{}/api/v1/bundles", self.block_engine_url);
+        debug!("   Endpoint: {} ({})", endpoint, request.method);
+
+        let response = self.http_client
+            .post(&endpoint)
+            .json(request)
+            .send()
+            .await?;
+
+        // Проверка статуса ответа
+        let status = response.status();
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            if status.is_client_error() {
+                return Err(BundleRejected { reason: format!("{}: {}", status, error_text) }.into());
+            }
+            anyhow::bail!("Jito вернул ошибку {}: {}", status, error_text);
         }
+
+        response.json().await.context("Не удалось распарсить ответ Jito")
     }
 }
 
@@ -193,48 +186,52 @@ impl JitoClient {
 // ============================================================================
 
 #[derive(Serialize)]
-struct SendBundleRequest {
-    jsonrpc: String,
+struct JsonRpcRequest {
+    jsonrpc: &'static str,
     id: u64,
-    method: String,
-    params: Vec<Vec<String>>,
+    method: &'static str,
+    params: Value,
 }
 
 #[derive(Deserialize)]
-struct SendBundleResponse {
-    result: Option<String>,
+struct JsonRpcResponse<T> {
+    result: Option<T>,
     error: Option<JsonRpcError>,
 }
 
-#[derive(Serialize)]
-struct GetBundleStatusRequest {
-    jsonrpc: String,
-    id: u64,
-    method: String,
-    params: Vec<Vec<String>>,
-}
-
-#[derive(Deserialize)]
-struct GetBundleStatusResponse {
-    result: BundleStatusResult,
+impl<T> JsonRpcResponse<T> {
+    fn into_result(self) -> Result<Option<T>> {
+        if let Some(error) = self.error {
+            return Err(BundleRejected { reason: format!("RPC ошибка {}: {}", error.code, error.message) }.into());
+        }
+        Ok(self.result)
+    }
 }
 
 #[derive(Deserialize)]
 struct BundleStatusResult {
-    value: Option<Vec<BundleStatus>>,
+    value: Vec<Option<BundleStatus>>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct BundleStatus {
     pub bundle_id: String,
     pub confirmation_status: String,
-    pub err: Option<String>,
+    /// Результат исполнения: {"Ok": null} при успехе
+    #[serde(default)]
+    pub err: Value,
     pub slot: Option<u64>,
 }
 
+impl BundleStatus {
+    pub fn is_ok(&self) -> bool {
+        self.err.is_null() || self.err.get("Ok").is_some()
+    }
+}
+
 #[derive(Debug, Deserialize)]
 struct JsonRpcError {
-    code: i32,
+    code: i64,
     message: String,
 }
 
@@ -242,11 +239,193 @@ struct JsonRpcError {
 // ВСПОМОГАТЕЛЬНЫЕ ФУНКЦИИ
 // ============================================================================
 
-/// Создание tip транзакции для Jito
+/// Создание tip инструкции для Jito
 pub fn create_tip_instruction(
     from: &Pubkey,
     tip_account: &Pubkey,
     lamports: u64,
-) -> solana_sdk::instruction::Instruction {
+) -> Instruction {
     solana_sdk::system_instruction::transfer(from, tip_account, lamports)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{JitoFallback, PubkeyString};
+    use solana_sdk::{signature::{Keypair, Signer}, transaction::Transaction};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::sync::mpsc;
+
+    /// Локальная замена block engine: отвечает заготовленными JSON-RPC ответами
+    /// по очереди (последний повторяется) и отдаёт тела запросов в канал
+    async fn block_engine(responses: Vec<Value>) -> (String, mpsc::UnboundedReceiver<Value>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (requests_tx, requests_rx) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            let mut served = 0;
+            loop {
+                let Ok((mut stream, _)) = listener.accept().await else {
+                    return;
+                };
+                while let Some(body) = read_request(&mut stream).await {
+                    let _ = requests_tx.send(body);
+                    let response = responses[served.min(responses.len() - 1)].to_string();
+                    served += 1;
+
+                    let http = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+                        response.len(), response
+                    );
+                    if stream.write_all(http.as_bytes()).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        (url, requests_rx)
+    }
+
+    async fn read_request(stream: &mut TcpStream) -> Option<Value> {
+        let mut buffer = Vec::new();
+        let mut chunk = [0u8; 4096];
+
+        let header_end = loop {
+            if let Some(pos) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+            let read = stream.read(&mut chunk).await.ok()?;
+            if read == 0 {
+                return None;
+            }
+            buffer.extend_from_slice(&chunk[..read]);
+        };
+
+        let headers = String::from_utf8_lossy(&buffer[..header_end]).to_ascii_lowercase();
+        let content_length: usize = headers.lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(0);
+
+        while buffer.len() < header_end + content_length {
+            let read = stream.read(&mut chunk).await.ok()?;
+            if read == 0 {
+                return None;
+            }
+            buffer.extend_from_slice(&chunk[..read]);
+        }
+
+        serde_json::from_slice(&buffer[header_end..header_end + content_length]).ok()
+    }
+
+    fn client(url: &str) -> JitoClient {
+        JitoClient::new(&JitoConfig {
+            block_engine_url: url.to_string(),
+            tip_account: PubkeyString("96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5".to_string()),
+            tip_lamports: 10_000,
+            confirm_timeout_secs: 1,
+            fallback: JitoFallback::OnError,
+        }).unwrap()
+    }
+
+    fn status(confirmation_status: &str, err: Value) -> Value {
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "context": { "slot": 100 },
+                "value": [{
+                    "bundle_id": "bundle",
+                    "transactions": [],
+                    "slot": 99,
+                    "confirmation_status": confirmation_status,
+                    "err": err,
+                }],
+            },
+        })
+    }
+
+    #[tokio::test]
+    async fn test_send_bundle_uses_base64_encoding() {
+        let (url, mut requests) = block_engine(vec![json!({ "jsonrpc": "2.0", "id": 1, "result": "bundle" })]).await;
+        let jito = client(&url);
+
+        let payer = Keypair::new();
+        let tx: VersionedTransaction = Transaction::new_with_payer(
+            &[jito.tip_instruction(&payer.pubkey())],
+            Some(&payer.pubkey()),
+        ).into();
+
+        assert_eq!(jito.send_bundle(std::slice::from_ref(&tx)).await.unwrap(), "bundle");
+
+        let request = requests.recv().await.unwrap();
+        assert_eq!(request["method"], "sendBundle");
+        assert_eq!(request["params"][1]["encoding"], "base64");
+
+        let encoded = request["params"][0][0].as_str().unwrap();
+        assert_eq!(BASE64.decode(encoded).unwrap(), bincode::serialize(&tx).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_send_bundle_rejection_only_on_explicit_error() {
+        let payer = Keypair::new();
+        let tx: VersionedTransaction = Transaction::new_with_payer(&[], Some(&payer.pubkey())).into();
+        let is_rejected = |result: Result<String>| result.unwrap_err().downcast_ref::<BundleRejected>().is_some();
+
+        let (url, _requests) = block_engine(vec![
+            json!({ "jsonrpc": "2.0", "id": 1, "error": { "code": -32602, "message": "bundle contains an already processed transaction" } }),
+        ]).await;
+        assert!(is_rejected(client(&url).send_bundle(std::slice::from_ref(&tx)).await));
+
+        // Неразборчивый ответ: block engine мог принять bundle
+        let (url, _requests) = block_engine(vec![json!("ok")]).await;
+        assert!(!is_rejected(client(&url).send_bundle(std::slice::from_ref(&tx)).await));
+
+        // Соединение не установлено — тоже не явный отказ
+        let closed = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", closed.local_addr().unwrap());
+        drop(closed);
+        assert!(!is_rejected(client(&url).send_bundle(std::slice::from_ref(&tx)).await));
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_confirmation_polls_until_confirmed() {
+        let (url, _requests) = block_engine(vec![
+            json!({ "jsonrpc": "2.0", "id": 1, "result": { "context": { "slot": 100 }, "value": [null] } }),
+            status("processed", json!({ "Ok": null })),
+            status("confirmed", json!({ "Ok": null })),
+        ]).await;
+
+        let confirmed = client(&url).wait_for_confirmation("bundle", Duration::from_secs(5)).await.unwrap();
+        assert_eq!(confirmed.unwrap().confirmation_status, "confirmed");
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_confirmation_survives_status_errors() {
+        let (url, _requests) = block_engine(vec![
+            json!({ "jsonrpc": "2.0", "id": 1, "error": { "code": -32603, "message": "internal error" } }),
+            status("confirmed", json!({ "Ok": null })),
+        ]).await;
+
+        // Сбой опроса — не провал bundle: ожидание продолжается до подтверждения
+        let confirmed = client(&url).wait_for_confirmation("bundle", Duration::from_secs(5)).await.unwrap();
+        assert_eq!(confirmed.unwrap().confirmation_status, "confirmed");
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_confirmation_times_out_and_reports_failures() {
+        let (url, _requests) = block_engine(vec![
+            json!({ "jsonrpc": "2.0", "id": 1, "result": { "context": { "slot": 100 }, "value": [] } }),
+        ]).await;
+        let pending = client(&url).wait_for_confirmation("bundle", Duration::from_millis(200)).await.unwrap();
+        assert!(pending.is_none());
+
+        let (url, _requests) = block_engine(vec![
+            status("processed", json!({ "Err": { "InstructionError": [2, { "Custom": 6001 }] } })),
+        ]).await;
+        assert!(client(&url).wait_for_confirmation("bundle", Duration::from_secs(5)).await.is_err());
+    }
+}
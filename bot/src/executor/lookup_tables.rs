@@ -0,0 +1,359 @@
+// bot/src/executor/lookup_tables.rs
+// Address Lookup Tables для v0-транзакций: аккаунты, часто встречающиеся
+// в маршрутах (пулы, vault'ы, программы DEX), выносятся в таблицы,
+// и в транзакции вместо 32 байт на аккаунт остаётся 1 байт индекса
+
+use anyhow::{Context, Result};
+use dashmap::DashMap;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    address_lookup_table::{
+        instruction::{create_lookup_table, extend_lookup_table},
+        state::{AddressLookupTable, LookupTableMeta},
+        AddressLookupTableAccount,
+    },
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use tracing::{debug, info, warn};
+
+use crate::config::LookupTableConfig;
+use crate::utils::rpc::get_multiple_accounts_chunked;
+
+/// Вместимость одной таблицы (ограничение программы ALT)
+const MAX_TABLE_ADDRESSES: usize = 256;
+/// Адресов в одной транзакции extend (32 байта каждый, с запасом до 1232 байт)
+const MAX_ADDRESSES_PER_EXTEND: usize = 20;
+/// Таблица подключается к транзакции, только если покрывает хотя бы столько аккаунтов:
+/// ссылка на таблицу стоит 32 байта ключа + длины списков индексов
+const MIN_KEYS_PER_TABLE: usize = 2;
+
+/// Состояние таблицы на момент последнего обновления
+#[derive(Debug, Clone)]
+struct TableState {
+    addresses: Vec<Pubkey>,
+    /// Сколько первых адресов уже активны (добавленные в текущем слоте ещё нет)
+    active_len: usize,
+    authority: Option<Pubkey>,
+}
+
+impl TableState {
+    fn active(&self) -> &[Pubkey] {
+        &self.addresses[..self.active_len]
+    }
+}
+
+/// Сколько первых адресов таблицы активны в текущем слоте: добавленные
+/// в слоте last_extended_slot активны только со следующего слота
+fn active_len(meta: &LookupTableMeta, len: usize, current_slot: u64) -> usize {
+    if current_slot > meta.last_extended_slot {
+        len
+    } else {
+        (meta.last_extended_slot_start_index as usize).min(len)
+    }
+}
+
+pub struct LookupTableManager {
+    rpc_client: Arc<RpcClient>,
+    keypair: Arc<Keypair>,
+    config: LookupTableConfig,
+    tables: RwLock<HashMap<Pubkey, TableState>>,
+    /// Сколько построенных транзакций ссылались на аккаунт
+    usage: DashMap<Pubkey, u32>,
+}
+
+impl LookupTableManager {
+    pub fn new(rpc_client: Arc<RpcClient>, keypair: Arc<Keypair>, config: LookupTableConfig) -> Result<Self> {
+        let manager = Self {
+            rpc_client,
+            keypair,
+            config,
+            tables: RwLock::new(HashMap::new()),
+            usage: DashMap::new(),
+        };
+
+        let mut tables = manager.tables.write()
+            .map_err(|_| anyhow::anyhow!("Состояние ALT отравлено"))?;
+        for address in &manager.config.addresses {
+            // Пустое состояние до первого refresh: таблица известна, но не используется
+            tables.insert(address.to_pubkey()?, TableState {
+                addresses: Vec::new(),
+                active_len: 0,
+                authority: None,
+            });
+        }
+        drop(tables);
+
+        Ok(manager)
+    }
+
+    /// Перечитывание известных таблиц. Деактивированные таблицы отбрасываются
+    pub async fn refresh(&self, current_slot: u64) -> Result<usize> {
+        let keys: Vec<Pubkey> = self.tables.read()
+            .map_err(|_| anyhow::anyhow!("Состояние ALT отравлено"))?
+            .keys()
+            .copied()
+            .collect();
+        if keys.is_empty() {
+            return Ok(0);
+        }
+
+        let accounts = get_multiple_accounts_chunked(&self.rpc_client, &keys).await
+            .context("RPC get_multiple_accounts (ALT)")?;
+
+        let mut refreshed = HashMap::new();
+        for (key, account) in keys.iter().zip(accounts) {
+            let Some(account) = account else {
+                warn!("⚠️ Таблица ALT {} не найдена", key);
+                continue;
+            };
+
+            let table = match AddressLookupTable::deserialize(&account.data) {
+                Ok(table) => table,
+                Err(e) => {
+                    warn!("⚠️ Не удалось декодировать ALT {}: {}", key, e);
+                    continue;
+                }
+            };
+
+            if table.meta.deactivation_slot != u64::MAX {
+                warn!("⚠️ Таблица ALT {} деактивирована, исключена", key);
+                continue;
+            }
+
+            refreshed.insert(*key, TableState {
+                addresses: table.addresses.to_vec(),
+                active_len: active_len(&table.meta, table.addresses.len(), current_slot),
+                authority: table.meta.authority,
+            });
+        }
+
+        let count = refreshed.len();
+        *self.tables.write().map_err(|_| anyhow::anyhow!("Состояние ALT отравлено"))? = refreshed;
+        debug!("📇 ALT: {} таблиц", count);
+        Ok(count)
+    }
+
+    /// Выбор таблиц для инструкций: жадно берётся таблица, покрывающая больше всего
+    /// ещё не покрытых аккаунтов. Подписанты и вызываемые программы не выносятся
+    pub fn select_for(&self, instructions: &[Instruction]) -> Vec<AddressLookupTableAccount> {
+        let Ok(tables) = self.tables.read() else {
+            return Vec::new();
+        };
+
+        let mut uncovered = Self::lookup_candidates(instructions);
+        let mut selected = Vec::new();
+
+        loop {
+            // У уже выбранных таблиц покрытие нулевое — повторно они не попадут
+            let best = tables.iter()
+                .map(|(key, table)| {
+                    let covered = table.active().iter().filter(|a| uncovered.contains(*a)).count();
+                    (key, table, covered)
+                })
+                .max_by_key(|(_, _, covered)| *covered);
+
+            let Some((key, table, covered)) = best else {
+                break;
+            };
+            if covered < MIN_KEYS_PER_TABLE {
+                break;
+            }
+
+            for address in table.active() {
+                uncovered.remove(address);
+            }
+            selected.push(AddressLookupTableAccount {
+                key: *key,
+                addresses: table.active().to_vec(),
+            });
+        }
+
+        selected
+    }
+
+    /// Учёт аккаунтов построенной транзакции для последующего расширения таблиц
+    pub fn record_usage(&self, instructions: &[Instruction]) {
+        for key in Self::lookup_candidates(instructions) {
+            *self.usage.entry(key).or_default() += 1;
+        }
+    }
+
+    /// Обновление таблиц и добавление часто используемых аккаунтов, которых в них нет.
+    /// Новая таблица создаётся, когда в своих таблицах не осталось места.
+    /// Добавленные адреса используются со следующего слота (после refresh).
+    /// Возвращает количество добавленных адресов
+    pub async fn maintain(&self, current_slot: u64) -> Result<usize> {
+        self.refresh(current_slot).await?;
+
+        if !self.config.auto_extend {
+            return Ok(0);
+        }
+
+        let mut pending: Vec<Pubkey> = {
+            let tables = self.tables.read().map_err(|_| anyhow::anyhow!("Состояние ALT отравлено"))?;
+            let known: HashSet<&Pubkey> = tables.values().flat_map(|t| t.addresses.iter()).collect();
+
+            let mut frequent: Vec<(Pubkey, u32)> = self.usage.iter()
+                .filter(|entry| *entry.value() >= self.config.min_usage && !known.contains(entry.key()))
+                .map(|entry| (*entry.key(), *entry.value()))
+                .collect();
+            frequent.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+            frequent.into_iter().map(|(key, _)| key).collect()
+        };
+
+        if pending.is_empty() {
+            return Ok(0);
+        }
+
+        let payer = self.keypair.pubkey();
+        let mut added = 0;
+
+        while !pending.is_empty() {
+            let table = match self.owned_table_with_space()? {
+                Some(table) => table,
+                None => self.create_table(current_slot).await?,
+            };
+
+            let space = MAX_TABLE_ADDRESSES - self.table_len(&table)?;
+            let batch: Vec<Pubkey> = pending.drain(..pending.len().min(space).min(MAX_ADDRESSES_PER_EXTEND)).collect();
+
+            let ix = extend_lookup_table(table, payer, Some(payer), batch.clone());
+            self.send(&[ix]).await.with_context(|| format!("Расширение ALT {}", table))?;
+
+            if let Some(state) = self.tables.write()
+                .map_err(|_| anyhow::anyhow!("Состояние ALT отравлено"))?
+                .get_mut(&table)
+            {
+                // Адреса не активны до следующего refresh в более позднем слоте
+                state.addresses.extend(&batch);
+            }
+
+            added += batch.len();
+            info!("📇 ALT {}: +{} адресов", table, batch.len());
+        }
+
+        Ok(added)
+    }
+
+    /// Аккаунты инструкций, которые можно вынести в таблицу
+    fn lookup_candidates(instructions: &[Instruction]) -> HashSet<Pubkey> {
+        let programs: HashSet<Pubkey> = instructions.iter().map(|ix| ix.program_id).collect();
+
+        instructions.iter()
+            .flat_map(|ix| ix.accounts.iter())
+            .filter(|meta| !meta.is_signer && !programs.contains(&meta.pubkey))
+            .map(|meta| meta.pubkey)
+            .collect()
+    }
+
+    fn owned_table_with_space(&self) -> Result<Option<Pubkey>> {
+        let owner = self.keypair.pubkey();
+        let tables = self.tables.read().map_err(|_| anyhow::anyhow!("Состояние ALT отравлено"))?;
+
+        Ok(tables.iter()
+            .filter(|(_, table)| table.authority == Some(owner) && table.addresses.len() < MAX_TABLE_ADDRESSES)
+            .map(|(key, _)| *key)
+            .next())
+    }
+
+    fn table_len(&self, table: &Pubkey) -> Result<usize> {
+        let tables = self.tables.read().map_err(|_| anyhow::anyhow!("Состояние ALT отравлено"))?;
+        Ok(tables.get(table).map_or(0, |state| state.addresses.len()))
+    }
+
+    async fn create_table(&self, current_slot: u64) -> Result<Pubkey> {
+        let payer = self.keypair.pubkey();
+        // recent_slot должен быть в SlotHashes: берётся слот на шаг раньше текущего
+        let (ix, table) = create_lookup_table(payer, payer, current_slot.saturating_sub(1));
+        self.send(&[ix]).await.context("Создание ALT")?;
+
+        info!("📇 Создана таблица ALT {} (добавьте в ALT_ADDRESSES, чтобы переиспользовать)", table);
+
+        self.tables.write()
+            .map_err(|_| anyhow::anyhow!("Состояние ALT отравлено"))?
+            .insert(table, TableState { addresses: Vec::new(), active_len: 0, authority: Some(payer) });
+        Ok(table)
+    }
+
+    async fn send(&self, instructions: &[Instruction]) -> Result<()> {
+        let blockhash = self.rpc_client.get_latest_blockhash().await?;
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.keypair.pubkey()),
+            &[self.keypair.as_ref()],
+            blockhash,
+        );
+        self.rpc_client.send_and_confirm_transaction(&tx).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::AccountMeta;
+
+    fn manager(tables: Vec<(Pubkey, TableState)>) -> LookupTableManager {
+        let manager = LookupTableManager::new(
+            Arc::new(RpcClient::new("http://localhost:8899".to_string())),
+            Arc::new(Keypair::new()),
+            LookupTableConfig { addresses: Vec::new(), auto_extend: false, min_usage: 1 },
+        ).unwrap();
+        *manager.tables.write().unwrap() = tables.into_iter().collect();
+        manager
+    }
+
+    fn table(addresses: &[Pubkey], active_len: usize) -> TableState {
+        TableState { addresses: addresses.to_vec(), active_len, authority: None }
+    }
+
+    #[test]
+    fn test_active_len_excludes_current_slot_extension() {
+        let meta = LookupTableMeta {
+            last_extended_slot: 100,
+            last_extended_slot_start_index: 3,
+            ..LookupTableMeta::default()
+        };
+
+        // Расширение в текущем слоте: новые адреса ещё не активны
+        assert_eq!(active_len(&meta, 5, 100), 3);
+        // Со следующего слота активна вся таблица
+        assert_eq!(active_len(&meta, 5, 101), 5);
+    }
+
+    #[test]
+    fn test_select_for_greedy_cover() {
+        let keys: Vec<Pubkey> = (0..6).map(|_| Pubkey::new_unique()).collect();
+        let (signer, program) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (largest, second, single, inactive) =
+            (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        let manager = manager(vec![
+            (largest, table(&[signer, keys[0], keys[1], keys[2], keys[3]], 5)),
+            (second, table(&[keys[3], keys[4], keys[5]], 3)),
+            (single, table(&[keys[5], program], 2)),
+            // Все адреса добавлены в текущем слоте — таблица не используется
+            (inactive, table(&keys, 0)),
+        ]);
+
+        let mut accounts = vec![AccountMeta::new(signer, true), AccountMeta::new_readonly(program, false)];
+        accounts.extend(keys.iter().map(|key| AccountMeta::new(*key, false)));
+        let ix = Instruction::new_with_bytes(program, &[], accounts);
+
+        // Подписант и программа не выносятся: largest покрывает 4, затем second — оставшиеся 2.
+        // single покрыл бы только уже покрытый keys[5]
+        let selected = manager.select_for(&[ix]);
+        let selected_keys: Vec<Pubkey> = selected.iter().map(|table| table.key).collect();
+        assert_eq!(selected_keys, vec![largest, second]);
+        assert_eq!(selected[1].addresses, vec![keys[3], keys[4], keys[5]]);
+
+        // Таблица ради одного аккаунта не подключается
+        let lone = Instruction::new_with_bytes(program, &[], vec![AccountMeta::new(keys[4], false)]);
+        assert!(manager.select_for(&[lone]).is_empty());
+    }
+}
@@ -1,24 +1,70 @@
 // bot/src/executor/mod.rs
 
 pub mod transaction_builder;
+pub mod blockhash_cache;
 pub mod jito_client;
 pub mod simulator;
+pub mod lookup_tables;
+pub mod token_accounts;
+pub mod fee_market;
+pub mod outcome;
 
-use anyhow::Result;
-use solana_sdk::signature::{Keypair, Signature};
-use solana_client::rpc_client::RpcClient;
+use anyhow::{Context, Result};
+use solana_sdk::{
+    instruction::Instruction,
+    signature::{Keypair, Signature, Signer},
+    transaction::{TransactionError, VersionedTransaction},
+};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_client::rpc_config::{RpcSendTransactionConfig, RpcTransactionConfig};
+use solana_transaction_status::UiTransactionEncoding;
 use std::sync::Arc;
-use tracing::info;
+use std::time::{Duration, Instant};
+use tokio::time::{interval, MissedTickBehavior};
+use tracing::{debug, info, warn};
 
-use crate::config::BotConfig;
+use crate::config::{BotConfig, JitoFallback};
 use crate::types::ArbitrageOpportunity;
 use transaction_builder::TransactionBuilder;
 use simulator::TransactionSimulator;
+use lookup_tables::LookupTableManager;
+use token_accounts::TokenAccountManager;
+use blockhash_cache::BlockhashCache;
+use fee_market::{ComputeBudget, FeeMarket};
+use jito_client::{BundleRejected, JitoClient};
+use outcome::ExecutionOutcome;
+
+/// Период опроса статуса отправленной транзакции
+const STATUS_POLL_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Итог отправки через Jito
+enum JitoAttempt {
+    /// Bundle подтверждён
+    Landed(ExecutionOutcome),
+    /// Block engine явно отклонил bundle: транзакция никуда не ушла
+    Rejected,
+    /// Bundle мог быть принят, но не подтверждён: попасть в блок может только эта же транзакция
+    Unconfirmed,
+}
+
+/// Отправка через Jito и политика fallback на RPC
+struct JitoSubmission {
+    client: JitoClient,
+    confirm_timeout: Duration,
+    fallback: JitoFallback,
+}
 
 pub struct TransactionExecutor {
     rpc_client: Arc<RpcClient>,
+    keypair: Arc<Keypair>,
+    jito: Option<JitoSubmission>,
     builder: TransactionBuilder,
     simulator: TransactionSimulator,
+    fee_market: FeeMarket,
+    lookup_tables: Arc<LookupTableManager>,
+    token_accounts: Arc<TokenAccountManager>,
+    blockhash_cache: Arc<BlockhashCache>,
+    confirm_timeout: Duration,
 }
 
 impl TransactionExecutor {
@@ -26,31 +72,237 @@ impl TransactionExecutor {
         rpc_client: Arc<RpcClient>,
         keypair: Arc<Keypair>,
         config: Arc<BotConfig>,
+        blockhash_cache: Arc<BlockhashCache>,
     ) -> Result<Self> {
+        let lookup_tables = Arc::new(LookupTableManager::new(
+            rpc_client.clone(),
+            keypair.clone(),
+            config.lookup_tables.clone(),
+        )?);
+        let token_accounts = Arc::new(TokenAccountManager::new(
+            rpc_client.clone(),
+            keypair.clone(),
+            config.ata.clone(),
+        )?);
+
+        let jito = config.jito.as_ref()
+            .map(|jito| -> Result<JitoSubmission> {
+                Ok(JitoSubmission {
+                    client: JitoClient::new(jito)?,
+                    confirm_timeout: Duration::from_secs(jito.confirm_timeout_secs),
+                    fallback: jito.fallback,
+                })
+            })
+            .transpose()?;
+
         Ok(Self {
             builder: TransactionBuilder::new(
                 rpc_client.clone(),
                 keypair.clone(),
                 config.clone(),
+                lookup_tables.clone(),
+                token_accounts.clone(),
+                blockhash_cache.clone(),
             )?,
             simulator: TransactionSimulator::new(rpc_client.clone()),
+            fee_market: FeeMarket::new(rpc_client.clone(), &config.trading),
+            confirm_timeout: Duration::from_secs(config.trading.confirm_timeout_secs),
             rpc_client,
+            keypair,
+            jito,
+            lookup_tables,
+            token_accounts,
+            blockhash_cache,
         })
     }
 
-    pub async fn execute(&self, opportunity: &ArbitrageOpportunity) -> Result<Signature> {
-        let transaction = self.builder.build_arbitrage_transaction(opportunity).await?;
+    /// Обновление ALT и вынос в них часто используемых аккаунтов маршрутов.
+    /// Ждёт подтверждения create/extend — вызывается из фоновой задачи
+    pub async fn maintain_lookup_tables(&self) -> Result<usize> {
+        self.lookup_tables.maintain(self.blockhash_cache.slot()).await
+    }
 
-        info!("🧪 Симуляция транзакции...");
-        let simulation = self.simulator.simulate(&transaction).await?;
-        if let Some(err) = simulation.err {
-            anyhow::bail!("Симуляция провалилась: {}\nЛоги:\n{:#?}", err, simulation.logs);
+    /// ATA кошелька и token program минтов (общий кэш с балансами кошелька)
+    pub fn token_accounts(&self) -> Arc<TokenAccountManager> {
+        self.token_accounts.clone()
+    }
+
+    /// Закрытие пустых промежуточных ATA (если включено ATA_CLEANUP)
+    pub async fn cleanup_token_accounts(&self) -> Result<usize> {
+        self.token_accounts.cleanup().await
+    }
+
+    /// Сборка, симуляция и отправка. Ошибка — транзакция не отправлена;
+    /// итог отправленной транзакции (включая откат) — ExecutionOutcome
+    pub async fn execute(&self, opportunity: &ArbitrageOpportunity) -> Result<ExecutionOutcome> {
+        let mut instructions = self.builder.build_arbitrage_instructions(opportunity).await?;
+
+        // Tip внутри транзакции арбитража: без успешного исполнения не платится
+        if let Some(jito) = &self.jito {
+            instructions.push(jito.client.tip_instruction(&self.keypair.pubkey()));
         }
 
-        info!("✅ Симуляция успешна (CU: {})", simulation.units_consumed.unwrap_or(0));
+        // Симуляция с максимальным лимитом: фактическое потребление CU
+        info!("🧪 Симуляция транзакции...");
+        let simulation_tx = self.builder.compile_with_budget(&instructions, ComputeBudget::simulation())?;
+        let units_consumed = self.simulator.estimate_compute_units(&simulation_tx).await?;
+
+        let budget = self.fee_market.compute_budget(&instructions, units_consumed, opportunity.net_profit_lamports).await;
+        info!("✅ Симуляция успешна (CU: {}, лимит {}, цена {} µlamports/CU, приоритет {} lamports)",
+              units_consumed, budget.unit_limit, budget.unit_price, budget.priority_fee_lamports());
+
+        let mut transaction = self.builder.compile_with_budget(&instructions, budget)?;
+
+        if let Some(jito) = &self.jito {
+            match self.send_via_jito(jito, &transaction).await? {
+                JitoAttempt::Landed(outcome) => return Ok(outcome),
+                JitoAttempt::Rejected => {
+                    // Вне bundle tip — просто перевод: транзакция пересобирается без него
+                    instructions.pop();
+                    transaction = self.builder.compile_with_budget(&instructions, budget)?;
+                }
+                JitoAttempt::Unconfirmed => {
+                    // За время ожидания bundle блокхеш мог истечь
+                    transaction = self.rebuild_if_expired(transaction, &instructions, budget)?;
+                }
+            }
+        }
 
         info!("📤 Отправка транзакции...");
-        let signature = self.rpc_client.send_and_confirm_transaction(&transaction)?;
-        Ok(signature)
+        self.send_and_track(&transaction).await
     }
-}
\ No newline at end of file
+
+    /// Отправка без preflight (транзакция уже симулирована) и опрос статуса
+    /// до подтверждения, истечения блокхеша или CONFIRM_TIMEOUT_SECS
+    async fn send_and_track(&self, transaction: &VersionedTransaction) -> Result<ExecutionOutcome> {
+        let signature = *transaction.signatures.first()
+            .ok_or_else(|| anyhow::anyhow!("Транзакция без подписи"))?;
+        let blockhash = *transaction.message.recent_blockhash();
+
+        let config = RpcSendTransactionConfig {
+            skip_preflight: true,
+            ..RpcSendTransactionConfig::default()
+        };
+        if let Err(e) = self.rpc_client.send_transaction_with_config(transaction, config).await {
+            if matches!(e.get_transaction_error(), Some(TransactionError::BlockhashNotFound)) {
+                return Ok(ExecutionOutcome::BlockhashExpired { signature });
+            }
+            return Err(e).context("RPC sendTransaction");
+        }
+
+        let commitment = self.rpc_client.commitment();
+        let deadline = Instant::now() + self.confirm_timeout;
+
+        loop {
+            tokio::time::sleep(STATUS_POLL_INTERVAL).await;
+
+            let status = self.rpc_client.get_signature_statuses(&[signature]).await
+                .context("RPC getSignatureStatuses")?
+                .value
+                .into_iter()
+                .next()
+                .flatten();
+
+            match status {
+                Some(status) if status.satisfies_commitment(commitment) => {
+                    let logs = self.transaction_logs(&signature).await;
+                    return Ok(ExecutionOutcome::from_status(signature, status.slot, status.err.as_ref(), &logs));
+                }
+                Some(status) => debug!("   Транзакция в слоте {}, ожидание подтверждения", status.slot),
+                None if self.blockhash_cache.is_invalid(&blockhash) => {
+                    return Ok(ExecutionOutcome::BlockhashExpired { signature });
+                }
+                None => {}
+            }
+
+            if Instant::now() >= deadline {
+                return Ok(ExecutionOutcome::Dropped { signature });
+            }
+        }
+    }
+
+    /// Логи подтверждённой транзакции: по ним определяются программа ошибки, нога и прибыль.
+    /// Без логов итог классифицируется только по коду ошибки
+    async fn transaction_logs(&self, signature: &Signature) -> Vec<String> {
+        let config = RpcTransactionConfig {
+            encoding: Some(UiTransactionEncoding::Base64),
+            commitment: Some(self.rpc_client.commitment()),
+            max_supported_transaction_version: Some(0),
+        };
+
+        match self.rpc_client.get_transaction_with_config(signature, config).await {
+            Ok(transaction) => transaction.transaction.meta
+                .and_then(|meta| Option::<Vec<String>>::from(meta.log_messages))
+                .unwrap_or_default(),
+            Err(e) => {
+                warn!("⚠️ Не удалось получить логи транзакции {}: {}", signature, e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Пересборка транзакции со свежим блокхешем вместо отправки заведомо отклоняемой
+    fn rebuild_if_expired(
+        &self,
+        transaction: VersionedTransaction,
+        instructions: &[Instruction],
+        budget: ComputeBudget,
+    ) -> Result<VersionedTransaction> {
+        if !self.blockhash_cache.is_expired(transaction.message.recent_blockhash()) {
+            return Ok(transaction);
+        }
+
+        warn!("♻️ Блокхеш транзакции истёк, пересборка");
+        self.builder.compile_with_budget(instructions, budget)
+    }
+
+    /// Отправка bundle из одной транзакции. Rejected и Unconfirmed — нужна отправка
+    /// через RPC по политике fallback
+    async fn send_via_jito(&self, jito: &JitoSubmission, transaction: &VersionedTransaction) -> Result<JitoAttempt> {
+        let signature = *transaction.signatures.first()
+            .ok_or_else(|| anyhow::anyhow!("Транзакция без подписи"))?;
+
+        let bundle_id = match jito.client.send_bundle(std::slice::from_ref(transaction)).await {
+            Ok(bundle_id) => bundle_id,
+            Err(e) if jito.fallback == JitoFallback::Never => return Err(e),
+            Err(e) if e.downcast_ref::<BundleRejected>().is_some() => {
+                warn!("⚠️ Jito отклонил bundle, отправка через RPC: {:#}", e);
+                return Ok(JitoAttempt::Rejected);
+            }
+            Err(e) => {
+                // Таймаут, 5xx или битый ответ: bundle мог уйти, поэтому только та же транзакция
+                warn!("⚠️ Ответ Jito не получен, отправка той же транзакции через RPC: {:#}", e);
+                return Ok(JitoAttempt::Unconfirmed);
+            }
+        };
+
+        match jito.client.wait_for_confirmation(&bundle_id, jito.confirm_timeout).await? {
+            Some(status) => {
+                // Bundle попадает в блок только целиком успешным
+                let logs = self.transaction_logs(&signature).await;
+                Ok(JitoAttempt::Landed(ExecutionOutcome::from_status(signature, status.slot.unwrap_or_default(), None, &logs)))
+            }
+            None if jito.fallback == JitoFallback::OnTimeout => {
+                // Та же подписанная транзакция: попасть в блок она может только один раз
+                warn!("⚠️ Bundle {} не подтверждён, отправка через RPC", bundle_id);
+                Ok(JitoAttempt::Unconfirmed)
+            }
+            None => anyhow::bail!("Bundle {} не подтверждён за {:?}", bundle_id, jito.confirm_timeout),
+        }
+    }
+}
+
+/// Фоновое обслуживание ALT: отправка create/extend с подтверждением
+/// занимает секунды и не задерживает поиск и исполнение
+pub async fn start_lookup_table_task(executor: Arc<TransactionExecutor>, period: Duration) {
+    let mut maintain_interval = interval(period);
+    maintain_interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    loop {
+        maintain_interval.tick().await;
+
+        if let Err(e) = executor.maintain_lookup_tables().await {
+            warn!("⚠️ Ошибка обслуживания ALT: {:#}", e);
+        }
+    }
+}
@@ -0,0 +1,326 @@
+// bot/src/executor/outcome.rs
+// Итог исполнения транзакции арбитража. Custom-коды ошибок декодируются
+// по программе, в которой они возникли (по логам транзакции)
+
+use arbitrage_executor::ArbitrageError;
+use solana_sdk::{
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::TransactionError,
+};
+use std::fmt;
+use std::str::FromStr;
+
+use super::transaction_builder::{METEORA_DLMM, RAYDIUM_AMM_V4, RAYDIUM_CLMM, RAYDIUM_CPMM};
+use crate::arbitrage::reputation::FailureKind;
+use crate::types::DexProtocol;
+
+/// Варианты ArbitrageError в порядке объявления: код = ERROR_CODE_OFFSET + индекс
+const ARBITRAGE_ERRORS: [ArbitrageError; 11] = [
+    ArbitrageError::InsufficientProfit,
+    ArbitrageError::SlippageExceeded,
+    ArbitrageError::InvalidAccountsCount,
+    ArbitrageError::InvalidDexProtocol,
+    ArbitrageError::MathOverflow,
+    ArbitrageError::Unauthorized,
+    ArbitrageError::TooManyLegs,
+    ArbitrageError::InsufficientBalance,
+    ArbitrageError::CpiCallFailed,
+    ArbitrageError::InvalidTokenAccount,
+    ArbitrageError::TooManySubSwaps,
+];
+
+/// Известные коды ошибок DEX: (протокол, код, имя). Все — превышение проскальзывания
+const DEX_SLIPPAGE_ERRORS: [(DexProtocol, u32, &str); 7] = [
+    (DexProtocol::RaydiumAmmV4, 30, "ExceededSlippage"),
+    (DexProtocol::RaydiumCpmm, 6005, "ExceededSlippage"),
+    (DexProtocol::RaydiumClmm, 6021, "PriceSlippageCheck"),
+    (DexProtocol::RaydiumClmm, 6022, "TooLittleOutputReceived"),
+    (DexProtocol::RaydiumClmm, 6023, "TooMuchInputPaid"),
+    (DexProtocol::MeteoraDlmm, 6003, "ExceededAmountSlippageTolerance"),
+    (DexProtocol::MeteoraDlmm, 6004, "ExceededBinSlippageTolerance"),
+];
+
+/// Ошибка исполнения, декодированная по коду и программе
+#[derive(Debug, Clone)]
+pub enum ProgramError {
+    /// Ошибка arbitrage-executor
+    Arbitrage(ArbitrageError),
+    /// Custom-ошибка DEX; имя известно только для кодов проскальзывания
+    Dex { protocol: DexProtocol, code: u32, name: Option<&'static str> },
+    /// Ошибка другой программы или рантайма
+    Other(String),
+}
+
+impl ProgramError {
+    /// Ошибка из статуса транзакции и её логов
+    pub fn decode(error: &TransactionError, logs: &[String]) -> Self {
+        let TransactionError::InstructionError(_, InstructionError::Custom(code)) = error else {
+            return ProgramError::Other(format!("{:?}", error));
+        };
+        let code = *code;
+
+        // Ошибка CPI прерывает транзакцию с кодом вызванной программы
+        if let Some(protocol) = failed_program(logs).and_then(dex_protocol) {
+            let name = DEX_SLIPPAGE_ERRORS.iter()
+                .find(|(p, c, _)| *p == protocol && *c == code)
+                .map(|(_, _, name)| *name);
+            return ProgramError::Dex { protocol, code, name };
+        }
+
+        ARBITRAGE_ERRORS.into_iter()
+            .find(|error| u32::from(*error) == code)
+            .map_or_else(|| ProgramError::Other(format!("Custom({})", code)), ProgramError::Arbitrage)
+    }
+
+    pub fn is_slippage(&self) -> bool {
+        match self {
+            ProgramError::Arbitrage(error) => matches!(error, ArbitrageError::SlippageExceeded),
+            ProgramError::Dex { name, .. } => name.is_some(),
+            ProgramError::Other(_) => false,
+        }
+    }
+
+    /// Категория неудачи для репутации цикла
+    pub fn failure_kind(&self) -> FailureKind {
+        match self {
+            ProgramError::Arbitrage(ArbitrageError::InsufficientProfit) => FailureKind::InsufficientProfit,
+            error if error.is_slippage() => FailureKind::Slippage,
+            _ => FailureKind::Other,
+        }
+    }
+}
+
+impl fmt::Display for ProgramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProgramError::Arbitrage(error) => {
+                write!(f, "ArbitrageError::{:?} (Custom({}))", error, u32::from(*error))
+            }
+            ProgramError::Dex { protocol, code, name } => {
+                write!(f, "{:?} {} (Custom({}))", protocol, name.unwrap_or("ошибка"), code)
+            }
+            ProgramError::Other(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+/// Итог отправки транзакции арбитража
+#[derive(Debug, Clone)]
+pub enum ExecutionOutcome {
+    /// В блоке: программа проверила min_profit. Прибыль — из лога программы
+    Landed { signature: Signature, slot: u64, profit_lamports: Option<u64> },
+    /// Откат по ArbitrageError::InsufficientProfit
+    InsufficientProfit { signature: Signature, slot: u64 },
+    /// Откат из-за проскальзывания; leg — номер ноги (с 1) по логам программы
+    Slippage { signature: Signature, slot: u64, leg: Option<usize>, error: ProgramError },
+    /// Откат с другой ошибкой
+    Failed { signature: Signature, slot: u64, leg: Option<usize>, error: ProgramError },
+    /// Блокхеш истёк до попадания в блок
+    BlockhashExpired { signature: Signature },
+    /// Не подтверждена за время ожидания
+    Dropped { signature: Signature },
+}
+
+impl ExecutionOutcome {
+    /// Итог подтверждённой транзакции по ошибке статуса и логам
+    pub fn from_status(signature: Signature, slot: u64, error: Option<&TransactionError>, logs: &[String]) -> Self {
+        let Some(error) = error else {
+            return ExecutionOutcome::Landed { signature, slot, profit_lamports: logged_profit(logs) };
+        };
+
+        let leg = failed_leg(logs);
+        match ProgramError::decode(error, logs) {
+            ProgramError::Arbitrage(ArbitrageError::InsufficientProfit) => {
+                ExecutionOutcome::InsufficientProfit { signature, slot }
+            }
+            error if error.is_slippage() => ExecutionOutcome::Slippage { signature, slot, leg, error },
+            error => ExecutionOutcome::Failed { signature, slot, leg, error },
+        }
+    }
+
+    pub fn signature(&self) -> &Signature {
+        match self {
+            ExecutionOutcome::Landed { signature, .. }
+            | ExecutionOutcome::InsufficientProfit { signature, .. }
+            | ExecutionOutcome::Slippage { signature, .. }
+            | ExecutionOutcome::Failed { signature, .. }
+            | ExecutionOutcome::BlockhashExpired { signature }
+            | ExecutionOutcome::Dropped { signature } => signature,
+        }
+    }
+
+    /// Категория неудачи цикла; None — успех или транзакция не попала в блок
+    /// (истёкший блокхеш и потеря транзакции не говорят о цикле)
+    pub fn failure_kind(&self) -> Option<FailureKind> {
+        match self {
+            ExecutionOutcome::InsufficientProfit { .. } => Some(FailureKind::InsufficientProfit),
+            ExecutionOutcome::Slippage { .. } => Some(FailureKind::Slippage),
+            ExecutionOutcome::Failed { error, .. } => Some(error.failure_kind()),
+            ExecutionOutcome::Landed { .. }
+            | ExecutionOutcome::BlockhashExpired { .. }
+            | ExecutionOutcome::Dropped { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for ExecutionOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecutionOutcome::Landed { slot, profit_lamports, .. } => match profit_lamports {
+                Some(profit) => write!(f, "в блоке (слот {}), прибыль {} lamports", slot, profit),
+                None => write!(f, "в блоке (слот {})", slot),
+            },
+            ExecutionOutcome::InsufficientProfit { slot, .. } => {
+                write!(f, "откат ArbitrageError::InsufficientProfit (слот {})", slot)
+            }
+            ExecutionOutcome::Slippage { slot, leg, error, .. } => {
+                write!(f, "откат: slippage {}{} (слот {})", error, leg_suffix(*leg), slot)
+            }
+            ExecutionOutcome::Failed { slot, leg, error, .. } => {
+                write!(f, "откат: {}{} (слот {})", error, leg_suffix(*leg), slot)
+            }
+            ExecutionOutcome::BlockhashExpired { .. } => write!(f, "блокхеш истёк до попадания в блок"),
+            ExecutionOutcome::Dropped { .. } => write!(f, "не подтверждена за время ожидания"),
+        }
+    }
+}
+
+fn leg_suffix(leg: Option<usize>) -> String {
+    leg.map(|leg| format!(" на ноге {}", leg)).unwrap_or_default()
+}
+
+fn dex_protocol(program_id: Pubkey) -> Option<DexProtocol> {
+    match program_id {
+        id if id == RAYDIUM_AMM_V4 => Some(DexProtocol::RaydiumAmmV4),
+        id if id == RAYDIUM_CPMM => Some(DexProtocol::RaydiumCpmm),
+        id if id == RAYDIUM_CLMM => Some(DexProtocol::RaydiumClmm),
+        id if id == METEORA_DLMM => Some(DexProtocol::MeteoraDlmm),
+        _ => None,
+    }
+}
+
+/// Программа, в которой возникла ошибка: первая строка "Program <id> failed" —
+/// самый глубокий вызов, дальше ошибка поднимается по стеку CPI
+fn failed_program(logs: &[String]) -> Option<Pubkey> {
+    logs.iter().find_map(|line| {
+        let (id, rest) = line.strip_prefix("Program ")?.split_once(' ')?;
+        if !rest.starts_with("failed") {
+            return None;
+        }
+        Pubkey::from_str(id).ok()
+    })
+}
+
+/// Номер ноги (с 1) из последнего лога программы "📊 Leg N/M"
+fn failed_leg(logs: &[String]) -> Option<usize> {
+    logs.iter().rev().find_map(|line| {
+        let (_, rest) = line.split_once("Leg ")?;
+        rest.split_once('/')?.0.parse().ok()
+    })
+}
+
+/// Прибыль из лога программы "📈 Profit: N lamports"
+fn logged_profit(logs: &[String]) -> Option<u64> {
+    logs.iter().find_map(|line| {
+        let (_, rest) = line.split_once("Profit: ")?;
+        rest.split_whitespace().next()?.parse().ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_insufficient_profit_decoded_from_executor_code() {
+        let program = Pubkey::new_unique();
+        let logs = vec![
+            format!("Program {} invoke [1]", program),
+            "Program log: 📊 Leg 3/3: 1 sub-swap(s)".to_string(),
+            "Program log: 📈 Profit: 10 lamports (0.0010%)".to_string(),
+            format!("Program {} failed: custom program error: 0x1770", program),
+        ];
+        let error = TransactionError::InstructionError(2, InstructionError::Custom(6000));
+
+        let outcome = ExecutionOutcome::from_status(Signature::default(), 7, Some(&error), &logs);
+        assert!(matches!(outcome, ExecutionOutcome::InsufficientProfit { slot: 7, .. }));
+        assert!(outcome.to_string().contains("InsufficientProfit"));
+    }
+
+    #[test]
+    fn test_dex_slippage_reports_failing_leg() {
+        let program = Pubkey::new_unique();
+        let logs = vec![
+            format!("Program {} invoke [1]", program),
+            "Program log: 📊 Leg 1/2: 1 sub-swap(s)".to_string(),
+            "Program log: 📊 Leg 2/2: 1 sub-swap(s)".to_string(),
+            format!("Program {} invoke [2]", METEORA_DLMM),
+            format!("Program {} failed: custom program error: 0x1773", METEORA_DLMM),
+            format!("Program {} failed: custom program error: 0x1773", program),
+        ];
+        let error = TransactionError::InstructionError(2, InstructionError::Custom(6003));
+
+        let outcome = ExecutionOutcome::from_status(Signature::default(), 7, Some(&error), &logs);
+        match outcome {
+            ExecutionOutcome::Slippage { leg, error: ProgramError::Dex { protocol, name, .. }, .. } => {
+                assert_eq!(leg, Some(2));
+                assert_eq!(protocol, DexProtocol::MeteoraDlmm);
+                assert_eq!(name, Some("ExceededAmountSlippageTolerance"));
+            }
+            other => panic!("ожидался slippage, получено {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_loss_reverts_with_insufficient_profit() {
+        let program = Pubkey::new_unique();
+        let logs = vec![
+            format!("Program {} invoke [1]", program),
+            "Program log: 📈 Profit: -4200 lamports (-0.4200%)".to_string(),
+            format!("Program {} failed: custom program error: 0x1770", program),
+        ];
+        let error = TransactionError::InstructionError(2, InstructionError::Custom(6000));
+
+        // Убыток — тот же откат InsufficientProfit, а не MathOverflow
+        let outcome = ExecutionOutcome::from_status(Signature::default(), 7, Some(&error), &logs);
+        assert!(matches!(outcome, ExecutionOutcome::InsufficientProfit { slot: 7, .. }));
+        assert_eq!(outcome.failure_kind(), Some(FailureKind::InsufficientProfit));
+        assert_eq!(logged_profit(&logs), None);
+    }
+
+    #[test]
+    fn test_failure_kind_from_failing_program() {
+        let program = Pubkey::new_unique();
+        let dex_failure = |dex: Pubkey, code: u32| {
+            let logs = vec![
+                format!("Program {} invoke [1]", program),
+                format!("Program {} invoke [2]", dex),
+                format!("Program {} failed: custom program error: {:#x}", dex, code),
+                format!("Program {} failed: custom program error: {:#x}", program, code),
+            ];
+            let error = TransactionError::InstructionError(2, InstructionError::Custom(code));
+            ExecutionOutcome::from_status(Signature::default(), 7, Some(&error), &logs)
+        };
+
+        // Код 6000 внутри DLMM — ошибка DEX, а не InsufficientProfit исполнителя
+        assert_eq!(dex_failure(METEORA_DLMM, 6000).failure_kind(), Some(FailureKind::Other));
+        // TooMuchInputPaid CLMM — проскальзывание
+        assert_eq!(dex_failure(RAYDIUM_CLMM, 6023).failure_kind(), Some(FailureKind::Slippage));
+
+        let dropped = ExecutionOutcome::Dropped { signature: Signature::default() };
+        assert_eq!(dropped.failure_kind(), None);
+    }
+
+    #[test]
+    fn test_landed_profit_parsed_from_logs() {
+        let logs = vec!["Program log: 📈 Profit: 125000 lamports (0.1250%)".to_string()];
+
+        let outcome = ExecutionOutcome::from_status(Signature::default(), 9, None, &logs);
+        assert!(matches!(
+            outcome,
+            ExecutionOutcome::Landed { slot: 9, profit_lamports: Some(125_000), .. }
+        ));
+    }
+}
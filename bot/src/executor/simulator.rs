@@ -1,10 +1,40 @@
 // bot/src/executor/simulator.rs (завершение)
 use tracing::debug;
-use solana_sdk::transaction::Transaction;
+use solana_sdk::transaction::VersionedTransaction;
+use crate::arbitrage::reputation::FailureKind;
 use crate::types::SimulationResult;
 use anyhow::Result;
-use solana_client::rpc_client::RpcClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use std::fmt;
 use std::sync::Arc;
+
+use super::outcome::ProgramError;
+
+/// Симуляция отклонена программой: цикл не исполнится в текущем состоянии пулов
+#[derive(Debug)]
+pub struct SimulationFailed {
+    pub error: ProgramError,
+    pub logs: Vec<String>,
+}
+
+impl SimulationFailed {
+    /// Категория неудачи: по коду ошибки, иначе по логам Anchor
+    pub fn failure_kind(&self) -> FailureKind {
+        match &self.error {
+            ProgramError::Other(_) => FailureKind::from_simulation_logs(&self.logs),
+            error => error.failure_kind(),
+        }
+    }
+}
+
+impl fmt::Display for SimulationFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Симуляция провалилась: {}\nЛоги:\n{:#?}", self.error, self.logs)
+    }
+}
+
+impl std::error::Error for SimulationFailed {}
+
 pub struct TransactionSimulator {
     rpc_client: Arc<RpcClient>,
 }
@@ -14,19 +44,20 @@ impl TransactionSimulator {
         Self { rpc_client }
     }
 
-    pub async fn simulate(&self, transaction: &Transaction) -> Result<SimulationResult> {
+    pub async fn simulate(&self, transaction: &VersionedTransaction) -> Result<SimulationResult> {
         let simulation = self.rpc_client
             .simulate_transaction(transaction)
+            .await
             .map_err(|e| anyhow::anyhow!("Ошибка симуляции: {}", e))?;
 
         let result = SimulationResult {
-            err: simulation.value.err.map(|e| format!("{:?}", e)),
+            err: simulation.value.err,
             logs: simulation.value.logs.clone().unwrap_or_default(),
             units_consumed: simulation.value.units_consumed,
         };
 
         if let Some(ref err) = result.err {
-            debug!("Симуляция завершилась с ошибкой: {}", err);
+            debug!("Симуляция завершилась с ошибкой: {:?}", err);
             if let Some(logs) = &simulation.value.logs {
                 for log in logs {
                     debug!("  Log: {}", log);
@@ -37,9 +68,14 @@ impl TransactionSimulator {
         Ok(result)
     }
 
-    /// Оценка compute units для транзакции
-    pub async fn estimate_compute_units(&self, transaction: &Transaction) -> Result<u64> {
+    /// Оценка compute units для транзакции. Провал симуляции — ошибка SimulationFailed
+    /// с декодированной ошибкой программы; ошибка RPC — обычная ошибка
+    pub async fn estimate_compute_units(&self, transaction: &VersionedTransaction) -> Result<u64> {
         let simulation = self.simulate(transaction).await?;
+        if let Some(err) = simulation.err {
+            let error = ProgramError::decode(&err, &simulation.logs);
+            return Err(SimulationFailed { error, logs: simulation.logs }.into());
+        }
 
         simulation.units_consumed
             .ok_or_else(|| anyhow::anyhow!("Не удалось определить compute units"))
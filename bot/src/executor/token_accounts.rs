@@ -0,0 +1,313 @@
+// bot/src/executor/token_accounts.rs
+// ATA кошелька для маршрутов: недостающие создаются идемпотентными инструкциями
+// (в самой транзакции арбитража или отдельной setup-транзакцией),
+// пустые промежуточные ATA, созданные ботом, закрываются для возврата ренты
+
+use anyhow::{Context, Result};
+use dashmap::{DashMap, DashSet};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
+};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+use crate::config::AtaConfig;
+use crate::dex_structs::read_u64;
+use crate::inventory::TOKEN_ACCOUNT_AMOUNT_OFFSET;
+use crate::utils::rpc::get_multiple_accounts_chunked;
+
+/// Инструкций CloseAccount в одной транзакции очистки
+const MAX_CLOSES_PER_TRANSACTION: usize = 8;
+
+/// ATA, созданный ботом: кандидат на закрытие после простоя
+#[derive(Debug, Clone, Copy)]
+struct CreatedAta {
+    mint: Pubkey,
+    token_program: Pubkey,
+    last_used: Instant,
+}
+
+pub struct TokenAccountManager {
+    rpc_client: Arc<RpcClient>,
+    keypair: Arc<Keypair>,
+    config: AtaConfig,
+    /// Минт -> token program (владелец аккаунта минта)
+    token_programs: DashMap<Pubkey, Pubkey>,
+    /// ATA, существование которых уже подтверждено
+    existing: DashSet<Pubkey>,
+    /// ATA -> минт и время последнего маршрута через него
+    created: DashMap<Pubkey, CreatedAta>,
+    keep_mints: HashSet<Pubkey>,
+    last_cleanup: Mutex<Instant>,
+}
+
+impl TokenAccountManager {
+    pub fn new(rpc_client: Arc<RpcClient>, keypair: Arc<Keypair>, config: AtaConfig) -> Result<Self> {
+        let keep_mints = config.keep_mints.iter()
+            .map(|mint| mint.to_pubkey())
+            .collect::<Result<HashSet<_>>>()
+            .context("Invalid ATA_KEEP_MINTS")?;
+
+        Ok(Self {
+            rpc_client,
+            keypair,
+            config,
+            token_programs: DashMap::new(),
+            existing: DashSet::new(),
+            created: DashMap::new(),
+            keep_mints,
+            last_cleanup: Mutex::new(Instant::now()),
+        })
+    }
+
+    /// Загрузка token program для минтов, которых ещё нет в кэше.
+    /// Token program минта — владелец аккаунта минта (SPL Token или Token-2022)
+    pub async fn resolve_token_programs(&self, mints: &[Pubkey]) -> Result<()> {
+        let unknown: Vec<Pubkey> = mints.iter()
+            .filter(|mint| !self.token_programs.contains_key(*mint))
+            .copied()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        if unknown.is_empty() {
+            return Ok(());
+        }
+
+        let accounts = get_multiple_accounts_chunked(&self.rpc_client, &unknown).await
+            .context("RPC get_multiple_accounts (минты)")?;
+        for (mint, account) in unknown.iter().zip(accounts) {
+            // Минт без аккаунта остаётся неизвестным: token_program вернёт ошибку
+            match account {
+                Some(account) => {
+                    self.token_programs.insert(*mint, account.owner);
+                }
+                None => warn!("⚠️ Минт {} не найден", mint),
+            }
+        }
+        Ok(())
+    }
+
+    /// Token program минта из кэша (после resolve_token_programs или provision)
+    pub fn token_program(&self, mint: &Pubkey) -> Result<Pubkey> {
+        self.token_programs.get(mint)
+            .map(|program| *program)
+            .with_context(|| format!("Token program минта {} неизвестен", mint))
+    }
+
+    /// ATA кошелька для минта: адрес зависит от token program минта
+    pub fn ata(&self, mint: &Pubkey) -> Result<Pubkey> {
+        Ok(get_associated_token_address_with_program_id(&self.keypair.pubkey(), mint, &self.token_program(mint)?))
+    }
+
+    /// Инструкции создания недостающих ATA для минтов маршрута.
+    /// В режиме setup-транзакции ATA создаются сразу, и возвращается пустой список
+    pub async fn provision(&self, mints: &[Pubkey]) -> Result<Vec<Instruction>> {
+        let owner = self.keypair.pubkey();
+        let now = Instant::now();
+        let mints: Vec<Pubkey> = mints.iter().copied().collect::<HashSet<_>>().into_iter().collect();
+
+        self.resolve_token_programs(&mints).await?;
+
+        let mut candidates = Vec::new();
+        for mint in mints {
+            let token_program = self.token_program(&mint)?;
+            let ata = get_associated_token_address_with_program_id(&owner, &mint, &token_program);
+
+            // Маршрут через созданный ботом ATA откладывает его закрытие
+            if let Some(mut created) = self.created.get_mut(&ata) {
+                created.last_used = now;
+            }
+            if !self.existing.contains(&ata) {
+                candidates.push((mint, token_program, ata));
+            }
+        }
+        if candidates.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let atas: Vec<Pubkey> = candidates.iter().map(|(_, _, ata)| *ata).collect();
+        let ata_accounts = get_multiple_accounts_chunked(&self.rpc_client, &atas).await
+            .context("RPC get_multiple_accounts (ATA маршрута)")?;
+
+        let mut missing = Vec::new();
+        for ((mint, token_program, ata), account) in candidates.into_iter().zip(ata_accounts) {
+            if account.is_some() {
+                self.existing.insert(ata);
+            } else {
+                missing.push((mint, token_program, ata));
+            }
+        }
+        if missing.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let instructions: Vec<Instruction> = missing.iter()
+            .map(|(mint, token_program, _)| {
+                create_associated_token_account_idempotent(&owner, &owner, mint, token_program)
+            })
+            .collect();
+
+        if self.config.setup_transaction {
+            self.send(&instructions).await.context("Setup-транзакция ATA")?;
+            info!("🪙 Создано ATA отдельной транзакцией: {}", missing.len());
+        } else {
+            debug!("🪙 Создание {} ATA в транзакции арбитража", missing.len());
+        }
+
+        // В inline-режиме ATA появится, только если транзакция попадёт в блок:
+        // до этого он перепроверяется, и idempotent-инструкция добавляется снова
+        for (mint, token_program, ata) in missing {
+            if self.config.setup_transaction {
+                self.existing.insert(ata);
+            }
+            self.created.insert(ata, CreatedAta { mint, token_program, last_used: now });
+        }
+
+        Ok(if self.config.setup_transaction { Vec::new() } else { instructions })
+    }
+
+    /// Закрытие пустых ATA, созданных ботом и не использованных за ATA_CLEANUP_INTERVAL_SECS.
+    /// Выполняется не чаще этого интервала. Возвращает количество закрытых ATA
+    pub async fn cleanup(&self) -> Result<usize> {
+        if !self.config.cleanup_enabled {
+            return Ok(0);
+        }
+
+        let interval = Duration::from_secs(self.config.cleanup_interval_secs);
+        {
+            let mut last_cleanup = self.last_cleanup.lock()
+                .map_err(|_| anyhow::anyhow!("Состояние очистки ATA отравлено"))?;
+            if last_cleanup.elapsed() < interval {
+                return Ok(0);
+            }
+            *last_cleanup = Instant::now();
+        }
+
+        let idle: Vec<(Pubkey, CreatedAta)> = self.created.iter()
+            .filter(|entry| entry.last_used.elapsed() >= interval && !self.keep_mints.contains(&entry.mint))
+            .map(|entry| (*entry.key(), *entry.value()))
+            .collect();
+        if idle.is_empty() {
+            return Ok(0);
+        }
+
+        let atas: Vec<Pubkey> = idle.iter().map(|(ata, _)| *ata).collect();
+        let accounts = get_multiple_accounts_chunked(&self.rpc_client, &atas).await
+            .context("RPC get_multiple_accounts (очистка ATA)")?;
+
+        let owner = self.keypair.pubkey();
+        let mut empty = Vec::new();
+        for ((ata, created), account) in idle.into_iter().zip(accounts) {
+            let Some(account) = account else {
+                // Закрыт вне бота
+                self.created.remove(&ata);
+                self.existing.remove(&ata);
+                continue;
+            };
+            if read_u64(&account.data, TOKEN_ACCOUNT_AMOUNT_OFFSET)? == 0 {
+                empty.push((ata, created));
+            }
+        }
+
+        let mut closed = 0;
+        for batch in empty.chunks(MAX_CLOSES_PER_TRANSACTION) {
+            let instructions = batch.iter()
+                .map(|(ata, created)| {
+                    let mut ix = spl_token::instruction::close_account(&spl_token::ID, ata, &owner, &owner, &[])?;
+                    // Формат CloseAccount в Token-2022 совпадает с SPL Token
+                    ix.program_id = created.token_program;
+                    Ok(ix)
+                })
+                .collect::<Result<Vec<_>>>()?;
+
+            if let Err(e) = self.send(&instructions).await {
+                warn!("⚠️ Не удалось закрыть {} ATA: {:#}", batch.len(), e);
+                continue;
+            }
+
+            for (ata, _) in batch {
+                self.created.remove(ata);
+                self.existing.remove(ata);
+            }
+            closed += batch.len();
+        }
+
+        if closed > 0 {
+            info!("🧹 Закрыто пустых ATA: {} (рента возвращена)", closed);
+        }
+        Ok(closed)
+    }
+
+    async fn send(&self, instructions: &[Instruction]) -> Result<()> {
+        let blockhash = self.rpc_client.get_latest_blockhash().await?;
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.keypair.pubkey()),
+            &[self.keypair.as_ref()],
+            blockhash,
+        );
+        self.rpc_client.send_and_confirm_transaction(&tx).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::BotConfig;
+    use crate::executor::transaction_builder::SPL_TOKEN_2022_ID;
+    use spl_associated_token_account::get_associated_token_address;
+
+    /// Менеджер с известными token program минтов; RPC недоступен
+    fn manager(token_programs: &[(Pubkey, Pubkey)]) -> TokenAccountManager {
+        let manager = TokenAccountManager::new(
+            Arc::new(RpcClient::new("http://127.0.0.1:1".to_string())),
+            Arc::new(Keypair::new()),
+            BotConfig::for_tests().ata,
+        ).unwrap();
+        for (mint, program) in token_programs {
+            manager.token_programs.insert(*mint, *program);
+        }
+        manager
+    }
+
+    #[test]
+    fn test_ata_follows_mint_token_program() {
+        let (spl_mint, token_2022_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let manager = manager(&[(spl_mint, spl_token::ID), (token_2022_mint, SPL_TOKEN_2022_ID)]);
+        let owner = manager.keypair.pubkey();
+
+        assert_eq!(manager.ata(&spl_mint).unwrap(), get_associated_token_address(&owner, &spl_mint));
+
+        // ATA Token-2022 выводится с другим token program в сидах
+        let ata = manager.ata(&token_2022_mint).unwrap();
+        assert_eq!(ata, get_associated_token_address_with_program_id(&owner, &token_2022_mint, &SPL_TOKEN_2022_ID));
+        assert_ne!(ata, get_associated_token_address(&owner, &token_2022_mint));
+
+        // Минт не загружен — адрес не угадывается
+        assert!(manager.ata(&Pubkey::new_unique()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_provision_skips_known_atas_without_rpc() {
+        let (spl_mint, token_2022_mint) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let manager = manager(&[(spl_mint, spl_token::ID), (token_2022_mint, SPL_TOKEN_2022_ID)]);
+        for mint in [spl_mint, token_2022_mint] {
+            manager.existing.insert(manager.ata(&mint).unwrap());
+        }
+
+        // Оба ATA уже подтверждены: ни инструкций, ни запросов к RPC
+        let instructions = manager.provision(&[spl_mint, token_2022_mint, spl_mint]).await.unwrap();
+        assert!(instructions.is_empty());
+    }
+}
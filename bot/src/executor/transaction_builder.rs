@@ -1,25 +1,33 @@
 // bot/src/executor/transaction_builder.rs
 use anchor_lang::prelude::*;
-use anchor_spl::{associated_token, token};
+use anchor_spl::token;
 use anyhow::{Context, Result};
-use solana_client::rpc_client::RpcClient;
+use futures::future::try_join_all;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
     compute_budget::ComputeBudgetInstruction,
-    hash::Hash,
     instruction::{AccountMeta, Instruction},
+    message::{v0, VersionedMessage},
+    packet::PACKET_DATA_SIZE,
     pubkey::Pubkey,
     signature::{Keypair, Signer},
-    transaction::Transaction,
+    transaction::VersionedTransaction,
     sysvar,
 };
 use solana_sdk::pubkey;
 use std::sync::Arc;
 use tracing::{debug, info, warn};
 
+use super::blockhash_cache::BlockhashCache;
+use super::fee_market::ComputeBudget;
+use super::lookup_tables::LookupTableManager;
+use super::token_accounts::TokenAccountManager;
 use crate::{
     config::BotConfig,
-    dex_structs::{AmmInfo, CpmmPoolInfo, ClmmPoolInfo},
-    types::{ArbitrageOpportunity, DexProtocol, SwapLeg},
+    arbitrage::{clmm_quote, dlmm_quote},
+    dex_structs::{AmmInfo, CpmmPoolInfo, ClmmPoolState, DlmmLbPair, OpenbookMarket},
+    types::{ArbitrageOpportunity, DexProtocol, SubSwap, SwapLeg},
+    utils::rpc::get_multiple_accounts_chunked,
 };
 
 // ============================================================================
@@ -29,6 +37,7 @@ use crate::{
 pub const RAYDIUM_AMM_V4: Pubkey = pubkey!("DRaya7Kj3aMWQSy19kSjvmuwq9docCHofyP9kanQGaav");
 pub const RAYDIUM_CPMM: Pubkey = pubkey!("DRaycpLY18LhpbydsBWbVJtxpNv9oXPgjRSfpF2bWpYb");
 pub const RAYDIUM_CLMM: Pubkey = pubkey!("DRayAUgENGQBKVaX8owNhgzkEDyoHTGVEGHVJT1E9pfH");
+pub const METEORA_DLMM: Pubkey = pubkey!("LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9YuVaPwxo");
 
 // SPL Program IDs для CLMM
 pub const SPL_TOKEN_ID: Pubkey = pubkey!("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
@@ -36,25 +45,42 @@ pub const SPL_TOKEN_2022_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnB
 pub const SPL_MEMO_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
 // ============================================================================
 
+/// TickArray сверх пройденных по котировке: цена может сдвинуться до исполнения
+const CLMM_EXTRA_TICK_ARRAYS: usize = 1;
+/// Верхняя граница TickArray на один CLMM своп (размер транзакции)
+const CLMM_MAX_TICK_ARRAYS: usize = 4;
+/// BinArray сверх пройденных по котировке: активный бин может сдвинуться до исполнения
+const DLMM_EXTRA_BIN_ARRAYS: usize = 1;
+/// Верхняя граница BinArray на один DLMM своп (размер транзакции)
+const DLMM_MAX_BIN_ARRAYS: usize = 4;
+
 pub struct TransactionBuilder {
     rpc_client: Arc<RpcClient>,
     keypair:    Arc<Keypair>,
     config:     Arc<BotConfig>,
     program_id: Pubkey,
+    lookup_tables: Arc<LookupTableManager>,
+    token_accounts: Arc<TokenAccountManager>,
+    blockhash_cache: Arc<BlockhashCache>,
 }
 
 /* ---------------- сериализуемые структуры ---------------- */
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
-struct ProgramSwapLeg {
+struct ProgramSubSwap {
     protocol:           u8,
     pool_id:            Pubkey,
-    input_mint:         Pubkey,
-    output_mint:        Pubkey,
     amount_in:          u64,
     minimum_amount_out: u64,
     accounts_len:       u8,
 }
 
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+struct ProgramSwapLeg {
+    input_mint:  Pubkey,
+    output_mint: Pubkey,
+    sub_swaps:   Vec<ProgramSubSwap>,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 struct ExecuteArbitrageParams {
     swap_legs:           Vec<ProgramSwapLeg>,
@@ -67,92 +93,137 @@ impl TransactionBuilder {
         rpc_client: Arc<RpcClient>,
         keypair: Arc<Keypair>,
         config:  Arc<BotConfig>,
+        lookup_tables: Arc<LookupTableManager>,
+        token_accounts: Arc<TokenAccountManager>,
+        blockhash_cache: Arc<BlockhashCache>,
     ) -> Result<Self> {
         Ok(Self {
             program_id: config.trading.executor_program_id.to_pubkey()?,
             rpc_client,
             keypair,
             config,
+            lookup_tables,
+            token_accounts,
+            blockhash_cache,
         })
     }
 
     /* ---------- публичный API ---------- */
-    pub async fn build_arbitrage_transaction(
+    /// Инструкции арбитража без ComputeBudget: ATA маршрута и execute_arbitrage.
+    /// Бюджет добавляется при компиляции (compile_with_budget) после симуляции
+    pub async fn build_arbitrage_instructions(
         &self,
         opp: &ArbitrageOpportunity,
-    ) -> Result<Transaction> {
+    ) -> Result<Vec<Instruction>> {
         info!("🔨 Строим транзакцию: {} свопов", opp.legs.len());
 
+        // Аккаунты всех пулов маршрута одним запросом
+        let pool_ids: Vec<Pubkey> = opp.legs.iter().flat_map(|leg| leg.pool_ids()).collect();
+        let pool_accounts = get_multiple_accounts_chunked(&self.rpc_client, &pool_ids).await?;
+
         /* ---------- mock-режим для devnet-fallback ---------- */
         let is_test_environment = self.config.rpc.url.contains("devnet") // Используем "devnet" в нижнем регистре
-            && opp
-            .legs
-            .iter()
-            .any(|leg| self.rpc_client.get_account(&leg.pool_id).is_err());
+            && pool_accounts.iter().any(|account| account.is_none());
 
         if is_test_environment {
+            // Mock-транзакция: только инструкции ComputeBudget
             info!("🧪 ТЕСТОВАЯ СРЕДА: возвращаем mock-транзакцию");
-
-            let mock_tx = Transaction::new_signed_with_payer(
-                &[ComputeBudgetInstruction::set_compute_unit_limit(
-                    self.config.trading.compute_unit_limit,
-                )],
-                Some(&self.keypair.pubkey()),
-                &[self.keypair.as_ref()],
-                self.latest_blockhash()?,
-            );
-
             warn!("⚠️  Пулы фиктивные – реальный RPC не выполняется");
-            return Ok(mock_tx);
+            return Ok(Vec::new());
         }
         /* ----------------------------------------------------- */
 
-        self.validate_pools_exist(opp).await?;
+        self.validate_pools_exist(opp, &pool_accounts)?;
 
-        /* ----- compute budget ----- */
-        let mut instructions = vec![
-            ComputeBudgetInstruction::set_compute_unit_limit(
-                self.config.trading.compute_unit_limit,
-            ),
-            ComputeBudgetInstruction::set_compute_unit_price(
-                self.config.trading.priority_fee_micro_lamports,
-            ),
-        ];
+        /* ----- недостающие ATA минтов маршрута ----- */
+        let mints: Vec<Pubkey> = opp.legs.iter()
+            .flat_map(|leg| [leg.input_mint, leg.output_mint])
+            .collect();
+        let mut instructions = self.token_accounts.provision(&mints).await?;
 
         /* ----- формируем legs ----- */
         let mut rem_accs = Vec::<AccountMeta>::new();
         let mut prog_legs = Vec::<ProgramSwapLeg>::new();
 
         for (idx, leg) in opp.legs.iter().enumerate() {
-            debug!("⚙️  leg #{} {:?}", idx + 1, leg.protocol);
+            // Каждый sub-swap — отдельный CPI со своим набором аккаунтов.
+            // Аккаунты sub-swap'ов запрашиваются параллельно, порядок сохраняется
+            let resolved = try_join_all(leg.sub_swaps.iter().map(|sub| {
+                debug!("⚙️  leg #{} {:?} ({})", idx + 1, sub.protocol, sub.pool_id);
+                self.accounts_for_sub_swap(leg, sub)
+            })).await?;
+
+            let mut sub_swaps = Vec::with_capacity(resolved.len());
+            for (accs, ps) in resolved {
+                rem_accs.extend(accs);
+                sub_swaps.push(ps);
+            }
 
-            let (accs, pl) = self.accounts_for_leg(leg).await?;
-            rem_accs.extend(accs);
-            prog_legs.push(pl);
+            prog_legs.push(ProgramSwapLeg {
+                input_mint:  leg.input_mint,
+                output_mint: leg.output_mint,
+                sub_swaps,
+            });
         }
 
-        instructions.push(self.make_execute_ix(prog_legs, opp.net_profit, rem_accs)?);
+        // Программа проверяет валовую дельту стартового токена: порог включает расходы
+        instructions.push(self.make_execute_ix(prog_legs, opp.onchain_min_profit(), rem_accs)?);
 
-        /* ----- финальный tx ----- */
-        let mut tx = Transaction::new_with_payer(&instructions, Some(&self.keypair.pubkey()));
-        tx.sign(&[self.keypair.as_ref()], self.latest_blockhash()?);
+        self.lookup_tables.record_usage(&instructions);
+        Ok(instructions)
+    }
 
-        Ok(tx)
+    /// Финальная v0-транзакция: ComputeBudget + инструкции арбитража, с lookup-таблицами
+    pub fn compile_with_budget(&self, instructions: &[Instruction], budget: ComputeBudget) -> Result<VersionedTransaction> {
+        let mut all = Vec::with_capacity(instructions.len() + 2);
+        all.push(ComputeBudgetInstruction::set_compute_unit_limit(budget.unit_limit));
+        all.push(ComputeBudgetInstruction::set_compute_unit_price(budget.unit_price));
+        all.extend_from_slice(instructions);
+
+        self.compile_v0(&all)
     }
 
-    /* ---------- helpers ---------- */
-    fn latest_blockhash(&self) -> Result<Hash> {
-        Ok(self.rpc_client.get_latest_blockhash()?)
+    /// Компиляция v0-транзакции с автоматически выбранными ALT и проверкой размера пакета.
+    /// Блокхеш берётся из фонового кэша, без запроса к RPC
+    fn compile_v0(&self, instructions: &[Instruction]) -> Result<VersionedTransaction> {
+        let tables = self.lookup_tables.select_for(instructions);
+
+        let message = v0::Message::try_compile(
+            &self.keypair.pubkey(),
+            instructions,
+            &tables,
+            self.blockhash_cache.latest()?.blockhash,
+        ).context("Компиляция v0-сообщения")?;
+
+        let tx = VersionedTransaction::try_new(VersionedMessage::V0(message), &[self.keypair.as_ref()])
+            .context("Подпись v0-транзакции")?;
+
+        let size = bincode::serialized_size(&tx)? as usize;
+        let static_keys = tx.message.static_account_keys().len();
+        debug!("   📦 v0: {} байт, {} статических аккаунтов, ALT: {}", size, static_keys, tables.len());
+
+        if size > PACKET_DATA_SIZE {
+            anyhow::bail!(
+                "Транзакция {} байт превышает лимит {} ({} статических аккаунтов, ALT: {}) — таблицы ещё не покрывают маршрут",
+                size, PACKET_DATA_SIZE, static_keys, tables.len()
+            );
+        }
+
+        Ok(tx)
     }
 
-    async fn validate_pools_exist(&self, opp: &ArbitrageOpportunity) -> Result<()> {
+    /* ---------- helpers ---------- */
+    /// Проверка пулов маршрута по аккаунтам, полученным в порядке `leg.pool_ids()`
+    fn validate_pools_exist(&self, opp: &ArbitrageOpportunity, accounts: &[Option<solana_sdk::account::Account>]) -> Result<()> {
+        let mut accounts = accounts.iter();
         for (i, leg) in opp.legs.iter().enumerate() {
-            let acc = self
-                .rpc_client
-                .get_account(&leg.pool_id)
-                .with_context(|| format!("RPC get_account {}", leg.pool_id))?;
-            if acc.data.is_empty() {
-                anyhow::bail!("Пул {} (leg #{}) пустой", leg.pool_id, i + 1);
+            for pool_id in leg.pool_ids() {
+                let acc = accounts.next()
+                    .and_then(Option::as_ref)
+                    .with_context(|| format!("Пул {} (leg #{}) не найден", pool_id, i + 1))?;
+                if acc.data.is_empty() {
+                    anyhow::bail!("Пул {} (leg #{}) пустой", pool_id, i + 1);
+                }
             }
         }
         Ok(())
@@ -164,61 +235,90 @@ impl TransactionBuilder {
             DexProtocol::RaydiumAmmV4 => RAYDIUM_AMM_V4,
             DexProtocol::RaydiumCpmm => RAYDIUM_CPMM,
             DexProtocol::RaydiumClmm => RAYDIUM_CLMM,
-            // Добавьте другие DEX по мере необходимости
-            _ => panic!("Неизвестный протокол DEX"),
+            DexProtocol::MeteoraDlmm => METEORA_DLMM,
         }
     }
 
-    async fn accounts_for_leg(
+    async fn accounts_for_sub_swap(
         &self,
         leg: &SwapLeg,
-    ) -> Result<(Vec<AccountMeta>, ProgramSwapLeg)> {
-        match leg.protocol {
-            DexProtocol::RaydiumAmmV4 => self.raydium_amm_v4_accounts(leg).await,
-            DexProtocol::RaydiumCpmm => self.raydium_cpmm_accounts(leg).await,
-            DexProtocol::RaydiumClmm => self.get_raydium_clmm_accounts(leg).await,
-            _ => unimplemented!("DEX {:?} не реализован", leg.protocol),
+        sub: &SubSwap,
+    ) -> Result<(Vec<AccountMeta>, ProgramSubSwap)> {
+        match sub.protocol {
+            DexProtocol::RaydiumAmmV4 => self.raydium_amm_v4_accounts(leg, sub).await,
+            DexProtocol::RaydiumCpmm => self.raydium_cpmm_accounts(leg, sub).await,
+            DexProtocol::RaydiumClmm => self.get_raydium_clmm_accounts(leg, sub).await,
+            DexProtocol::MeteoraDlmm => self.meteora_dlmm_accounts(leg, sub).await,
         }
     }
 
     async fn raydium_amm_v4_accounts(
         &self,
         leg: &SwapLeg,
-    ) -> Result<(Vec<AccountMeta>, ProgramSwapLeg)> {
-        let data = self.rpc_client.get_account(&leg.pool_id)?.data;
+        sub: &SubSwap,
+    ) -> Result<(Vec<AccountMeta>, ProgramSubSwap)> {
+        let data = self.rpc_client.get_account(&sub.pool_id).await?.data;
         let amm  = AmmInfo::try_from_slice(&data).context("decode AmmInfo")?;
 
         // ID программы DEX *не* включается в список аккаунтов для CPI
-        let dex_program_id = self.dex_program_id_for_protocol(leg.protocol);
+        let dex_program_id = self.dex_program_id_for_protocol(sub.protocol);
+        let amm_authority = amm.authority(&dex_program_id)?;
 
-        let user_src = associated_token::get_associated_token_address(&self.keypair.pubkey(), &leg.input_mint);
-        let user_dst = associated_token::get_associated_token_address(&self.keypair.pubkey(), &leg.output_mint);
+        let user_src = self.token_accounts.ata(&leg.input_mint)?;
+        let user_dst = self.token_accounts.ata(&leg.output_mint)?;
 
-        // Raydium AMM V4 требует 18 аккаунтов.
-        let accts = vec![
+        let mut accts = vec![
             // ИСПРАВЛЕНО: ВОЗВРАЩАЕМ Program ID. Это 1-й аккаунт для SC (для invoke).
             AccountMeta::new_readonly(dex_program_id, false),
 
-            // 8 стандартных аккаунтов, которые мы знаем (18 всего)
-            AccountMeta::new(leg.pool_id, false),
-            AccountMeta::new_readonly(amm.market_id, false),
-            AccountMeta::new(amm.base_vault, false),
-            AccountMeta::new(amm.quote_vault, false),
-            AccountMeta::new(user_src, false),
-            AccountMeta::new(user_dst, false),
-            AccountMeta::new_readonly(self.keypair.pubkey(), true),
-            AccountMeta::new_readonly(token::ID, false),
-            // ... здесь не хватает 10 аккаунтов для V4, но это отдельная проблема
+            AccountMeta::new_readonly(token::ID, false),             // 0. Token Program
+            AccountMeta::new(sub.pool_id, false),                    // 1. AMM
+            AccountMeta::new_readonly(amm_authority, false),         // 2. AMM Authority
         ];
 
-        let pl = ProgramSwapLeg {
-            protocol:           leg.protocol as u8,
-            pool_id:            leg.pool_id,
-            input_mint:         leg.input_mint,
-            output_mint:        leg.output_mint,
-            amount_in:          leg.amount_in,
-            minimum_amount_out: leg.minimum_amount_out,
-            accounts_len:       accts.len() as u8, // 9 аккаунтов (DEX ID + 8)
+        if self.config.dex.raydium_amm_swap_v2 {
+            // swap_base_in_v2: 8 аккаунтов, рынок OpenBook не нужен
+            accts.extend([
+                AccountMeta::new(amm.base_vault, false),             // 3. Pool Coin Vault
+                AccountMeta::new(amm.quote_vault, false),            // 4. Pool Pc Vault
+                AccountMeta::new(user_src, false),                   // 5. User Source
+                AccountMeta::new(user_dst, false),                   // 6. User Destination
+                AccountMeta::new_readonly(self.keypair.pubkey(), true), // 7. User Owner
+            ]);
+        } else {
+            // Legacy swap_base_in: 18 аккаунтов, включая рынок OpenBook
+            let market_data = self.rpc_client.get_account(&amm.market_id).await
+                .with_context(|| format!("RPC get_account рынка {}", amm.market_id))?
+                .data;
+            let market = OpenbookMarket::try_from_slice(&market_data)
+                .with_context(|| format!("Не удалось декодировать рынок OpenBook {}", amm.market_id))?;
+            let vault_signer = market.vault_signer(&amm.market_id, &amm.market_program_id)?;
+
+            accts.extend([
+                AccountMeta::new(amm.open_orders, false),            // 3. AMM Open Orders
+                AccountMeta::new(amm.target_orders, false),          // 4. AMM Target Orders
+                AccountMeta::new(amm.base_vault, false),             // 5. Pool Coin Vault
+                AccountMeta::new(amm.quote_vault, false),            // 6. Pool Pc Vault
+                AccountMeta::new_readonly(amm.market_program_id, false), // 7. Market Program
+                AccountMeta::new(amm.market_id, false),              // 8. Market
+                AccountMeta::new(market.bids, false),                // 9. Bids
+                AccountMeta::new(market.asks, false),                // 10. Asks
+                AccountMeta::new(market.event_queue, false),         // 11. Event Queue
+                AccountMeta::new(market.base_vault, false),          // 12. Market Coin Vault
+                AccountMeta::new(market.quote_vault, false),         // 13. Market Pc Vault
+                AccountMeta::new_readonly(vault_signer, false),      // 14. Market Vault Signer
+                AccountMeta::new(user_src, false),                   // 15. User Source
+                AccountMeta::new(user_dst, false),                   // 16. User Destination
+                AccountMeta::new_readonly(self.keypair.pubkey(), true), // 17. User Owner
+            ]);
+        }
+
+        let pl = ProgramSubSwap {
+            protocol:           sub.protocol as u8,
+            pool_id:            sub.pool_id,
+            amount_in:          sub.amount_in,
+            minimum_amount_out: sub.minimum_amount_out,
+            accounts_len:       accts.len() as u8, // 9 (v2) или 19 (legacy) с DEX ID
         };
 
         Ok((accts, pl))
@@ -227,12 +327,13 @@ impl TransactionBuilder {
     async fn raydium_cpmm_accounts(
         &self,
         leg: &SwapLeg,
-    ) -> Result<(Vec<AccountMeta>, ProgramSwapLeg)> {
+        sub: &SubSwap,
+    ) -> Result<(Vec<AccountMeta>, ProgramSubSwap)> {
 
-        let data = self.rpc_client.get_account(&leg.pool_id)?.data;
+        let data = self.rpc_client.get_account(&sub.pool_id).await?.data;
 
         let pool_info = CpmmPoolInfo::try_from_slice(&data)
-            .with_context(|| format!("Не удалось декодировать CpmmPoolInfo для пула {}", leg.pool_id))?;
+            .with_context(|| format!("Не удалось декодировать CpmmPoolInfo для пула {}", sub.pool_id))?;
 
         let (authority, vault_a, vault_b, mint_a) =
             (pool_info.authority, pool_info.vault_a, pool_info.vault_b, pool_info.mint_a);
@@ -244,9 +345,9 @@ impl TransactionBuilder {
             (vault_b, vault_a)
         };
 
-        let dex_program_id = self.dex_program_id_for_protocol(leg.protocol);
-        let user_src = associated_token::get_associated_token_address(&self.keypair.pubkey(), &leg.input_mint);
-        let user_dst = associated_token::get_associated_token_address(&self.keypair.pubkey(), &leg.output_mint);
+        let dex_program_id = self.dex_program_id_for_protocol(sub.protocol);
+        let user_src = self.token_accounts.ata(&leg.input_mint)?;
+        let user_dst = self.token_accounts.ata(&leg.output_mint)?;
 
         // 10 аккаунтов для CPMM (DEX ID + 9)
         let accts = vec![
@@ -254,7 +355,7 @@ impl TransactionBuilder {
             AccountMeta::new_readonly(dex_program_id, false),
 
             // 9 стандартных Raydium CPI аккаунтов (начиная со 2-го аккаунта в списке)
-            AccountMeta::new(leg.pool_id, false),                    // 1. Пул/Стейт (Mut)
+            AccountMeta::new(sub.pool_id, false),                    // 1. Пул/Стейт (Mut)
             AccountMeta::new_readonly(authority, false),             // 2. Authority пула (Readonly)
             AccountMeta::new(token_vault_in, false),                 // 3. Vault IN (Mut)
             AccountMeta::new(token_vault_out, false),                // 4. Vault OUT (Mut)
@@ -267,13 +368,11 @@ impl TransactionBuilder {
 
         let accounts_len = accts.len() as u8;
 
-        let pl = ProgramSwapLeg {
-            protocol:           leg.protocol as u8,
-            pool_id:            leg.pool_id,
-            input_mint:         leg.input_mint,
-            output_mint:        leg.output_mint,
-            amount_in:          leg.amount_in,
-            minimum_amount_out: leg.minimum_amount_out,
+        let pl = ProgramSubSwap {
+            protocol:           sub.protocol as u8,
+            pool_id:            sub.pool_id,
+            amount_in:          sub.amount_in,
+            minimum_amount_out: sub.minimum_amount_out,
             accounts_len:       accounts_len, // Теперь 10 для CPMM (1+9)
         };
 
@@ -283,63 +382,38 @@ impl TransactionBuilder {
     async fn get_raydium_clmm_accounts(
         &self,
         leg: &SwapLeg,
-    ) -> Result<(Vec<AccountMeta>, ProgramSwapLeg)> {
-        debug!("📊 Получение аккаунтов для Raydium CLMM пула: {}", leg.pool_id);
-
-        let pool_account = self.rpc_client.get_account(&leg.pool_id)?;
-        let pool_data = &pool_account.data[8..]; // Пропускаем Anchor discriminator
-
-        let amm_config = Pubkey::new_from_array(pool_data[1..33].try_into().map_err(|_| {
-            anyhow::anyhow!("Не удалось извлечь amm_config из pool data")
-        })?);
-        let authority = Pubkey::new_from_array(pool_data[33..65].try_into().map_err(|_| {
-            anyhow::anyhow!("Не удалось извлечь authority из pool data")
-        })?);
-        let token_mint_0 = Pubkey::new_from_array(pool_data[65..97].try_into().map_err(|_| {
-            anyhow::anyhow!("Не удалось извлечь token_mint_0 из pool data")
-        })?);
-        let token_mint_1 = Pubkey::new_from_array(pool_data[97..129].try_into().map_err(|_| {
-            anyhow::anyhow!("Не удалось извлечь token_mint_1 из pool data")
-        })?);
-        let token_vault_0 = Pubkey::new_from_array(pool_data[129..161].try_into().map_err(|_| {
-            anyhow::anyhow!("Не удалось извлечь token_vault_0 из pool data")
-        })?);
-        let token_vault_1 = Pubkey::new_from_array(pool_data[161..193].try_into().map_err(|_| {
-            anyhow::anyhow!("Не удалось извлечь token_vault_1 из pool data")
-        })?);
-        let observation_key = Pubkey::new_from_array(pool_data[193..225].try_into().map_err(|_| {
-            anyhow::anyhow!("Не удалось извлечь observation_key из pool data")
-        })?);
-
-        let (input_vault, output_vault) = if leg.input_mint == token_mint_0 {
-            (token_vault_0, token_vault_1)
+        sub: &SubSwap,
+    ) -> Result<(Vec<AccountMeta>, ProgramSubSwap)> {
+        debug!("📊 Получение аккаунтов для Raydium CLMM пула: {}", sub.pool_id);
+
+        // Свежее состояние пула: tick_current мог сдвинуться с момента котировки
+        let pool_account = self.rpc_client.get_account(&sub.pool_id).await?;
+        let pool = ClmmPoolState::try_from_slice(&pool_account.data)
+            .with_context(|| format!("Не удалось декодировать CLMM пул {}", sub.pool_id))?;
+
+        let zero_for_one = leg.input_mint == pool.token_mint_0;
+        let (input_vault, output_vault) = if zero_for_one {
+            (pool.token_vault_0, pool.token_vault_1)
         } else {
-            (token_vault_1, token_vault_0)
+            (pool.token_vault_1, pool.token_vault_0)
         };
 
-        let user_input_ata = associated_token::get_associated_token_address(
-            &self.keypair.pubkey(),
-            &leg.input_mint
-        );
-        let user_output_ata = associated_token::get_associated_token_address(
-            &self.keypair.pubkey(),
-            &leg.output_mint
-        );
-
-        let dex_program_id = self.dex_program_id_for_protocol(leg.protocol);
+        let user_input_ata = self.token_accounts.ata(&leg.input_mint)?;
+        let user_output_ata = self.token_accounts.ata(&leg.output_mint)?;
 
+        let dex_program_id = self.dex_program_id_for_protocol(sub.protocol);
 
         // 13 фиксированных аккаунтов для CLMM swap_v2 (согласно официальной структуре)
-        let accounts = vec![
+        let mut accounts = vec![
             // ИСПРАВЛЕНО: ВОЗВРАЩАЕМ Program ID. Это 1-й аккаунт для SC (для invoke).
             AccountMeta::new_readonly(dex_program_id, false),
 
             // 0. payer (signer) - Это наш Payer (Keypair)
             AccountMeta::new(self.keypair.pubkey(), true),
             // 1. amm_config
-            AccountMeta::new_readonly(amm_config, false),
+            AccountMeta::new_readonly(pool.amm_config, false),
             // 2. pool_state
-            AccountMeta::new(leg.pool_id, false),
+            AccountMeta::new(sub.pool_id, false),
             // 3. input_token_account (ATA пользователя)
             AccountMeta::new(user_input_ata, false),
             // 4. output_token_account (ATA пользователя)
@@ -349,7 +423,7 @@ impl TransactionBuilder {
             // 6. output_vault
             AccountMeta::new(output_vault, false),
             // 7. observation_state
-            AccountMeta::new(observation_key, false),
+            AccountMeta::new(pool.observation_key, false),
             // 8. token_program (Используем константу)
             AccountMeta::new_readonly(SPL_TOKEN_ID, false),
             // 9. token_program2022 (Используем константу)
@@ -360,24 +434,128 @@ impl TransactionBuilder {
             AccountMeta::new_readonly(leg.input_mint, false),
             // 12. output_vault_mint
             AccountMeta::new_readonly(leg.output_mint, false),
+        ];
+
+        // Remaining accounts: [bitmap extension], TickArray в порядке обхода.
+        // Пройденные по котировке массивы + запас на сдвиг цены до исполнения
+        let count = (sub.arrays_crossed as usize).max(1) + CLMM_EXTRA_TICK_ARRAYS;
+        let (starts, needs_extension) =
+            clmm_quote::swap_tick_array_starts(&pool, zero_for_one, count.min(CLMM_MAX_TICK_ARRAYS));
+        if starts.is_empty() {
+            anyhow::bail!(
+                "CLMM пул {}: нет инициализированных TickArray по направлению свопа (тик {})",
+                sub.pool_id, pool.tick_current
+            );
+        }
+
+        if needs_extension {
+            accounts.push(AccountMeta::new_readonly(
+                clmm_quote::tick_array_bitmap_extension_address(&dex_program_id, &sub.pool_id),
+                false,
+            ));
+        }
+        for start in &starts {
+            accounts.push(AccountMeta::new(
+                clmm_quote::tick_array_address(&dex_program_id, &sub.pool_id, *start),
+                false,
+            ));
+        }
+
+        debug!("   ✅ Подготовлено {} аккаунтов для Raydium CLMM (TickArray: {:?}, bitmap extension: {})",
+               accounts.len(), starts, needs_extension);
+
+        let program_sub = ProgramSubSwap {
+            protocol: sub.protocol as u8,
+            pool_id: sub.pool_id,
+            amount_in: sub.amount_in,
+            minimum_amount_out: sub.minimum_amount_out,
+            accounts_len: u8::try_from(accounts.len()).context("Слишком много аккаунтов CLMM")?,
+        };
+
+        Ok((accounts, program_sub))
+    }
+
+    async fn meteora_dlmm_accounts(
+        &self,
+        leg: &SwapLeg,
+        sub: &SubSwap,
+    ) -> Result<(Vec<AccountMeta>, ProgramSubSwap)> {
+        debug!("📊 Получение аккаунтов для Meteora DLMM пары: {}", sub.pool_id);
+
+        // Свежее состояние пары: активный бин мог сдвинуться с момента котировки
+        let pair_account = self.rpc_client.get_account(&sub.pool_id).await?;
+        let pair = DlmmLbPair::try_from_slice(&pair_account.data)
+            .with_context(|| format!("Не удалось декодировать DLMM пару {}", sub.pool_id))?;
+
+        // Token program каждого минта (SPL Token или Token-2022) — из кэша минтов маршрута
+        let token_x_program = self.token_accounts.token_program(&pair.token_x_mint)?;
+        let token_y_program = self.token_accounts.token_program(&pair.token_y_mint)?;
+
+        let swap_for_y = leg.input_mint == pair.token_x_mint;
+        let dex_program_id = self.dex_program_id_for_protocol(sub.protocol);
+
+        let user_input_ata = self.token_accounts.ata(&leg.input_mint)?;
+        let user_output_ata = self.token_accounts.ata(&leg.output_mint)?;
+
+        // BinArray в порядке обхода: пройденные по котировке + запас на сдвиг цены
+        let count = (sub.arrays_crossed as usize).max(1) + DLMM_EXTRA_BIN_ARRAYS;
+        let (indexes, needs_extension) =
+            dlmm_quote::swap_bin_array_indexes(&pair, swap_for_y, count.min(DLMM_MAX_BIN_ARRAYS));
+        if indexes.is_empty() {
+            anyhow::bail!(
+                "DLMM пара {}: нет инициализированных BinArray по направлению свопа (активный бин {})",
+                sub.pool_id, pair.active_id
+            );
+        }
+
+        // Отсутствующие опциональные аккаунты Anchor передаются как ID программы
+        let bitmap_extension = if needs_extension {
+            dlmm_quote::bin_array_bitmap_extension_address(&dex_program_id, &sub.pool_id)
+        } else {
+            dex_program_id
+        };
+
+        let mut accounts = vec![
+            // Program ID — 1-й аккаунт для SC (для invoke)
+            AccountMeta::new_readonly(dex_program_id, false),
 
-            // Remaining accounts: tick arrays (TODO: добавить динамически на основе swap размера)
-            // Для простоты пока не добавляем; в продакшене нужно вычислить и добавить 1-3 tick array PDA
+            AccountMeta::new(sub.pool_id, false),                        // 0. lb_pair
+            AccountMeta::new_readonly(bitmap_extension, false),          // 1. bin_array_bitmap_extension
+            AccountMeta::new(pair.reserve_x, false),                     // 2. reserve_x
+            AccountMeta::new(pair.reserve_y, false),                     // 3. reserve_y
+            AccountMeta::new(user_input_ata, false),                     // 4. user_token_in
+            AccountMeta::new(user_output_ata, false),                    // 5. user_token_out
+            AccountMeta::new_readonly(pair.token_x_mint, false),         // 6. token_x_mint
+            AccountMeta::new_readonly(pair.token_y_mint, false),         // 7. token_y_mint
+            AccountMeta::new(pair.oracle, false),                        // 8. oracle
+            AccountMeta::new_readonly(dex_program_id, false),            // 9. host_fee_in (нет)
+            AccountMeta::new_readonly(self.keypair.pubkey(), true),      // 10. user
+            AccountMeta::new_readonly(token_x_program, false),           // 11. token_x_program
+            AccountMeta::new_readonly(token_y_program, false),           // 12. token_y_program
+            AccountMeta::new_readonly(dlmm_quote::event_authority_address(&dex_program_id), false), // 13. event_authority
+            AccountMeta::new_readonly(dex_program_id, false),            // 14. program
         ];
 
-        debug!("   ✅ Подготовлено {} аккаунтов для Raydium CLMM (14 fixed + tick arrays TBD)", accounts.len());
+        // Remaining accounts: BinArray в порядке обхода
+        for index in &indexes {
+            accounts.push(AccountMeta::new(
+                dlmm_quote::bin_array_address(&dex_program_id, &sub.pool_id, *index),
+                false,
+            ));
+        }
+
+        debug!("   ✅ Подготовлено {} аккаунтов для Meteora DLMM (BinArray: {:?}, bitmap extension: {})",
+               accounts.len(), indexes, needs_extension);
 
-        let program_leg = ProgramSwapLeg {
-            protocol: leg.protocol as u8,
-            pool_id: leg.pool_id,
-            input_mint: leg.input_mint,
-            output_mint: leg.output_mint,
-            amount_in: leg.amount_in,
-            minimum_amount_out: leg.minimum_amount_out,
-            accounts_len: accounts.len() as u8, // Теперь 14 (1 + 13)
+        let program_sub = ProgramSubSwap {
+            protocol: sub.protocol as u8,
+            pool_id: sub.pool_id,
+            amount_in: sub.amount_in,
+            minimum_amount_out: sub.minimum_amount_out,
+            accounts_len: u8::try_from(accounts.len()).context("Слишком много аккаунтов DLMM")?,
         };
 
-        Ok((accounts, program_leg))
+        Ok((accounts, program_sub))
     }
 
     /* ---------- execute-ix ---------- */
@@ -388,8 +566,11 @@ impl TransactionBuilder {
         mut rem: Vec<AccountMeta>,
     ) -> Result<Instruction> {
         let first_mint = legs.first().context("legs empty")?.input_mint;
-        let user_ata =
-            associated_token::get_associated_token_address(&self.keypair.pubkey(), &first_mint);
+        // Программа принимает стартовый ATA только под SPL Token (Program<Token>)
+        if self.token_accounts.token_program(&first_mint)? != token::ID {
+            anyhow::bail!("Стартовый минт {} не SPL Token: программа его не примет", first_mint);
+        }
+        let user_ata = self.token_accounts.ata(&first_mint)?;
 
         let mut accs = vec![
             AccountMeta::new(self.keypair.pubkey(), true),
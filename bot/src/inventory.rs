@@ -0,0 +1,95 @@
+// bot/src/inventory.rs
+// Балансы ATA кошелька: один батч getMultipleAccounts на все mint'ы графа, кэш с TTL
+
+use anyhow::Result;
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+use crate::dex_structs::read_u64;
+use crate::executor::token_accounts::TokenAccountManager;
+use crate::utils::rpc::get_multiple_accounts_chunked;
+
+/// Время жизни кэша балансов (после исполнения кэш сбрасывается явно)
+const BALANCE_CACHE_TTL: Duration = Duration::from_secs(10);
+/// Офсет amount в SPL Token аккаунте (mint 32 + owner 32)
+pub const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+
+struct CachedBalances {
+    fetched_at: Instant,
+    /// Mint'ы, для которых запрашивались ATA
+    requested: HashSet<Pubkey>,
+    /// mint -> баланс существующего ATA
+    balances: HashMap<Pubkey, u64>,
+}
+
+pub struct WalletInventory {
+    rpc_client: Arc<RpcClient>,
+    /// Адреса ATA по token program минта (SPL Token или Token-2022)
+    token_accounts: Arc<TokenAccountManager>,
+    cache: RwLock<Option<CachedBalances>>,
+}
+
+impl WalletInventory {
+    pub fn new(rpc_client: Arc<RpcClient>, token_accounts: Arc<TokenAccountManager>) -> Self {
+        Self {
+            rpc_client,
+            token_accounts,
+            cache: RwLock::new(None),
+        }
+    }
+
+    /// Балансы существующих ATA для mint'ов (из кэша, если он свежий и покрывает запрос).
+    /// Mint без ATA в результат не попадает
+    pub async fn balances(&self, mints: &[Pubkey]) -> Result<HashMap<Pubkey, u64>> {
+        if let Ok(cache) = self.cache.read() {
+            if let Some(cached) = cache.as_ref() {
+                if cached.fetched_at.elapsed() < BALANCE_CACHE_TTL
+                    && mints.iter().all(|mint| cached.requested.contains(mint))
+                {
+                    return Ok(cached.balances.clone());
+                }
+            }
+        }
+
+        let requested: HashSet<Pubkey> = mints.iter().copied().collect();
+        let mints: Vec<Pubkey> = requested.iter().copied().collect();
+
+        // Адрес ATA зависит от token program минта; минт без аккаунта пропускается
+        self.token_accounts.resolve_token_programs(&mints).await?;
+        let (mints, atas): (Vec<Pubkey>, Vec<Pubkey>) = mints.iter()
+            .filter_map(|mint| Some((*mint, self.token_accounts.ata(mint).ok()?)))
+            .unzip();
+
+        let accounts = get_multiple_accounts_chunked(&self.rpc_client, &atas).await?;
+
+        let mut balances = HashMap::new();
+        for (mint, account) in mints.iter().zip(accounts) {
+            if let Some(account) = account {
+                balances.insert(*mint, read_u64(&account.data, TOKEN_ACCOUNT_AMOUNT_OFFSET)?);
+            }
+        }
+
+        debug!("💼 Балансы кошелька: {} ATA из {} mint'ов", balances.len(), mints.len());
+
+        if let Ok(mut cache) = self.cache.write() {
+            *cache = Some(CachedBalances {
+                fetched_at: Instant::now(),
+                requested,
+                balances: balances.clone(),
+            });
+        }
+
+        Ok(balances)
+    }
+
+    /// Сброс кэша (после отправки транзакции балансы изменились)
+    pub fn invalidate(&self) {
+        if let Ok(mut cache) = self.cache.write() {
+            *cache = None;
+        }
+    }
+}
@@ -7,21 +7,32 @@ mod scanner;
 mod arbitrage;
 mod executor;
 mod utils;
+mod oracle;
+mod inventory;
 pub mod dex_structs;
 
 use solana_sdk::signature::Signer;
 use anyhow::{Result, Context};
-use solana_client::rpc_client::RpcClient;
+use futures::future::join_all;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::commitment_config::CommitmentConfig;
 use std::sync::Arc;
+use tokio::sync::broadcast::{self, error::RecvError};
 use tokio::time::{interval, Duration};
-use tracing::{info, error, warn};
+use tracing::{debug, info, error, warn};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 use config::BotConfig;
-use scanner::{MultiDexScanner, pool_monitor::PoolMonitor};
+use scanner::{MultiDexScanner, pool_monitor::{ChangeThresholds, PoolChanged, PoolMonitor}};
 use arbitrage::ArbitrageFinder;
-use executor::TransactionExecutor;
+use arbitrage::reputation::FailureKind;
+use executor::{start_lookup_table_task, TransactionExecutor};
+use executor::outcome::ExecutionOutcome;
+use executor::simulator::SimulationFailed;
+use executor::blockhash_cache::{start_blockhash_refresh_task, BlockhashCache};
+use oracle::OracleGuard;
+use types::{ArbitrageOpportunity, PoolState};
+use inventory::WalletInventory;
 use utils::load_keypair_from_file;
 use devnet_pools::get_devnet_pools;
 
@@ -55,12 +66,12 @@ async fn main() -> Result<()> {
     ));
 
     // Проверка подключения
-    let cluster_version = rpc_client.get_version()?;
+    let cluster_version = rpc_client.get_version().await?;
     info!("   Подключено к кластеру: {} (Solana {})",
           config.rpc.url, cluster_version.solana_core);
 
     // Проверка баланса
-    let balance = rpc_client.get_balance(&keypair.pubkey())?;
+    let balance = rpc_client.get_balance(&keypair.pubkey()).await?;
     let balance_sol = balance as f64 / 1_000_000_000.0;
     info!("💰 Баланс кошелька: {:.9} SOL", balance_sol);
 
@@ -72,14 +83,27 @@ async fn main() -> Result<()> {
     // Инициализация компонентов
     info!("🔧 Инициализация компонентов бота...");
 
-    let pool_monitor = Arc::new(PoolMonitor::new(5000)); // 5 секунд TTL
+    let pool_monitor = Arc::new(PoolMonitor::new(
+        5000, // 5 секунд TTL
+        ChangeThresholds::from_config(&config.monitoring),
+    ));
+    let pool_events = pool_monitor.subscribe();
     let dex_scanner = MultiDexScanner::new(config.clone(), rpc_client.clone());
-    let arbitrage_finder = ArbitrageFinder::new(config.clone());
-    let executor = TransactionExecutor::new(
+    let arbitrage_finder = Arc::new(ArbitrageFinder::new(config.clone()));
+    // Блокхеш и слот обновляются в фоне: сборка транзакций не ждёт RPC
+    let blockhash_cache = Arc::new(BlockhashCache::new(rpc_client.clone()).await?);
+    tokio::spawn(start_blockhash_refresh_task(
+        blockhash_cache.clone(),
+        Duration::from_millis(config.rpc.blockhash_refresh_ms),
+    ));
+    let executor = Arc::new(TransactionExecutor::new(
         rpc_client.clone(),
         keypair.clone(),
         config.clone(),
-    )?;
+        blockhash_cache.clone(),
+    )?);
+    let oracle_guard = Arc::new(OracleGuard::new(config.clone(), rpc_client.clone())?);
+    let inventory = Arc::new(WalletInventory::new(rpc_client.clone(), executor.token_accounts()));
 
     info!("✅ Все компоненты инициализированы\n");
 
@@ -113,6 +137,24 @@ async fn main() -> Result<()> {
         scanner::pool_monitor::start_cache_cleanup_task(monitor_clone).await;
     });
 
+    // Lookup-таблицы: активация добавленных адресов и расширение новыми — в фоне
+    tokio::spawn(start_lookup_table_task(
+        executor.clone(),
+        Duration::from_millis(config.rpc.timeout_seconds * 1000),
+    ));
+
+    // Backrun по событиям PoolChanged — параллельно с полным проходом.
+    // События публикует полный скан: backrun опережает полный проход той же
+    // итерации только на время его подготовки (оракул, ATA, балансы)
+    tokio::spawn(run_backrun_task(
+        pool_events,
+        pool_monitor.clone(),
+        arbitrage_finder.clone(),
+        oracle_guard.clone(),
+        executor.clone(),
+        inventory.clone(),
+    ));
+
     // Главный цикл бота
     info!("🚀 Запуск главного цикла бота...");
     info!("{}", "═".repeat(80));
@@ -135,44 +177,56 @@ async fn main() -> Result<()> {
                     continue;
                 }
 
-                // Обновление кэша
+                // Снимок делится между кэшем и графом без копирования данных пулов
+                let pools: Vec<Arc<PoolState>> = pools.into_iter().map(Arc::new).collect();
+
+                // Обновление кэша; значительные изменения приходят событиями PoolChanged
+                let slot = blockhash_cache.slot();
                 for pool in &pools {
-                    pool_monitor.update_pool(pool.clone());
+                    pool_monitor.update_pool(pool.clone(), slot);
+                }
+
+                if let Err(e) = oracle_guard.refresh(slot).await {
+                    warn!("   ⚠️ Ошибка обновления цен Pyth: {}", e);
+                }
+
+                // Возврат ренты пустых промежуточных ATA
+                if let Err(e) = executor.cleanup_token_accounts().await {
+                    warn!("   ⚠️ Ошибка очистки ATA: {}", e);
+                }
+
+                // Балансы кошелька: циклы стартуют только с токенов, которые есть в ATA
+                let mut mints: Vec<_> = pools.iter()
+                    .flat_map(|pool| [pool.token_a, pool.token_b])
+                    .collect();
+                mints.sort();
+                mints.dedup();
+                match inventory.balances(&mints).await {
+                    Ok(balances) => arbitrage_finder.set_balances(balances),
+                    Err(e) => warn!("   ⚠️ Ошибка загрузки балансов кошелька: {}", e),
                 }
 
                 info!("   📊 Активных пулов: {}", pools.len());
 
-                // Шаг 2: Поиск арбитражных возможностей
-                match arbitrage_finder.find_opportunities(&pools) {
-                    Ok(opportunities) => {
-                        if opportunities.is_empty() {
+                // Шаг 2: Поиск арбитражных возможностей по всему снимку
+                // (backrun через сдвинувшиеся пулы идёт в отдельной задаче).
+                // Поиск занимает CPU на весь бюджет — воркер runtime отдаётся другим задачам
+                match tokio::task::block_in_place(|| arbitrage_finder.find_opportunities(&pools)) {
+                    Ok(found) => {
+                        if found.partial {
+                            warn!("   ⏱️ Поиск прерван дедлайном {} мс: неоценённые циклы перенесены на следующую итерацию",
+                                  config.trading.search_budget_ms);
+                        }
+
+                        // Риск-скорректированный выбор возможностей без общих пулов и стартовых токенов
+                        let selected = arbitrage_finder.select_opportunities(found.opportunities);
+                        if selected.is_empty() {
                             info!("   ⏳ Прибыльных возможностей не найдено");
                             continue;
                         }
 
-                        info!("   🔥 Найдено возможностей: {}", opportunities.len());
-
-                        // Берём лучшую возможность
-                        let best = &opportunities[0];
-                        info!("   💎 Лучшая возможность:");
-                        info!("      Прибыль: {:.9} SOL ({:.4}%)",
-                              best.net_profit as f64 / 1_000_000_000.0,
-                              best.profit_percentage);
-                        info!("      Шагов: {}", best.legs.len());
-
-                        // Шаг 3: Исполнение арбитража
-                        info!("   🔧 Исполнение арбитража...");
-                        match executor.execute(best).await {
-                            Ok(signature) => {
-                                info!("   ✅ АРБИТРАЖ УСПЕШЕН!");
-                                info!("      Транзакция: {}", signature);
-                                info!("      Explorer: https://explorer.solana.com/tx/{}?cluster=devnet",
-                                      signature);
-                            }
-                            Err(e) => {
-                                error!("   ❌ Ошибка исполнения: {}", e);
-                            }
-                        }
+                        info!("   🔥 Выбрано возможностей: {}", selected.len());
+                        execute_opportunities(&arbitrage_finder, &oracle_guard, &executor, &inventory, selected).await;
                     }
                     Err(e) => {
                         error!("   ❌ Ошибка поиска возможностей: {}", e);
@@ -186,6 +240,141 @@ async fn main() -> Result<()> {
     }
 }
 
+/// Backrun: каждое событие PoolChanged сразу обновляет пул в графе и, если граф
+/// ещё не видел это состояние, ищет циклы через него в бюджете задержки
+/// и исполняет найденное, не дожидаясь полного прохода. Реакция — с частотой
+/// скана, а не в слоте сдвига: подписки на аккаунты пулов нет
+async fn run_backrun_task(
+    mut events: broadcast::Receiver<PoolChanged>,
+    pool_monitor: Arc<PoolMonitor>,
+    arbitrage_finder: Arc<ArbitrageFinder>,
+    oracle_guard: Arc<OracleGuard>,
+    executor: Arc<TransactionExecutor>,
+    inventory: Arc<WalletInventory>,
+) {
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                warn!("   ⚠️ Пропущено {} событий PoolChanged", skipped);
+                continue;
+            }
+            Err(RecvError::Closed) => break,
+        };
+
+        let Some(pool) = pool_monitor.get_pool(&event.pool_id) else {
+            continue;
+        };
+
+        info!("   ⚡ Пул {} сдвинулся: {:.6} -> {:.6} (слот {})",
+              event.pool_id, event.old_price, event.new_price, event.slot);
+
+        let backrun = tokio::task::block_in_place(|| -> Result<_> {
+            // Граф уже содержит это состояние: его циклы оценил полный проход
+            if !arbitrage_finder.update_pool(&pool)? {
+                return Ok(None);
+            }
+            arbitrage_finder.find_opportunities_through(&pool.id).map(Some)
+        });
+        let selected = match backrun {
+            Ok(Some(found)) => arbitrage_finder.select_opportunities(found.opportunities),
+            Ok(None) => {
+                debug!("   Пул {} уже синхронизирован полным проходом", pool.id);
+                continue;
+            }
+            Err(e) => {
+                warn!("   ⚠️ Ошибка backrun через {}: {}", pool.id, e);
+                continue;
+            }
+        };
+        if selected.is_empty() {
+            continue;
+        }
+
+        // Подтверждение занимает секунды: следующие события не ждут его
+        let (finder, oracle, executor, inventory) =
+            (arbitrage_finder.clone(), oracle_guard.clone(), executor.clone(), inventory.clone());
+        tokio::spawn(async move {
+            execute_opportunities(&finder, &oracle, &executor, &inventory, selected).await;
+        });
+    }
+}
+
+/// Захват пулов и стартовых токенов, проверка оракулом, параллельное исполнение и учёт итогов
+/// выбранных (без общих пулов и стартовых токенов) возможностей
+async fn execute_opportunities(
+    arbitrage_finder: &ArbitrageFinder,
+    oracle_guard: &OracleGuard,
+    executor: &TransactionExecutor,
+    inventory: &WalletInventory,
+    selected: Vec<ArbitrageOpportunity>,
+) {
+    let mut validated = Vec::with_capacity(selected.len());
+    for opportunity in &selected {
+        // Цикл через исполняемые пулы или с занятого ATA уже отправлен другим проходом
+        let Some(claim) = arbitrage_finder.claim(opportunity) else {
+            info!("   ⏭️ Пропуск возможности: её пулы или стартовый токен уже исполняются");
+            continue;
+        };
+
+        info!("   💎 Возможность (оценка {:.0}):", opportunity.score);
+        info!("      Прибыль: {:.9} SOL ({:.4}%)",
+              opportunity.net_profit_lamports as f64 / 1_000_000_000.0,
+              opportunity.profit_percentage);
+        info!("      Шагов: {}", opportunity.legs.len());
+
+        // Проверка курсов ног по оракулу: битые данные пула дают фантомную прибыль
+        if let Err(e) = oracle_guard.validate(opportunity) {
+            warn!("   🛡️ Отклонено оракулом: {}", e);
+            arbitrage_finder.record_failure(opportunity, FailureKind::Other);
+            continue;
+        }
+        validated.push((opportunity, claim));
+    }
+
+    if validated.is_empty() {
+        return;
+    }
+
+    // Шаг 3: Исполнение арбитража. Захваченные возможности не делят ни пулы,
+    // ни ATA стартового токена (в том числе с другими проходами),
+    // поэтому симуляция и подтверждение идут параллельно
+    info!("   🔧 Исполнение арбитража: {}", validated.len());
+    let results = join_all(validated.iter().map(|(opportunity, _)| executor.execute(opportunity))).await;
+    inventory.invalidate();
+
+    // Пулы и ATA освобождаются после учёта итога (claim живёт до конца итерации)
+    for ((opportunity, _claim), result) in validated.into_iter().zip(results) {
+        match result {
+            Ok(outcome @ ExecutionOutcome::Landed { .. }) => {
+                arbitrage_finder.record_success(opportunity);
+                info!("   ✅ АРБИТРАЖ УСПЕШЕН: {}", outcome);
+                info!("      Транзакция: {}", outcome.signature());
+                info!("      Explorer: https://explorer.solana.com/tx/{}?cluster=devnet",
+                      outcome.signature());
+            }
+            Ok(outcome) => match outcome.failure_kind() {
+                // Откат в блоке: котировка цикла не подтвердилась
+                Some(kind) => {
+                    error!("   ❌ Транзакция {}: {}", outcome.signature(), outcome);
+                    arbitrage_finder.record_failure(opportunity, kind);
+                }
+                // Истёкший блокхеш или потеря транзакции не говорят о цикле
+                None => warn!("   ⚠️ Транзакция {}: {}", outcome.signature(), outcome),
+            },
+            Err(e) => match e.downcast_ref::<SimulationFailed>() {
+                // Симуляцию отклонила программа — неудача цикла
+                Some(failed) => {
+                    error!("   ❌ Ошибка исполнения: {}", e);
+                    arbitrage_finder.record_failure(opportunity, failed.failure_kind());
+                }
+                // RPC, Jito, кэш блокхеша: инфраструктура, цикл на кулдаун не ставится
+                None => error!("   ❌ Ошибка исполнения (инфраструктура): {:#}", e),
+            },
+        }
+    }
+}
+
 /// Инициализация системы логирования
 fn init_logging() {
     let log_level = std::env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string());
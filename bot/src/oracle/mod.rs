@@ -0,0 +1,204 @@
+// bot/src/oracle/mod.rs
+// Проверка курсов пулов по оракулу Pyth перед исполнением:
+// неверный офсет декодера или манипулированный пул выглядят как огромная «прибыль»
+
+pub mod pyth;
+
+use anyhow::{Context, Result};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tracing::{debug, info, warn};
+
+use crate::config::BotConfig;
+use crate::types::ArbitrageOpportunity;
+use crate::utils::rpc::get_multiple_accounts_chunked;
+use pyth::{check_sub_swap, PythPrice};
+
+pub struct OracleGuard {
+    rpc_client: Arc<RpcClient>,
+    /// mint -> аккаунт цены Pyth
+    feeds: HashMap<Pubkey, Pubkey>,
+    max_deviation_bps: u16,
+    max_staleness_slots: u64,
+    /// Последние свежие цены по mint
+    prices: RwLock<HashMap<Pubkey, PythPrice>>,
+}
+
+impl OracleGuard {
+    pub fn new(config: Arc<BotConfig>, rpc_client: Arc<RpcClient>) -> Result<Self> {
+        let mut feeds = HashMap::new();
+        for (mint, account) in &config.oracle.pyth_feeds {
+            feeds.insert(
+                mint.to_pubkey().context("Invalid PYTH_FEEDS mint")?,
+                account.to_pubkey().context("Invalid PYTH_FEEDS price account")?,
+            );
+        }
+
+        if feeds.is_empty() {
+            info!("🛡️ Фиды Pyth не заданы — проверка по оракулу отключена");
+        }
+
+        Ok(Self {
+            rpc_client,
+            feeds,
+            max_deviation_bps: config.oracle.max_deviation_bps,
+            max_staleness_slots: config.oracle.max_staleness_slots,
+            prices: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Загрузка цен всех фидов одним батчем. Возвращает количество свежих цен
+    pub async fn refresh(&self, current_slot: u64) -> Result<usize> {
+        if self.feeds.is_empty() {
+            return Ok(0);
+        }
+
+        let (mints, accounts): (Vec<Pubkey>, Vec<Pubkey>) =
+            self.feeds.iter().map(|(mint, account)| (*mint, *account)).unzip();
+
+        let fetched = get_multiple_accounts_chunked(&self.rpc_client, &accounts).await?;
+
+        let mut fresh = HashMap::new();
+        for ((mint, address), account) in mints.iter().zip(&accounts).zip(fetched) {
+            let Some(account) = account else {
+                warn!("⚠️ Аккаунт цены Pyth {} не найден", address);
+                continue;
+            };
+
+            match PythPrice::try_from_slice(&account.data) {
+                Ok(price) if current_slot.saturating_sub(price.slot) <= self.max_staleness_slots => {
+                    fresh.insert(*mint, price);
+                }
+                Ok(price) => {
+                    debug!("   Цена Pyth {} устарела: слот {} (текущий {})",
+                           address, price.slot, current_slot);
+                }
+                Err(e) => {
+                    warn!("⚠️ Не удалось декодировать цену Pyth {}: {}", address, e);
+                }
+            }
+        }
+
+        let count = fresh.len();
+        if let Ok(mut prices) = self.prices.write() {
+            *prices = fresh;
+        }
+        Ok(count)
+    }
+
+    /// Проверка всех ног, у которых оба токена имеют свежую цену оракула.
+    /// Ошибка — нога через пул, курс которого расходится с оракулом
+    pub fn validate(&self, opportunity: &ArbitrageOpportunity) -> Result<()> {
+        let prices = self.prices.read()
+            .map_err(|_| anyhow::anyhow!("Кэш цен оракула отравлен"))?;
+
+        for leg in &opportunity.legs {
+            let (Some(price_in), Some(price_out)) =
+                (prices.get(&leg.input_mint), prices.get(&leg.output_mint))
+            else {
+                continue;
+            };
+
+            // Каждый пул разбиения проверяется отдельно: битый пул не прячется в среднем
+            for sub in &leg.sub_swaps {
+                let Some(check) = check_sub_swap(leg, sub, price_in, price_out, self.max_deviation_bps) else {
+                    continue;
+                };
+
+                if !check.is_ok() {
+                    anyhow::bail!(
+                        "Курс пула {} расходится с Pyth на {:.0} bps (допуск {:.0} bps)",
+                        sub.pool_id,
+                        check.deviation_bps,
+                        check.tolerance_bps
+                    );
+                }
+
+                debug!("   🛡️ Пул {}: отклонение от Pyth {:.0} bps", sub.pool_id, check.deviation_bps);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{DexProtocol, SubSwap, SwapLeg};
+
+    const SOL: PythPrice = PythPrice { price: 15_000_000_000, conf: 0, expo: -8, slot: 0 };
+    const USDC: PythPrice = PythPrice { price: 100_000_000, conf: 0, expo: -8, slot: 0 };
+
+    fn oracle_guard(prices: &[(Pubkey, PythPrice)]) -> OracleGuard {
+        let config = Arc::new(BotConfig::for_tests());
+        let rpc_client = Arc::new(RpcClient::new(config.rpc.url.clone()));
+        let guard = OracleGuard::new(config, rpc_client).unwrap();
+        *guard.prices.write().unwrap() = prices.iter().copied().collect();
+        guard
+    }
+
+    fn sub_swap(pool_id: Pubkey, spot_rate: f64) -> SubSwap {
+        SubSwap {
+            protocol: DexProtocol::RaydiumCpmm,
+            pool_id,
+            amount_in: 1_000_000_000,
+            minimum_amount_out: 0,
+            estimated_amount_out: 150_000_000,
+            fee_bps: 25,
+            spot_rate,
+            pool_state_data: vec![],
+            arrays_crossed: 0,
+        }
+    }
+
+    /// Нога SOL (9 decimals) -> USDC (6 decimals), разбитая по пулам с данными mid-курсами
+    fn opportunity(sol: Pubkey, usdc: Pubkey, spot_rates: &[f64]) -> ArbitrageOpportunity {
+        let sub_swaps: Vec<SubSwap> = spot_rates.iter()
+            .map(|&rate| sub_swap(Pubkey::new_unique(), rate))
+            .collect();
+        ArbitrageOpportunity {
+            legs: vec![SwapLeg {
+                pool_id: sub_swaps[0].pool_id,
+                input_mint: sol,
+                output_mint: usdc,
+                input_decimals: 9,
+                output_decimals: 6,
+                amount_in: 1_000_000_000,
+                minimum_amount_out: 0,
+                estimated_amount_out: 150_000_000,
+                sub_swaps,
+            }],
+            initial_amount: 1_000_000_000,
+            expected_final_amount: 1_000_000_000,
+            gross_profit: 0,
+            net_profit: 0,
+            net_profit_lamports: 0,
+            min_net_profit: 0,
+            profit_percentage: 0.0,
+            discovered_at: 0,
+            oldest_pool_update: 0,
+            score: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_validate_compares_each_pool_with_oracle() {
+        let (sol, usdc) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let guard = oracle_guard(&[(sol, SOL), (usdc, USDC)]);
+
+        // Mid-курс 151 USDC за SOL — в пределах 300 bps
+        assert!(guard.validate(&opportunity(sol, usdc, &[0.151])).is_ok());
+
+        // Битый второй пул разбиения не прячется за честным первым
+        let broken = opportunity(sol, usdc, &[0.15, 0.3]);
+        let error = guard.validate(&broken).unwrap_err().to_string();
+        assert!(error.contains(&broken.legs[0].sub_swaps[1].pool_id.to_string()));
+
+        // Без цены одного из токенов нога не проверяется
+        let unpriced = oracle_guard(&[(sol, SOL)]);
+        assert!(unpriced.validate(&broken).is_ok());
+    }
+}
@@ -0,0 +1,231 @@
+// bot/src/oracle/pyth.rs
+// Декодирование аккаунтов цены Pyth и сравнение курса ноги с оракулом
+
+use anyhow::{anyhow, Result};
+
+use crate::dex_structs::{read_i32, read_i64, read_u32, read_u64};
+use crate::types::{SubSwap, SwapLeg};
+
+/// Legacy push-оракул (аккаунт Price v2)
+const PYTH_MAGIC: u32 = 0xa1b2_c3d4;
+const PYTH_ACCOUNT_TYPE_PRICE: u32 = 3;
+const PYTH_STATUS_TRADING: u32 = 1;
+const PYTH_MAGIC_OFFSET: usize = 0;
+const PYTH_ACCOUNT_TYPE_OFFSET: usize = 8;
+const PYTH_EXPO_OFFSET: usize = 20;
+const PYTH_AGG_PRICE_OFFSET: usize = 208;
+const PYTH_AGG_CONF_OFFSET: usize = 216;
+const PYTH_AGG_STATUS_OFFSET: usize = 224;
+const PYTH_AGG_PUB_SLOT_OFFSET: usize = 232;
+const PYTH_PRICE_ACCOUNT_MIN_LEN: usize = 240;
+
+/// Pull-оракул (PriceUpdateV2 программы pyth-solana-receiver)
+const PRICE_UPDATE_V2_DISCRIMINATOR: [u8; 8] = [34, 241, 35, 99, 157, 126, 244, 205];
+/// discriminator (8) + write_authority (32)
+const PRICE_UPDATE_V2_VERIFICATION_OFFSET: usize = 40;
+const VERIFICATION_LEVEL_FULL: u8 = 1;
+/// PriceFeedMessage: feed_id (32), price, conf, exponent, publish_time,
+/// prev_publish_time, ema_price, ema_conf; за ним posted_slot
+const MESSAGE_PRICE_OFFSET: usize = 32;
+const MESSAGE_CONF_OFFSET: usize = 40;
+const MESSAGE_EXPO_OFFSET: usize = 48;
+const MESSAGE_POSTED_SLOT_OFFSET: usize = 84;
+
+/// Агрегированная цена Pyth: price × 10^expo (USD за UI-единицу токена)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PythPrice {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    /// Слот публикации (legacy) или записи обновления (pull)
+    pub slot: u64,
+}
+
+impl PythPrice {
+    /// Декодирование legacy Price аккаунта или PriceUpdateV2
+    pub fn try_from_slice(data: &[u8]) -> Result<Self> {
+        if data.starts_with(&PRICE_UPDATE_V2_DISCRIMINATOR) {
+            return Self::from_price_update_v2(data);
+        }
+
+        if data.len() < PYTH_PRICE_ACCOUNT_MIN_LEN {
+            return Err(anyhow!(
+                "Недостаточно данных для Pyth Price: {} байт, требуется минимум {}",
+                data.len(),
+                PYTH_PRICE_ACCOUNT_MIN_LEN
+            ));
+        }
+        if read_u32(data, PYTH_MAGIC_OFFSET)? != PYTH_MAGIC {
+            return Err(anyhow!("Аккаунт не является аккаунтом Pyth"));
+        }
+        if read_u32(data, PYTH_ACCOUNT_TYPE_OFFSET)? != PYTH_ACCOUNT_TYPE_PRICE {
+            return Err(anyhow!("Аккаунт Pyth не является аккаунтом цены"));
+        }
+        if read_u32(data, PYTH_AGG_STATUS_OFFSET)? != PYTH_STATUS_TRADING {
+            return Err(anyhow!("Цена Pyth не в статусе Trading"));
+        }
+
+        Ok(Self {
+            price: read_i64(data, PYTH_AGG_PRICE_OFFSET)?,
+            conf: read_u64(data, PYTH_AGG_CONF_OFFSET)?,
+            expo: read_i32(data, PYTH_EXPO_OFFSET)?,
+            slot: read_u64(data, PYTH_AGG_PUB_SLOT_OFFSET)?,
+        })
+    }
+
+    fn from_price_update_v2(data: &[u8]) -> Result<Self> {
+        // VerificationLevel: Partial { num_signatures: u8 } (2 байта) | Full (1 байт)
+        let level = *data.get(PRICE_UPDATE_V2_VERIFICATION_OFFSET)
+            .ok_or_else(|| anyhow!("Недостаточно данных для PriceUpdateV2"))?;
+        if level != VERIFICATION_LEVEL_FULL {
+            return Err(anyhow!("PriceUpdateV2 без полной верификации"));
+        }
+        let message = PRICE_UPDATE_V2_VERIFICATION_OFFSET + 1;
+
+        Ok(Self {
+            price: read_i64(data, message + MESSAGE_PRICE_OFFSET)?,
+            conf: read_u64(data, message + MESSAGE_CONF_OFFSET)?,
+            expo: read_i32(data, message + MESSAGE_EXPO_OFFSET)?,
+            slot: read_u64(data, message + MESSAGE_POSTED_SLOT_OFFSET)?,
+        })
+    }
+
+    /// Цена в USD за UI-единицу
+    pub fn value(&self) -> f64 {
+        self.price as f64 * 10f64.powi(self.expo)
+    }
+
+    /// Доверительный интервал относительно цены
+    pub fn relative_confidence(&self) -> f64 {
+        if self.price <= 0 {
+            return f64::INFINITY;
+        }
+        self.conf as f64 / self.price as f64
+    }
+}
+
+/// Результат сравнения курса ноги с оракулом (в bps)
+#[derive(Debug, Clone, Copy)]
+pub struct PriceCheck {
+    pub deviation_bps: f64,
+    /// Порог + доверительные интервалы обеих цен
+    pub tolerance_bps: f64,
+}
+
+impl PriceCheck {
+    pub fn is_ok(&self) -> bool {
+        self.deviation_bps <= self.tolerance_bps
+    }
+}
+
+/// Сравнение mid-курса пула sub-swap'а (out/in в UI-единицах, без комиссии
+/// и price impact) с курсом оракула price_in / price_out. Курс исполнения
+/// не годится: на крупном объёме его законно сдвигает impact.
+/// None — сравнивать не с чем
+pub fn check_sub_swap(
+    leg: &SwapLeg,
+    sub: &SubSwap,
+    price_in: &PythPrice,
+    price_out: &PythPrice,
+    max_deviation_bps: u16,
+) -> Option<PriceCheck> {
+    if !sub.spot_rate.is_finite() || sub.spot_rate <= 0.0 || price_in.price <= 0 || price_out.price <= 0 {
+        return None;
+    }
+
+    let implied = sub.spot_rate * 10f64.powi(leg.input_decimals as i32 - leg.output_decimals as i32);
+    let oracle = price_in.value() / price_out.value();
+
+    let confidence_bps = (price_in.relative_confidence() + price_out.relative_confidence()) * 10_000.0;
+
+    Some(PriceCheck {
+        deviation_bps: (implied / oracle - 1.0).abs() * 10_000.0,
+        tolerance_bps: max_deviation_bps as f64 + confidence_bps,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::DexProtocol;
+    use solana_sdk::pubkey::Pubkey;
+
+    fn legacy_account(price: i64, conf: u64, expo: i32, status: u32) -> Vec<u8> {
+        let mut data = vec![0u8; 3312];
+        data[PYTH_MAGIC_OFFSET..4].copy_from_slice(&PYTH_MAGIC.to_le_bytes());
+        data[PYTH_ACCOUNT_TYPE_OFFSET..12].copy_from_slice(&PYTH_ACCOUNT_TYPE_PRICE.to_le_bytes());
+        data[PYTH_EXPO_OFFSET..24].copy_from_slice(&expo.to_le_bytes());
+        data[PYTH_AGG_PRICE_OFFSET..216].copy_from_slice(&price.to_le_bytes());
+        data[PYTH_AGG_CONF_OFFSET..224].copy_from_slice(&conf.to_le_bytes());
+        data[PYTH_AGG_STATUS_OFFSET..228].copy_from_slice(&status.to_le_bytes());
+        data[PYTH_AGG_PUB_SLOT_OFFSET..240].copy_from_slice(&42u64.to_le_bytes());
+        data
+    }
+
+    /// Нога SOL (9 decimals) -> USDC (6 decimals) через один пул
+    fn leg(spot_rate: f64, amount_in: u64, amount_out: u64) -> SwapLeg {
+        let pool_id = Pubkey::new_unique();
+        SwapLeg {
+            pool_id,
+            input_mint: Pubkey::new_unique(),
+            output_mint: Pubkey::new_unique(),
+            input_decimals: 9,
+            output_decimals: 6,
+            amount_in,
+            minimum_amount_out: 0,
+            estimated_amount_out: amount_out,
+            sub_swaps: vec![SubSwap {
+                protocol: DexProtocol::RaydiumCpmm,
+                pool_id,
+                amount_in,
+                minimum_amount_out: 0,
+                estimated_amount_out: amount_out,
+                fee_bps: 25,
+                spot_rate,
+                pool_state_data: vec![],
+                arrays_crossed: 0,
+            }],
+        }
+    }
+
+    fn check(leg: &SwapLeg, price_in: &PythPrice, price_out: &PythPrice) -> PriceCheck {
+        check_sub_swap(leg, &leg.sub_swaps[0], price_in, price_out, 100).unwrap()
+    }
+
+    #[test]
+    fn test_decode_legacy_and_pull_accounts() {
+        let price = PythPrice::try_from_slice(&legacy_account(15_000_000_000, 7_500_000, -8, 1)).unwrap();
+        assert_eq!(price.slot, 42);
+        assert!((price.value() - 150.0).abs() < 1e-9);
+        assert!(PythPrice::try_from_slice(&legacy_account(15_000_000_000, 0, -8, 0)).is_err());
+
+        let mut update = PRICE_UPDATE_V2_DISCRIMINATOR.to_vec();
+        update.resize(134, 0);
+        update[PRICE_UPDATE_V2_VERIFICATION_OFFSET] = VERIFICATION_LEVEL_FULL;
+        let message = PRICE_UPDATE_V2_VERIFICATION_OFFSET + 1;
+        update[message + MESSAGE_PRICE_OFFSET..][..8].copy_from_slice(&100_000_000i64.to_le_bytes());
+        update[message + MESSAGE_EXPO_OFFSET..][..4].copy_from_slice(&(-8i32).to_le_bytes());
+        update[message + MESSAGE_POSTED_SLOT_OFFSET..][..8].copy_from_slice(&7u64.to_le_bytes());
+        let price = PythPrice::try_from_slice(&update).unwrap();
+        assert_eq!((price.value(), price.slot), (1.0, 7));
+    }
+
+    #[test]
+    fn test_leg_deviation_with_confidence() {
+        let sol = PythPrice { price: 15_000_000_000, conf: 15_000_000, expo: -8, slot: 0 };
+        let usdc = PythPrice { price: 100_000_000, conf: 0, expo: -8, slot: 0 };
+
+        // Mid-цена пула 150.5 USDC за SOL — в пределах порога
+        let fair = check(&leg(0.1505, 1_000_000_000, 149_625_000), &sol, &usdc);
+        assert!(fair.is_ok());
+        assert!((fair.tolerance_bps - 110.0).abs() < 1e-6);
+
+        // Крупный объём: impact опускает курс исполнения до 120, mid-цена честная
+        let deep = check(&leg(0.15, 1_000_000_000_000, 120_000_000_000), &sol, &usdc);
+        assert!(deep.is_ok());
+
+        // Кривой декодер: пул «платит» 300 USDC за SOL
+        let broken = check(&leg(0.3, 1_000_000_000, 300_000_000), &sol, &usdc);
+        assert!(!broken.is_ok());
+    }
+}
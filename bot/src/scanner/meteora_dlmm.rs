@@ -1,18 +1,28 @@
 // bot/src/scanner/meteora_dlmm.rs
-use anyhow::Result;
+use anyhow::{Result, Context};
 use serde::Deserialize;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
 use std::str::FromStr;
 use tracing::{info, warn};
 use std::sync::Arc;
 
+use crate::arbitrage::dlmm_quote::{bin_array_address, initialized_bin_array_indexes};
 use crate::config::BotConfig;
-use crate::types::{PoolState, DexProtocol};
+use crate::dex_structs::{DlmmLbPair, DLMM_LB_PAIR_MIN_LEN};
+use crate::types::{PoolState, DexProtocol, LiquidityAccount};
+use crate::utils::rpc::get_multiple_accounts_chunked;
 use super::DexScanner;
 
+/// Сколько инициализированных BinArray загружать в каждую сторону от активного бина
+const BIN_ARRAYS_PER_SIDE: usize = 2;
+
 #[derive(Clone)]
 pub struct MeteoraDlmmScanner {
+    rpc_client: Arc<RpcClient>,
     config: Arc<BotConfig>,
+    program_id: Pubkey,
 }
 
 #[derive(Deserialize)]
@@ -26,8 +36,15 @@ struct MeteoraPair {
 }
 
 impl MeteoraDlmmScanner {
-    pub fn new(config: Arc<BotConfig>) -> Self {
-        Self { config }
+    pub fn new(config: Arc<BotConfig>, rpc_client: Arc<RpcClient>) -> Result<Self> {
+        let program_id = config.dex.meteora_dlmm.to_pubkey()
+            .context("Некорректный Meteora DLMM program ID")?;
+
+        Ok(Self {
+            rpc_client,
+            config,
+            program_id,
+        })
     }
 
     fn convert_api_pool_to_pool_state(&self, api_pool: MeteoraPair) -> Result<PoolState> {
@@ -50,8 +67,63 @@ impl MeteoraDlmmScanner {
             full_state_data: Vec::new(),
             decimals_a: 9,
             decimals_b: 9,
+            liquidity_accounts: Vec::new(),
         })
     }
+
+    /// ПАКЕТНАЯ загрузка LbPair и BinArray вокруг активного бина для котировки
+    async fn fetch_liquidity_accounts_batch(&self, pools: &mut Vec<PoolState>) -> Result<()> {
+        let pool_ids: Vec<Pubkey> = pools.iter().map(|p| p.id).collect();
+
+        // Шаг 1: состояние пар (активный бин, параметры комиссии, битмап массивов)
+        let accounts = get_multiple_accounts_chunked(&self.rpc_client, &pool_ids).await?;
+
+        for (pool, account) in pools.iter_mut().zip(accounts) {
+            let Some(account) = account else { continue };
+            if account.data.len() >= DLMM_LB_PAIR_MIN_LEN {
+                pool.full_state_data = account.data;
+            }
+        }
+
+        // Пары без состояния не котируются — отбрасываем
+        pools.retain(|p| !p.full_state_data.is_empty());
+
+        // Шаг 2: BinArray вокруг активного бина
+        let mut keys = Vec::new();
+        let mut key_to_pools: HashMap<Pubkey, Vec<usize>> = HashMap::new();
+
+        for (pool_idx, pool) in pools.iter().enumerate() {
+            let Ok(pair) = DlmmLbPair::try_from_slice(&pool.full_state_data) else {
+                continue;
+            };
+
+            for index in initialized_bin_array_indexes(&pair, BIN_ARRAYS_PER_SIDE) {
+                let key = bin_array_address(&self.program_id, &pool.id, index);
+                let owners = key_to_pools.entry(key).or_default();
+                if owners.is_empty() {
+                    keys.push(key);
+                }
+                owners.push(pool_idx);
+            }
+        }
+
+        info!("📊 Загрузка {} BinArray аккаунтов DLMM", keys.len());
+
+        let accounts = get_multiple_accounts_chunked(&self.rpc_client, &keys).await?;
+
+        for (key, account) in keys.iter().zip(accounts) {
+            let Some(account) = account else { continue };
+
+            for &pool_idx in &key_to_pools[key] {
+                pools[pool_idx].liquidity_accounts.push(LiquidityAccount {
+                    pubkey: *key,
+                    data: account.data.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[async_trait::async_trait]
@@ -84,6 +156,8 @@ impl DexScanner for MeteoraDlmmScanner {
             }
         }
 
+        self.fetch_liquidity_accounts_batch(&mut pools).await?;
+
         info!("✅ Meteora DLMM: найдено {} валидных пулов", pools.len());
         Ok(pools)
     }
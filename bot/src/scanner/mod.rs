@@ -9,7 +9,7 @@ use futures::future::join_all;
 use anyhow::Result;
 use async_trait::async_trait;
 use tracing::{info, warn, error};
-use solana_client::rpc_client::RpcClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
 
 use crate::config::BotConfig;
 use crate::types::{PoolState, DexProtocol};
@@ -40,7 +40,7 @@ impl MultiDexScanner {
             Box::new(RaydiumAmmScanner::new(config.clone(), rpc_client.clone()).unwrap()),
             Box::new(RaydiumCpmmScanner::new(config.clone(), rpc_client.clone()).unwrap()),
             Box::new(RaydiumClmmScanner::new(config.clone(), rpc_client.clone()).unwrap()),
-            Box::new(MeteoraDlmmScanner::new(config.clone())), // [cite: 73]
+            Box::new(MeteoraDlmmScanner::new(config.clone(), rpc_client.clone()).unwrap()), // [cite: 73]
         ];
         Self {
             scanners,
@@ -71,10 +71,18 @@ impl MultiDexScanner {
             warn!("🧪 Devnet пулы не загружены, сканирование по сети"); // [cite: 79]
         }
 
-        // ... (остальной код функции) [cite: 79-84]
-        // ...
-        let all_pools = Vec::new(); // [cite: 79]
-        // ...
+        // Все сканеры работают одновременно: каждый ждёт своих RPC-ответов,
+        // не блокируя остальных
+        let results = join_all(self.scanners.iter().map(|scanner| scanner.scan_pools())).await;
+
+        let mut all_pools = Vec::new();
+        for (scanner, result) in self.scanners.iter().zip(results) {
+            match result {
+                Ok(pools) => all_pools.extend(pools),
+                Err(e) => warn!("⚠️ Ошибка сканирования {:?}: {}", scanner.protocol(), e),
+            }
+        }
+
         if is_devnet && all_pools.is_empty() { // [cite: 81]
             if let Some(cached) = self.cached_test_pools.lock().unwrap().as_ref() {
                 return Ok(cached.clone()); // [cite: 82]
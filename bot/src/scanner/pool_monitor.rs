@@ -5,54 +5,128 @@
 use dashmap::DashMap;
 use solana_sdk::pubkey::Pubkey;
 use std::sync::Arc;
+use tokio::sync::broadcast;
 use tokio::time::{interval, Duration};
 use tracing::{info, debug};
 
-use crate::types::PoolState;
+use crate::arbitrage::pool_math::spot_price;
+use crate::config::MonitoringConfig;
+use crate::dex_structs::ClmmPoolState;
+use crate::types::{DexProtocol, PoolState};
+
+/// Ёмкость канала событий (отстающий подписчик получает Lagged)
+const EVENT_CHANNEL_CAPACITY: usize = 4096;
+
+/// Событие значительного изменения пула. Единственный источник состояний —
+/// полный скан (scan_all_dex раз в rpc.timeout_seconds), поэтому события
+/// приходят с частотой скана, а не в слоте изменения пула
+#[derive(Debug, Clone)]
+pub struct PoolChanged {
+    pub pool_id: Pubkey,
+    /// Mid-цена до и после: атомов token_b за атом token_a (см. pool_math::spot_price)
+    pub old_price: f64,
+    pub new_price: f64,
+    /// Слот, в котором наблюдалось новое состояние
+    pub slot: u64,
+}
+
+/// Пороги значимости изменения пула (%)
+#[derive(Debug, Clone, Copy)]
+pub struct ChangeThresholds {
+    /// Изменение цены
+    pub price_change_pct: f64,
+    /// Изменение ликвидности без сдвига цены: любой из резервов, у CLMM — активная ликвидность
+    pub reserve_change_pct: f64,
+}
+
+impl ChangeThresholds {
+    pub fn from_config(config: &MonitoringConfig) -> Self {
+        Self {
+            price_change_pct: config.pool_change_price_pct,
+            reserve_change_pct: config.pool_change_reserve_pct,
+        }
+    }
+}
 
 /// Кэш состояний пулов с автообновлением
 pub struct PoolMonitor {
-    /// Кэш пулов: pool_id -> PoolState
-    cache: Arc<DashMap<Pubkey, PoolState>>,
+    /// Кэш пулов: pool_id -> PoolState (общий со снимком сканера и графом)
+    cache: Arc<DashMap<Pubkey, Arc<PoolState>>>,
 
     /// Интервал обновления в миллисекундах
     update_interval_ms: u64,
+
+    /// Пороги публикации PoolChanged
+    thresholds: ChangeThresholds,
+
+    /// Канал событий изменения пулов
+    events: broadcast::Sender<PoolChanged>,
 }
 
 impl PoolMonitor {
-    pub fn new(update_interval_ms: u64) -> Self {
+    pub fn new(update_interval_ms: u64, thresholds: ChangeThresholds) -> Self {
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+
         Self {
             cache: Arc::new(DashMap::new()),
             update_interval_ms,
+            thresholds,
+            events,
         }
     }
 
-    /// Обновление состояния пула
-    pub fn update_pool(&self, pool: PoolState) {
+    /// Подписка на события изменения пулов
+    pub fn subscribe(&self) -> broadcast::Receiver<PoolChanged> {
+        self.events.subscribe()
+    }
+
+    /// Обновление состояния пула, наблюдённого в слоте `slot`.
+    /// При значительном изменении публикуется PoolChanged
+    pub fn update_pool(&self, pool: Arc<PoolState>, slot: u64) {
         let pool_id = pool.id;
 
         // Проверяем значительность изменения
-        if let Some(old_pool) = self.cache.get(&pool_id) {
-            let price_change = self.calculate_price_change(&old_pool, &pool);
+        let event = self.cache.get(&pool_id).and_then(|old_pool| {
+            // Цена из декодированного состояния: у CLMM и DLMM резервы не отражают цену
+            let old_price = spot_price(&old_pool).unwrap_or(0.0);
+            let new_price = spot_price(&pool).unwrap_or(0.0);
+            let price_change = Self::calculate_price_change(old_price, new_price);
+            let reserve_change = Self::calculate_reserve_change(&old_pool, &pool);
 
-            if price_change > 0.5 {
-                debug!("Значительное изменение цены в пуле {}: {:.2}%",
-                       pool_id, price_change);
+            if price_change < self.thresholds.price_change_pct
+                && reserve_change < self.thresholds.reserve_change_pct
+            {
+                return None;
             }
-        }
+
+            debug!("Значительное изменение пула {}: цена {:.2}%, ликвидность {:.2}%",
+                   pool_id, price_change, reserve_change);
+
+            Some(PoolChanged {
+                pool_id,
+                old_price,
+                new_price,
+                slot,
+            })
+        });
 
         self.cache.insert(pool_id, pool);
+
+        if let Some(event) = event {
+            // Ошибка только при отсутствии подписчиков — событие просто некому доставить
+            let _ = self.events.send(event);
+        }
     }
 
     /// Получение всех пулов из кэша
-    pub fn get_all_pools(&self) -> Vec<PoolState> {
+    pub fn get_all_pools(&self) -> Vec<Arc<PoolState>> {
         self.cache.iter()
             .map(|entry| entry.value().clone())
             .collect()
     }
 
     /// Получение конкретного пула
-    pub fn get_pool(&self, pool_id: &Pubkey) -> Option<PoolState> {
+    pub fn get_pool(&self, pool_id: &Pubkey) -> Option<Arc<PoolState>> {
         self.cache.get(pool_id).map(|entry| entry.value().clone())
     }
 
@@ -62,10 +136,7 @@ impl PoolMonitor {
     }
 
     /// Расчёт изменения цены в процентах
-    fn calculate_price_change(&self, old: &PoolState, new: &PoolState) -> f64 {
-        let old_price = old.price_a_to_b();
-        let new_price = new.price_a_to_b();
-
+    fn calculate_price_change(old_price: f64, new_price: f64) -> f64 {
         if old_price == 0.0 {
             return 0.0;
         }
@@ -73,6 +144,24 @@ impl PoolMonitor {
         ((new_price - old_price) / old_price).abs() * 100.0
     }
 
+    /// Максимальное изменение ликвидности в процентах: у CLMM — активная
+    /// ликвидность из PoolState (резервы не заполняются), у остальных — резервы
+    fn calculate_reserve_change(old: &PoolState, new: &PoolState) -> f64 {
+        let change = |old: f64, new: f64| {
+            if old == 0.0 {
+                return 0.0;
+            }
+            (new - old).abs() / old * 100.0
+        };
+
+        if let (Some(old_liquidity), Some(new_liquidity)) = (clmm_liquidity(old), clmm_liquidity(new)) {
+            return change(old_liquidity, new_liquidity);
+        }
+
+        change(old.reserve_a as f64, new.reserve_a as f64)
+            .max(change(old.reserve_b as f64, new.reserve_b as f64))
+    }
+
     /// Очистка устаревших пулов (старше threshold_seconds)
     pub fn cleanup_stale_pools(&self, threshold_seconds: i64) {
         let now = chrono::Utc::now().timestamp();
@@ -84,6 +173,14 @@ impl PoolMonitor {
     }
 }
 
+/// Активная ликвидность CLMM пула
+fn clmm_liquidity(pool: &PoolState) -> Option<f64> {
+    if pool.protocol != DexProtocol::RaydiumClmm {
+        return None;
+    }
+    ClmmPoolState::try_from_slice(&pool.full_state_data).ok().map(|state| state.liquidity as f64)
+}
+
 /// Фоновая задача периодической очистки кэша
 pub async fn start_cache_cleanup_task(monitor: Arc<PoolMonitor>) {
     let mut cleanup_interval = interval(Duration::from_secs(60)); // Каждую минуту
@@ -100,4 +197,100 @@ pub async fn start_cache_cleanup_task(monitor: Arc<PoolMonitor>) {
                   count_before - count_after);
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arbitrage::clmm_quote::sqrt_price_at_tick;
+
+    fn pool(id: Pubkey, reserve_a: u64, reserve_b: u64) -> PoolState {
+        PoolState {
+            id,
+            protocol: DexProtocol::RaydiumCpmm,
+            token_a: Pubkey::new_unique(),
+            token_b: Pubkey::new_unique(),
+            reserve_a,
+            reserve_b,
+            fee_bps: 25,
+            last_updated: 0,
+            full_state_data: vec![],
+            decimals_a: 9,
+            decimals_b: 9,
+            liquidity_accounts: vec![],
+        }
+    }
+
+    #[test]
+    fn test_publishes_only_significant_changes() {
+        let monitor = PoolMonitor::new(5000, ChangeThresholds {
+            price_change_pct: 0.5,
+            reserve_change_pct: 2.0,
+        });
+        let mut events = monitor.subscribe();
+        let id = Pubkey::new_unique();
+
+        monitor.update_pool(Arc::new(pool(id, 1_000_000, 1_000_000)), 1);
+        monitor.update_pool(Arc::new(pool(id, 1_001_000, 1_000_000)), 2); // 0.1% — ниже порогов
+        monitor.update_pool(Arc::new(pool(id, 1_015_000, 1_015_000)), 3); // цена почти та же, резервы +1.5%
+        assert!(events.try_recv().is_err());
+
+        monitor.update_pool(Arc::new(pool(id, 1_030_000, 1_000_000)), 4);
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.pool_id, id);
+        assert_eq!(event.slot, 4);
+        // Токена A стало больше — он дешевеет в B
+        assert!(event.new_price < event.old_price);
+
+        monitor.update_pool(Arc::new(pool(id, 1_100_000, 1_100_000)), 5); // резервы +6.8%, цена -3%
+        assert_eq!(events.try_recv().unwrap().slot, 5);
+    }
+
+    #[test]
+    fn test_clmm_sqrt_price_move_publishes_change() {
+        let monitor = PoolMonitor::new(5000, ChangeThresholds {
+            price_change_pct: 0.5,
+            reserve_change_pct: 2.0,
+        });
+        let mut events = monitor.subscribe();
+        let id = Pubkey::new_unique();
+        let (mint_0, mint_1) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        // Резервы CLMM не заполняются: цена только из sqrt_price_x64
+        let clmm = |tick: i32| {
+            let state = ClmmPoolState {
+                amm_config: Pubkey::new_unique(),
+                token_mint_0: mint_0,
+                token_mint_1: mint_1,
+                token_vault_0: Pubkey::new_unique(),
+                token_vault_1: Pubkey::new_unique(),
+                observation_key: Pubkey::new_unique(),
+                mint_decimals_0: 9,
+                mint_decimals_1: 9,
+                tick_spacing: 10,
+                liquidity: 1_000_000_000,
+                sqrt_price_x64: sqrt_price_at_tick(tick).unwrap(),
+                tick_current: tick,
+                tick_array_bitmap: [0; 16],
+            };
+            PoolState {
+                protocol: DexProtocol::RaydiumClmm,
+                token_a: mint_0,
+                token_b: mint_1,
+                reserve_a: 0,
+                reserve_b: 0,
+                full_state_data: state.to_account_data(),
+                ..pool(id, 0, 0)
+            }
+        };
+
+        monitor.update_pool(Arc::new(clmm(0)), 1);
+        monitor.update_pool(Arc::new(clmm(20)), 2); // +0.2% — ниже порога
+        assert!(events.try_recv().is_err());
+
+        monitor.update_pool(Arc::new(clmm(100)), 3); // +80 тиков: цена ×1.0001^80 ≈ +0.8%
+        let event = events.try_recv().unwrap();
+        assert_eq!(event.slot, 3);
+        assert!((event.new_price / event.old_price - 1.0001f64.powi(80)).abs() < 1e-9);
+    }
+}
@@ -1,7 +1,7 @@
 // bot/src/scanner/raydium_amm.rs
 use anyhow::{Result, Context};
 use rayon::prelude::*;
-use solana_client::rpc_client::RpcClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
 use solana_client::rpc_filter::RpcFilterType;
 use solana_sdk::commitment_config::CommitmentConfig;
@@ -15,6 +15,7 @@ use tracing::{info, warn, debug};
 use crate::config::BotConfig;
 use crate::dex_structs::AmmInfo;
 use crate::types::{DexProtocol, PoolState};
+use crate::utils::rpc::get_multiple_accounts_chunked;
 use super::DexScanner;
 
 #[derive(Clone)]
@@ -68,13 +69,14 @@ impl RaydiumAmmScanner {
             fee_bps: (amm_info.fees.swap_fee_numerator * 10000 / amm_info.fees.swap_fee_denominator) as u16,
             last_updated: chrono::Utc::now().timestamp(),
             full_state_data: data.to_vec(),
-            decimals_a: 9, // ДОБАВЛЕНО
-            decimals_b: 9, // ДОБАВЛЕНО
+            decimals_a: amm_info.base_decimals,
+            decimals_b: amm_info.quote_decimals,
+            liquidity_accounts: Vec::new(),
         })
     }
 
     /// ПАКЕТНОЕ получение резервов vault'ов
-    fn fetch_vault_reserves_batch(&self, pools: &mut [PoolState]) -> Result<()> {
+    async fn fetch_vault_reserves_batch(&self, pools: &mut [PoolState]) -> Result<()> {
         if pools.is_empty() {
             return Ok(());
         }
@@ -100,8 +102,8 @@ impl RaydiumAmmScanner {
 
         info!("📊 Получение резервов для {} vault'ов", vault_keys.len());
 
-        // ПАКЕТНЫЕ запросы по 100 аккаунтов
-        let vault_accounts = self.get_multiple_accounts_batch(&vault_keys)?;
+        // ПАКЕТНЫЕ запросы по 100 аккаунтов (параллельно)
+        let vault_accounts = get_multiple_accounts_chunked(&self.rpc_client, &vault_keys).await?;
 
         // Обновляем резервы в pools
         for (vault_key, account_opt) in vault_keys.iter().zip(vault_accounts.iter()) {
@@ -118,19 +120,6 @@ impl RaydiumAmmScanner {
 
         Ok(())
     }
-
-    /// Пакетный запрос аккаунтов с разбивкой на чанки по 100
-    fn get_multiple_accounts_batch(&self, keys: &[Pubkey]) -> Result<Vec<Option<solana_sdk::account::Account>>> {
-        const BATCH_SIZE: usize = 100;
-        let mut all_accounts = Vec::with_capacity(keys.len());
-
-        for chunk in keys.chunks(BATCH_SIZE) {
-            let accounts = self.rpc_client.get_multiple_accounts(chunk)?;
-            all_accounts.extend(accounts);
-        }
-
-        Ok(all_accounts)
-    }
 }
 
 #[async_trait::async_trait]
@@ -157,6 +146,7 @@ impl DexScanner for RaydiumAmmScanner {
 
         let accounts = self.rpc_client
             .get_program_accounts_with_config(&self.program_id, config)
+            .await
             .context("Ошибка получения аккаунтов программы")?;
 
         info!("   📊 Найдено {} потенциальных аккаунтов пулов", accounts.len());
@@ -179,7 +169,7 @@ impl DexScanner for RaydiumAmmScanner {
 
         // ПАКЕТНОЕ получение резервов
         if !pools.is_empty() {
-            self.fetch_vault_reserves_batch(&mut pools)?;
+            self.fetch_vault_reserves_batch(&mut pools).await?;
 
             // Фильтруем пулы с нулевыми резервами
             pools.retain(|pool| pool.reserve_a > 0 && pool.reserve_b > 0);
@@ -1,18 +1,25 @@
 // bot/src/scanner/raydium_clmm.rs
 use anyhow::{Result, Context};
 use rayon::prelude::*;
-use solana_client::rpc_client::RpcClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
 use solana_client::rpc_filter::{RpcFilterType, Memcmp, MemcmpEncodedBytes};
 use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::pubkey::Pubkey;
 use std::sync::Arc;
+use std::collections::HashMap;
 use tracing::{info, warn};
 
+use crate::arbitrage::clmm_quote::{initialized_tick_array_starts, tick_array_address};
 use crate::config::BotConfig;
-use crate::types::{PoolState, DexProtocol};
+use crate::dex_structs::{ClmmAmmConfig, ClmmPoolState, CLMM_AMM_CONFIG_LEN};
+use crate::types::{PoolState, DexProtocol, LiquidityAccount};
+use crate::utils::rpc::get_multiple_accounts_chunked;
 use super::DexScanner;
 
+/// Сколько инициализированных TickArray загружать в каждую сторону от текущего тика
+const TICK_ARRAYS_PER_SIDE: usize = 3;
+
 #[derive(Clone)]
 pub struct RaydiumClmmScanner {
     rpc_client: Arc<RpcClient>,
@@ -20,45 +27,6 @@ pub struct RaydiumClmmScanner {
     program_id: Pubkey,
 }
 
-// Упрощенная CLMM структура для парсинга ключевых полей
-#[derive(Debug)]
-struct ClmmPoolInfo {
-    token_mint_0: Pubkey,
-    token_mint_1: Pubkey,
-    token_vault_0: Pubkey,
-    token_vault_1: Pubkey,
-    tick_spacing: u16,
-    liquidity: u128,
-}
-
-impl ClmmPoolInfo {
-    /// Парсинг CLMM pool из сырых данных (упрощенная версия)
-    fn try_from_slice(data: &[u8]) -> Result<Self> {
-        if data.len() < 400 {
-            return Err(anyhow::anyhow!("Недостаточно данных для CLMM pool"));
-        }
-
-        // Примерные офсеты для CLMM (нужно уточнить по IDL)
-        let token_mint_0 = crate::dex_structs::read_pubkey(data, 72)?;
-        let token_mint_1 = crate::dex_structs::read_pubkey(data, 104)?;
-        let token_vault_0 = crate::dex_structs::read_pubkey(data, 136)?;
-        let token_vault_1 = crate::dex_structs::read_pubkey(data, 168)?;
-        let tick_spacing = u16::from_le_bytes([data[200], data[201]]);
-        let liquidity = u128::from_le_bytes(
-            data[300..316].try_into().unwrap_or([0u8; 16])
-        );
-
-        Ok(Self {
-            token_mint_0,
-            token_mint_1,
-            token_vault_0,
-            token_vault_1,
-            tick_spacing,
-            liquidity,
-        })
-    }
-}
-
 impl RaydiumClmmScanner {
     pub fn new(config: Arc<BotConfig>, rpc_client: Arc<RpcClient>) -> Result<Self> {
         let program_id = config.dex.raydium_clmm.to_pubkey()
@@ -74,7 +42,7 @@ impl RaydiumClmmScanner {
     }
 
     fn parse_clmm_pool(&self, pool_id: Pubkey, data: &[u8]) -> Result<PoolState> {
-        let pool_info = ClmmPoolInfo::try_from_slice(data)?;
+        let pool_info = ClmmPoolState::try_from_slice(data)?;
 
         Ok(PoolState {
             id: pool_id,
@@ -83,13 +51,61 @@ impl RaydiumClmmScanner {
             token_b: pool_info.token_mint_1,
             reserve_a: 0, // Будет получено из vault'ов
             reserve_b: 0,
-            fee_bps: 30, // Типичная комиссия CLMM (0.3%)
+            fee_bps: 30, // Уточняется из AmmConfig в fetch_liquidity_accounts_batch
             last_updated: chrono::Utc::now().timestamp(),
             full_state_data: data.to_vec(),
-            decimals_a: 9,
-            decimals_b: 9,
+            decimals_a: pool_info.mint_decimals_0,
+            decimals_b: pool_info.mint_decimals_1,
+            liquidity_accounts: Vec::new(),
         })
     }
+
+    /// ПАКЕТНАЯ загрузка AmmConfig и TickArray вокруг tick_current для котировки
+    async fn fetch_liquidity_accounts_batch(&self, pools: &mut [PoolState]) -> Result<()> {
+        let mut keys = Vec::new();
+        let mut key_to_pools: HashMap<Pubkey, Vec<usize>> = HashMap::new();
+
+        for (pool_idx, pool) in pools.iter().enumerate() {
+            let Ok(state) = ClmmPoolState::try_from_slice(&pool.full_state_data) else {
+                continue;
+            };
+
+            let tick_arrays = initialized_tick_array_starts(&state, TICK_ARRAYS_PER_SIDE)
+                .into_iter()
+                .map(|start| tick_array_address(&self.program_id, &pool.id, start));
+
+            for key in std::iter::once(state.amm_config).chain(tick_arrays) {
+                let owners = key_to_pools.entry(key).or_default();
+                if owners.is_empty() {
+                    keys.push(key);
+                }
+                owners.push(pool_idx);
+            }
+        }
+
+        info!("📊 Загрузка {} AmmConfig/TickArray аккаунтов CLMM", keys.len());
+
+        let accounts = get_multiple_accounts_chunked(&self.rpc_client, &keys).await?;
+
+        for (key, account) in keys.iter().zip(accounts) {
+            let Some(account) = account else { continue };
+
+            for &pool_idx in &key_to_pools[key] {
+                let pool = &mut pools[pool_idx];
+                if account.data.len() == CLMM_AMM_CONFIG_LEN {
+                    if let Ok(config) = ClmmAmmConfig::try_from_slice(&account.data) {
+                        pool.fee_bps = (config.trade_fee_rate / 100) as u16;
+                    }
+                }
+                pool.liquidity_accounts.push(LiquidityAccount {
+                    pubkey: *key,
+                    data: account.data.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[async_trait::async_trait]
@@ -118,12 +134,13 @@ impl DexScanner for RaydiumClmmScanner {
 
         let accounts = self.rpc_client
             .get_program_accounts_with_config(&self.program_id, config)
+            .await
             .context("Ошибка получения CLMM аккаунтов")?;
 
         info!("   📊 Найдено {} потенциальных CLMM пулов", accounts.len());
 
         // Параллельный парсинг
-        let pools: Vec<PoolState> = accounts
+        let mut pools: Vec<PoolState> = accounts
             .par_iter()
             .filter_map(|(pubkey, account)| {
                 match self.parse_clmm_pool(*pubkey, &account.data) {
@@ -136,6 +153,8 @@ impl DexScanner for RaydiumClmmScanner {
             })
             .collect();
 
+        self.fetch_liquidity_accounts_batch(&mut pools).await?;
+
         info!("✅ Raydium CLMM: найдено {} пулов", pools.len());
         Ok(pools)
     }
@@ -1,18 +1,23 @@
 // bot/src/scanner/raydium_cpmm.rs
 use anyhow::{Result, Context};
 use rayon::prelude::*;
-use solana_client::rpc_client::RpcClient;
+use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_client::rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig};
 use solana_client::rpc_filter::RpcFilterType;
 use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::pubkey::Pubkey;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::{info, warn};
 
 use crate::config::BotConfig;
 use crate::types::{PoolState, DexProtocol};
+use crate::utils::rpc::get_multiple_accounts_chunked;
 use super::DexScanner;
 
+/// Офсет amount в аккаунте токена (одинаков для SPL Token и Token-2022)
+const TOKEN_ACCOUNT_AMOUNT_OFFSET: usize = 64;
+
 #[derive(Clone)]
 pub struct RaydiumCpmmScanner {
     rpc_client: Arc<RpcClient>,
@@ -82,8 +87,44 @@ impl RaydiumCpmmScanner {
             full_state_data: data.to_vec(),
             decimals_a: 9,
             decimals_b: 9,
+            liquidity_accounts: Vec::new(),
         })
     }
+
+    /// ПАКЕТНОЕ получение резервов из vault'ов: без них пул не котируется
+    async fn fetch_vault_reserves_batch(&self, pools: &mut [PoolState]) -> Result<()> {
+        let mut vault_keys = Vec::new();
+        let mut pool_vault_map = HashMap::new();
+
+        for (pool_idx, pool) in pools.iter().enumerate() {
+            if let Ok(pool_info) = CpmmPoolInfo::try_from_slice(&pool.full_state_data) {
+                vault_keys.push(pool_info.token_0_vault);
+                vault_keys.push(pool_info.token_1_vault);
+
+                pool_vault_map.insert(pool_info.token_0_vault, (pool_idx, true));
+                pool_vault_map.insert(pool_info.token_1_vault, (pool_idx, false));
+            }
+        }
+
+        vault_keys.sort();
+        vault_keys.dedup();
+
+        let vault_accounts = get_multiple_accounts_chunked(&self.rpc_client, &vault_keys).await?;
+
+        for (vault_key, account_opt) in vault_keys.iter().zip(vault_accounts.iter()) {
+            if let (Some(account), Some((pool_idx, is_token_0))) = (account_opt, pool_vault_map.get(vault_key)) {
+                if let Ok(amount) = crate::dex_structs::read_u64(&account.data, TOKEN_ACCOUNT_AMOUNT_OFFSET) {
+                    if *is_token_0 {
+                        pools[*pool_idx].reserve_a = amount;
+                    } else {
+                        pools[*pool_idx].reserve_b = amount;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[async_trait::async_trait]
@@ -111,11 +152,12 @@ impl DexScanner for RaydiumCpmmScanner {
 
         let accounts = self.rpc_client
             .get_program_accounts_with_config(&self.program_id, config)
+            .await
             .context("Ошибка получения CPMM аккаунтов")?;
 
         info!("   📊 Найдено {} потенциальных CPMM пулов", accounts.len());
 
-        let pools: Vec<PoolState> = accounts
+        let mut pools: Vec<PoolState> = accounts
             .par_iter()
             .filter_map(|(pubkey, account)| {
                 match self.parse_cpmm_pool(*pubkey, &account.data) {
@@ -128,6 +170,11 @@ impl DexScanner for RaydiumCpmmScanner {
             })
             .collect();
 
+        if !pools.is_empty() {
+            self.fetch_vault_reserves_batch(&mut pools).await?;
+            pools.retain(|pool| pool.reserve_a > 0 && pool.reserve_b > 0);
+        }
+
         info!("✅ Raydium CPMM: найдено {} пулов", pools.len());
         Ok(pools)
     }
@@ -2,7 +2,7 @@
 // Shared types and structures
 
 use serde::{Deserialize, Serialize};
-use solana_sdk::pubkey::Pubkey;
+use solana_sdk::{pubkey::Pubkey, transaction::TransactionError};
 use std::fmt;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -38,6 +38,15 @@ pub struct PoolState {
     pub full_state_data: Vec<u8>,
     pub decimals_a: u8,
     pub decimals_b: u8,
+    /// Дополнительные аккаунты для точной котировки (AmmConfig, TickArray и т.п.)
+    pub liquidity_accounts: Vec<LiquidityAccount>,
+}
+
+/// Сырые данные вспомогательного аккаунта пула
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LiquidityAccount {
+    pub pubkey: Pubkey,
+    pub data: Vec<u8>,
 }
 
 impl PoolState {
@@ -56,18 +65,44 @@ impl PoolState {
     }
 }
 
-/// Single swap leg in arbitrage route
+/// Swap through a single pool within a hop
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct SwapLeg {
+pub struct SubSwap {
     pub protocol: DexProtocol,
     pub pool_id: Pubkey,
-    pub input_mint: Pubkey,
-    pub output_mint: Pubkey,
     pub amount_in: u64,
     pub minimum_amount_out: u64,
     pub estimated_amount_out: u64,
     pub fee_bps: u16,
+    /// Mid-курс пула на момент котировки (атомов выхода за атом входа,
+    /// без комиссии и price impact); 0.0 — не определён
+    pub spot_rate: f64,
     pub pool_state_data: Vec<u8>,
+    /// Массивы ликвидности (TickArray/BinArray), пройденные свопом по котировке
+    pub arrays_crossed: u8,
+}
+
+/// Single swap leg (hop) in arbitrage route, split across parallel pools
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SwapLeg {
+    /// Пул цикла для этого перехода (подпись цикла и репутация)
+    pub pool_id: Pubkey,
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub input_decimals: u8,
+    pub output_decimals: u8,
+    /// Суммы по всем sub-swap'ам перехода
+    pub amount_in: u64,
+    pub minimum_amount_out: u64,
+    pub estimated_amount_out: u64,
+    pub sub_swaps: Vec<SubSwap>,
+}
+
+impl SwapLeg {
+    /// Все пулы, через которые реально идёт переход
+    pub fn pool_ids(&self) -> impl Iterator<Item = Pubkey> + '_ {
+        self.sub_swaps.iter().map(|sub| sub.pool_id)
+    }
 }
 
 /// Complete arbitrage opportunity
@@ -76,15 +111,32 @@ pub struct ArbitrageOpportunity {
     pub legs: Vec<SwapLeg>,
     pub initial_amount: u64,
     pub expected_final_amount: u64,
-    pub gross_profit: u64,
-    pub net_profit: u64,
+    /// Валовая и чистая прибыль в атомах стартового токена (убыток < 0)
+    pub gross_profit: i64,
+    pub net_profit: i64,
+    /// Чистая прибыль, нормализованная к SOL (lamports)
+    pub net_profit_lamports: i64,
+    /// Минимальная чистая прибыль (trading.min_profit_lamports) в атомах стартового токена
+    pub min_net_profit: i64,
     pub profit_percentage: f64,
     pub discovered_at: i64,
+    /// Время обновления самого старого пула цикла (unix, сек)
+    pub oldest_pool_update: i64,
+    /// Риск-скорректированная оценка (заполняется OpportunityScorer)
+    pub score: f64,
 }
 
 impl ArbitrageOpportunity {
-    pub fn is_profitable(&self, min_profit: u64) -> bool {
-        self.net_profit >= min_profit
+    /// Чистая прибыль в lamports не ниже порога (min_profit_lamports)
+    pub fn is_profitable(&self, min_profit_lamports: u64) -> bool {
+        self.net_profit_lamports >= 0 && self.net_profit_lamports as u64 >= min_profit_lamports
+    }
+
+    /// Порог min_profit для программы. Она сравнивает его с валовой дельтой
+    /// стартового токена, поэтому к минимальной чистой прибыли прибавляются
+    /// оценённые расходы в атомах токена (gross - net)
+    pub fn onchain_min_profit(&self) -> u64 {
+        (self.gross_profit - self.net_profit + self.min_net_profit).max(0) as u64
     }
 }
 
@@ -95,12 +147,14 @@ pub struct PriceEdge {
     pub to_token: Pubkey,
     pub pool_id: Pubkey,
     pub protocol: DexProtocol,
-    pub weight: f64, // -log(exchange_rate) for Bellman-Ford
+    pub weight: f64, // -log(effective_rate) for Bellman-Ford
     pub fee_bps: u16,
+    /// Эталонный объём (в атомах from_token), по которому посчитан вес
+    pub reference_amount: u64,
 }
 
 pub struct SimulationResult {
-    pub err: Option<String>,
+    pub err: Option<TransactionError>,
     pub logs: Vec<String>,
     pub units_consumed: Option<u64>,
 }
\ No newline at end of file
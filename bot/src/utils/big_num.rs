@@ -0,0 +1,41 @@
+// bot/src/utils/big_num.rs
+// Широкие целые для Q64.64 математики (CLMM/DLMM), как в on-chain программах
+#![allow(clippy::assign_op_pattern, clippy::ptr_offset_with_cast, clippy::manual_range_contains, clippy::manual_div_ceil)]
+
+use uint::construct_uint;
+
+construct_uint! {
+    /// 512-битное беззнаковое целое для промежуточных произведений Q64.64
+    pub struct U512(8);
+}
+
+impl U512 {
+    /// Обратное преобразование в u128 с проверкой переполнения
+    pub fn try_to_u128(self) -> Option<u128> {
+        if self > U512::from(u128::MAX) {
+            None
+        } else {
+            Some(self.as_u128())
+        }
+    }
+}
+
+/// floor(a * b / denominator) без потери точности
+pub fn mul_div_floor(a: u128, b: u128, denominator: u128) -> Option<u128> {
+    if denominator == 0 {
+        return None;
+    }
+    (U512::from(a) * U512::from(b) / U512::from(denominator)).try_to_u128()
+}
+
+/// ceil(a * b / denominator) без потери точности
+pub fn mul_div_ceil(a: u128, b: u128, denominator: u128) -> Option<u128> {
+    if denominator == 0 {
+        return None;
+    }
+    let denominator = U512::from(denominator);
+    let product = U512::from(a) * U512::from(b);
+    let (quotient, remainder) = product.div_mod(denominator);
+    let quotient = if remainder.is_zero() { quotient } else { quotient + U512::one() };
+    quotient.try_to_u128()
+}
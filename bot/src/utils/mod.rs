@@ -1,4 +1,5 @@
 // bot/src/utils/mod.rs
+pub mod big_num;
 pub mod math;
 pub mod rpc;
 
@@ -2,15 +2,18 @@
 // Утилиты для работы с RPC клиентами Solana
 
 use anyhow::Result;
+use futures::{stream, StreamExt, TryStreamExt};
 use solana_client::{
-    rpc_client::RpcClient,
+    nonblocking::rpc_client::RpcClient,
     rpc_config::RpcSendTransactionConfig,
     // УДАЛИТЕ неиспользуемые импорты:
     // rpc_config::{RpcSendTransactionConfig, RpcSimulateTransactionConfig},
     // client_error::ClientError,
 };
 use solana_sdk::{
+    account::Account,
     commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
     transaction::Transaction,
     signature::Signature,
 };
@@ -18,6 +21,26 @@ use std::time::Duration;
 use tokio::time::sleep;
 use tracing::{warn, debug};
 
+/// Лимит аккаунтов в одном запросе getMultipleAccounts
+pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
+/// Одновременных запросов при пакетной загрузке аккаунтов
+const MAX_CONCURRENT_REQUESTS: usize = 8;
+
+/// getMultipleAccounts для любого числа ключей: чанки по 100 запрашиваются
+/// параллельно, порядок результата совпадает с порядком ключей
+pub async fn get_multiple_accounts_chunked(
+    rpc_client: &RpcClient,
+    keys: &[Pubkey],
+) -> Result<Vec<Option<Account>>> {
+    let chunks: Vec<Vec<Option<Account>>> = stream::iter(keys.chunks(MAX_MULTIPLE_ACCOUNTS))
+        .map(|chunk| rpc_client.get_multiple_accounts(chunk))
+        .buffered(MAX_CONCURRENT_REQUESTS)
+        .try_collect()
+        .await?;
+
+    Ok(chunks.into_iter().flatten().collect())
+}
+
 /// Конфигурация ретраев для RPC запросов
 pub struct RetryConfig {
     pub max_retries: usize,
@@ -76,7 +99,7 @@ impl MultiRpcClient {
 
         // Попытка через primary RPC
         for attempt in 0..self.retry_config.max_retries {
-            match self.primary.send_transaction_with_config(transaction, config) {
+            match self.primary.send_transaction_with_config(transaction, config).await {
                 Ok(signature) => {
                     debug!("Транзакция отправлена: {} (попытка {})", signature, attempt + 1);
                     return Ok(signature);
@@ -95,7 +118,7 @@ impl MultiRpcClient {
 
         // Попытка через fallback RPCs
         for (idx, fallback) in self.fallbacks.iter().enumerate() {
-            match fallback.send_transaction_with_config(transaction, config) {
+            match fallback.send_transaction_with_config(transaction, config).await {
                 Ok(signature) => {
                     debug!("Транзакция отправлена через fallback #{}: {}", idx + 1, signature);
                     return Ok(signature);
@@ -122,7 +145,7 @@ impl MultiRpcClient {
         let mut last_error = None;
 
         for attempt in 0..self.retry_config.max_retries {
-            match self.primary.simulate_transaction(transaction) {
+            match self.primary.simulate_transaction(transaction).await {
                 Ok(result) => return Ok(result.value),
                 Err(e) => {
                     warn!("Симуляция провалилась (попытка {}): {}", attempt + 1, e);
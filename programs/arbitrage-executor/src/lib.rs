@@ -49,19 +49,38 @@ impl DexProtocol {
     }
 }
 
-/// Single swap leg in multi-hop route
+/// Maximum number of parallel pools (CPIs) feeding one hop
+pub const MAX_SUB_SWAPS_PER_LEG: usize = 3;
+
+/// Swap through a single pool within a hop
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
-pub struct SwapLeg {
+pub struct SubSwap {
     pub protocol: DexProtocol,
     pub pool_id: Pubkey,
-    pub input_mint: Pubkey,
-    pub output_mint: Pubkey,
     pub amount_in: u64,
     pub minimum_amount_out: u64,
     /// Number of accounts needed for CPI (extracted from remaining_accounts)
     pub accounts_len: u8,
 }
 
+/// Single swap leg (hop) in multi-hop route, split across parallel pools
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct SwapLeg {
+    pub input_mint: Pubkey,
+    pub output_mint: Pubkey,
+    pub sub_swaps: Vec<SubSwap>,
+}
+
+impl SwapLeg {
+    /// Total input of the hop across all sub-swaps
+    pub fn amount_in(&self) -> Result<u64> {
+        self.sub_swaps
+            .iter()
+            .try_fold(0_u64, |total, sub| total.checked_add(sub.amount_in))
+            .ok_or_else(|| error!(ArbitrageError::MathOverflow))
+    }
+}
+
 // ============================================================================
 // ERRORS
 // ============================================================================
@@ -88,6 +107,8 @@ pub enum ArbitrageError {
     CpiCallFailed,
     #[msg("Invalid token account")]
     InvalidTokenAccount,
+    #[msg("Invalid number of sub-swaps in a leg (1..=3)")]
+    TooManySubSwaps,
 }
 
 // ============================================================================
@@ -133,9 +154,17 @@ pub mod arbitrage_executor {
         let initial_balance = ctx.accounts.user_token_account.amount;
         msg!("💰 Initial balance: {} lamports", initial_balance);
 
+        // Validate sub-swap count of every leg
+        for leg in &swap_legs {
+            require!(
+                !leg.sub_swaps.is_empty() && leg.sub_swaps.len() <= MAX_SUB_SWAPS_PER_LEG,
+                ArbitrageError::TooManySubSwaps
+            );
+        }
+
         // Validate sufficient balance
         require!(
-            initial_balance >= swap_legs[0].amount_in,
+            initial_balance >= swap_legs[0].amount_in()?,
             ArbitrageError::InsufficientBalance
         );
 
@@ -144,28 +173,32 @@ pub mod arbitrage_executor {
 
         for (idx, leg) in swap_legs.iter().enumerate() {
             msg!(
-                "📊 Leg {}/{}: {:?} on pool {}",
+                "📊 Leg {}/{}: {} sub-swap(s)",
                 idx + 1,
                 swap_legs.len(),
-                leg.protocol,
-                leg.pool_id
+                leg.sub_swaps.len()
             );
 
-            // Extract accounts for current leg from remaining_accounts
-            let accounts_end = account_cursor
-                .checked_add(leg.accounts_len as usize)
-                .ok_or(ArbitrageError::MathOverflow)?;
-            require!(
-            accounts_end <= ctx.remaining_accounts.len(),
-            ArbitrageError::InvalidAccountsCount
-        );
+            // Several CPIs feed the same hop: each one swaps its share of the input
+            for sub in &leg.sub_swaps {
+                msg!("   {:?} on pool {}", sub.protocol, sub.pool_id);
 
-            let leg_accounts = &ctx.remaining_accounts[account_cursor..accounts_end];
+                // Extract accounts for current sub-swap from remaining_accounts
+                let accounts_end = account_cursor
+                    .checked_add(sub.accounts_len as usize)
+                    .ok_or(ArbitrageError::MathOverflow)?;
+                require!(
+                    accounts_end <= ctx.remaining_accounts.len(),
+                    ArbitrageError::InvalidAccountsCount
+                );
 
-            // Execute swap via CPI
-            execute_swap_cpi(leg, leg_accounts, &ctx.accounts.user)?;
+                let sub_accounts = &ctx.remaining_accounts[account_cursor..accounts_end];
 
-            account_cursor = accounts_end;
+                // Execute swap via CPI
+                execute_swap_cpi(sub, sub_accounts, &ctx.accounts.user)?;
+
+                account_cursor = accounts_end;
+            }
 
             // Reload balance after intermediate step
             if idx < swap_legs.len() - 1 {
@@ -180,23 +213,27 @@ pub mod arbitrage_executor {
 
         msg!("💎 Final balance: {} lamports", final_balance);
 
-        // Calculate net profit
-        let profit = final_balance
-            .checked_sub(initial_balance)
-            .ok_or(ArbitrageError::MathOverflow)?;
+        // Signed delta for the log: a losing cycle is reported, not an overflow
+        let delta = final_balance as i128 - initial_balance as i128;
 
         msg!(
             "📈 Profit: {} lamports ({:.4}%)",
-            profit,
-            (profit as f64 / initial_balance as f64) * 100.0
+            delta,
+            (delta as f64 / initial_balance as f64) * 100.0
         );
 
-        // Verify minimum profit (CRITICAL: reverts if insufficient)
+        // Verify minimum profit (CRITICAL: reverts if insufficient, including a loss)
+        let required_balance = initial_balance
+            .checked_add(min_profit_lamports)
+            .ok_or(ArbitrageError::MathOverflow)?;
         require!(
-            profit >= min_profit_lamports,
+            final_balance >= required_balance,
             ArbitrageError::InsufficientProfit
         );
 
+        // final_balance >= initial_balance after the check above
+        let profit = final_balance - initial_balance;
+
         msg!("✅ ARBITRAGE SUCCESSFUL");
 
         // Emit event for monitoring
@@ -257,7 +294,7 @@ pub struct ArbitrageExecutedEvent {
 
 /// Execute swap via CPI to DEX
 fn execute_swap_cpi<'info>(
-    leg: &SwapLeg,
+    leg: &SubSwap,
     accounts: &'info [AccountInfo<'info>],
     user: &Signer<'info>,
 ) -> Result<()> {
@@ -265,47 +302,48 @@ fn execute_swap_cpi<'info>(
         DexProtocol::RaydiumAmmV4 => raydium_amm_swap(leg, accounts, user),
         DexProtocol::RaydiumCpmm => raydium_cpmm_swap(leg, accounts, user),
         DexProtocol::RaydiumClmm => raydium_clmm_swap(leg, accounts, user),
-        DexProtocol::MeteoraDlmm => meteora_dlmm_swap(leg, accounts, user), // Ошибка E0425 здесь
+        DexProtocol::MeteoraDlmm => meteora_dlmm_swap(leg, accounts, user),
     }
 }
 
-/// Raydium AMM V4 swap_base_in CPI
+/// Raydium AMM V4 swap_base_in instruction tag (legacy, with OpenBook market accounts)
+const RAYDIUM_AMM_SWAP_BASE_IN: u8 = 9;
+/// Raydium AMM V4 swap_base_in_v2 instruction tag (no market accounts)
+const RAYDIUM_AMM_SWAP_BASE_IN_V2: u8 = 16;
+
+/// Raydium AMM V4 swap CPI. The layout is chosen by account count:
+/// - [AMM program, 8 accounts]  — swap_base_in_v2
+/// - [AMM program, 18 accounts] — legacy swap_base_in with the OpenBook market
 fn raydium_amm_swap<'info>(
-    leg: &SwapLeg,
+    leg: &SubSwap,
     accounts: &'info [AccountInfo<'info>],
     _user: &Signer<'info>,
 ) -> Result<()> {
-    require!(accounts.len() == 18, ArbitrageError::InvalidAccountsCount);
+    let instruction = match accounts.len() {
+        9 => RAYDIUM_AMM_SWAP_BASE_IN_V2,
+        19 => RAYDIUM_AMM_SWAP_BASE_IN,
+        _ => return err!(ArbitrageError::InvalidAccountsCount),
+    };
 
-    // Raydium AMM V4 swap_base_in discriminator: 0x09
-    let mut data = vec![0x09];
+    let mut data = vec![instruction];
     data.extend_from_slice(&leg.amount_in.to_le_bytes());
     data.extend_from_slice(&leg.minimum_amount_out.to_le_bytes());
 
-    let account_metas: Vec<AccountMeta> = accounts
-        .iter()
-        .map(|acc| AccountMeta {
-            pubkey: *acc.key,
-            is_signer: acc.is_signer,
-            is_writable: acc.is_writable,
-        })
-        .collect();
-
     let ix = Instruction {
         program_id: RAYDIUM_AMM_V4,
-        accounts: account_metas,
+        accounts: dex_account_metas(accounts, RAYDIUM_AMM_V4)?,
         data,
     };
 
     invoke(&ix, accounts).map_err(|_| error!(ArbitrageError::CpiCallFailed))?;
 
-    msg!("   ✅ Raydium AMM swap executed");
+    msg!("   ✅ Raydium AMM swap executed (instruction {})", instruction);
     Ok(())
 }
 
 /// Raydium CPMM swap CPI
 fn raydium_cpmm_swap<'info>(
-    leg: &SwapLeg,
+    leg: &SubSwap,
     accounts: &'info [AccountInfo<'info>],
     _user: &Signer<'info>,
 ) -> Result<()> {
@@ -338,37 +376,29 @@ fn raydium_cpmm_swap<'info>(
     Ok(())
 }
 
-/// Raydium CLMM swap CPI
+/// Raydium CLMM swap_v2 CPI
+///
+/// Accounts: [CLMM program, 13 fixed swap_v2 accounts, optional tick array
+/// bitmap extension, tick arrays in traversal order]
 fn raydium_clmm_swap<'info>(
-    leg: &SwapLeg,
+    leg: &SubSwap,
     accounts: &'info [AccountInfo<'info>],
     _user: &Signer<'info>,
 ) -> Result<()> {
-    require!(accounts.len() >= 13, ArbitrageError::InvalidAccountsCount);
+    require!(accounts.len() >= 15, ArbitrageError::InvalidAccountsCount);
 
-    // ПРАВИЛЬНЫЙ дискриминатор для swap_v2 (sha256("global:swap_v2")[..8])
-    // УДАЛЕНЫ ЛИШНИЕ СИМВОЛЫ **
-    let mut data: Vec<u8> = vec![0xf3, 0x0c, 0x03, 0x33, 0x8f, 0x93, 0x18, 0x39];
+    // swap_v2 discriminator: sha256("global:swap_v2")[..8]
+    let mut data: Vec<u8> = vec![43, 4, 237, 11, 26, 201, 30, 98];
 
     // Параметры для swap_v2
     data.extend_from_slice(&leg.amount_in.to_le_bytes());          // amount: u64
     data.extend_from_slice(&leg.minimum_amount_out.to_le_bytes());  // other_amount_threshold: u64
     data.extend_from_slice(&(0_u128).to_le_bytes());              // sqrt_price_limit_x64: u128 (0 = no limit)
-    // data.extend_from_slice(&(1_u8).to_le_bytes());                // is_base_input: bool (true)
-
-    // УДАЛЕНЫ ЛИШНИЕ СИМВОЛЫ **
-    let account_metas: Vec<AccountMeta> = accounts
-        .iter()
-        .map(|acc| AccountMeta {
-            pubkey: *acc.key,
-            is_signer: acc.is_signer,
-            is_writable: acc.is_writable,
-        })
-        .collect();
+    data.push(1);                                                  // is_base_input: bool (exact input)
 
     let ix = Instruction {
         program_id: RAYDIUM_CLMM,
-        accounts: account_metas,
+        accounts: dex_account_metas(accounts, RAYDIUM_CLMM)?,
         data,
     };
 
@@ -378,32 +408,44 @@ fn raydium_clmm_swap<'info>(
     Ok(())
 }
 
-// **(3) ДОБАВЛЕНА ОТСУТСТВУЮЩАЯ ФУНКЦИЯ meteora_dlmm_swap (ИСПРАВЛЕНИЕ E0425)**
-/// Meteora DLMM swap CPI
-fn meteora_dlmm_swap<'info>(
-    leg: &SwapLeg,
-    accounts: &'info [AccountInfo<'info>],
-    _user: &Signer<'info>,
-) -> Result<()> {
-    require!(accounts.len() >= 10, ArbitrageError::InvalidAccountsCount);
-
-    // Meteora DLMM swap discriminator
-    let mut data = vec![0x13, 0x98, 0xa2, 0x5f, 0x5e, 0x8f, 0x2d, 0x7c];
-    data.extend_from_slice(&leg.amount_in.to_le_bytes());
-    data.extend_from_slice(&leg.minimum_amount_out.to_le_bytes());
+/// Account metas for a DEX instruction. accounts[0] is the DEX program itself:
+/// `invoke` needs its AccountInfo, but it is not an account of the instruction
+fn dex_account_metas(accounts: &[AccountInfo], program_id: Pubkey) -> Result<Vec<AccountMeta>> {
+    let (program, instruction_accounts) = accounts
+        .split_first()
+        .ok_or(ArbitrageError::InvalidAccountsCount)?;
+    require_keys_eq!(*program.key, program_id, ArbitrageError::InvalidDexProtocol);
 
-    let account_metas: Vec<AccountMeta> = accounts
+    Ok(instruction_accounts
         .iter()
         .map(|acc| AccountMeta {
             pubkey: *acc.key,
             is_signer: acc.is_signer,
             is_writable: acc.is_writable,
         })
-        .collect();
+        .collect())
+}
+
+/// Meteora DLMM swap CPI
+///
+/// Accounts: [DLMM program, 15 fixed swap accounts, bin arrays in traversal
+/// order]. Absent optional accounts (bitmap extension, host fee) are passed
+/// as the DLMM program id
+fn meteora_dlmm_swap<'info>(
+    leg: &SubSwap,
+    accounts: &'info [AccountInfo<'info>],
+    _user: &Signer<'info>,
+) -> Result<()> {
+    require!(accounts.len() >= 17, ArbitrageError::InvalidAccountsCount);
+
+    // swap discriminator: sha256("global:swap")[..8]
+    let mut data: Vec<u8> = vec![248, 198, 158, 145, 225, 117, 135, 200];
+    data.extend_from_slice(&leg.amount_in.to_le_bytes());          // amount_in: u64
+    data.extend_from_slice(&leg.minimum_amount_out.to_le_bytes());  // min_amount_out: u64
 
     let ix = Instruction {
         program_id: METEORA_DLMM,
-        accounts: account_metas,
+        accounts: dex_account_metas(accounts, METEORA_DLMM)?,
         data,
     };
 